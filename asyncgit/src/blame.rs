@@ -1,7 +1,8 @@
 use crate::{
+	error::Error,
 	error::Result,
 	hash,
-	sync::{self, CommitId, FileBlame, RepoPath},
+	sync::{self, BlameProgress, CommitId, FileBlame, RepoPath},
 	AsyncGitNotification,
 };
 use crossbeam_channel::Sender;
@@ -34,6 +35,7 @@ struct LastResult<P, R> {
 pub struct AsyncBlame {
 	current: Arc<Mutex<Request<u64, FileBlame>>>,
 	last: Arc<Mutex<Option<LastResult<BlameParams, FileBlame>>>>,
+	progress: Arc<Mutex<Option<BlameProgress>>>,
 	sender: Sender<AsyncGitNotification>,
 	pending: Arc<AtomicUsize>,
 	repo: RepoPath,
@@ -49,6 +51,7 @@ impl AsyncBlame {
 			repo,
 			current: Arc::new(Mutex::new(Request(0, None))),
 			last: Arc::new(Mutex::new(None)),
+			progress: Arc::new(Mutex::new(None)),
 			sender: sender.clone(),
 			pending: Arc::new(AtomicUsize::new(0)),
 		}
@@ -79,6 +82,16 @@ impl AsyncBlame {
 		self.pending.load(Ordering::Relaxed) > 0
 	}
 
+	/// progress of the currently running blame
+	pub fn progress(&self) -> Result<Option<BlameProgress>> {
+		Ok(*self.progress.lock()?)
+	}
+
+	/// stops the currently running blame at the next chunk of lines
+	pub fn cancel(&mut self) -> Result<()> {
+		self.clear_current()
+	}
+
 	///
 	pub fn request(
 		&mut self,
@@ -101,6 +114,7 @@ impl AsyncBlame {
 
 		let arc_current = Arc::clone(&self.current);
 		let arc_last = Arc::clone(&self.last);
+		let arc_progress = Arc::clone(&self.progress);
 		let sender = self.sender.clone();
 		let arc_pending = Arc::clone(&self.pending);
 		let repo = self.repo.clone();
@@ -113,10 +127,17 @@ impl AsyncBlame {
 				params,
 				&arc_last,
 				&arc_current,
+				&arc_progress,
+				&sender,
 				hash,
 			);
 
+			if let Ok(mut progress) = arc_progress.lock() {
+				*progress = None;
+			}
+
 			let notify = match notify {
+				Err(Error::Canceled) => false,
 				Err(err) => {
 					log::error!("get_blame_helper error: {}", err);
 					true
@@ -145,12 +166,29 @@ impl AsyncBlame {
 			Mutex<Option<LastResult<BlameParams, FileBlame>>>,
 		>,
 		arc_current: &Arc<Mutex<Request<u64, FileBlame>>>,
+		arc_progress: &Arc<Mutex<Option<BlameProgress>>>,
+		sender: &Sender<AsyncGitNotification>,
 		hash: u64,
 	) -> Result<bool> {
-		let file_blame = sync::blame::blame_file(
+		let file_blame = sync::blame::blame_file_with_progress(
 			repo_path,
 			&params.file_path,
 			params.commit_id,
+			|progress| {
+				let is_current = arc_current
+					.lock()
+					.map_or(false, |current| current.0 == hash);
+
+				if is_current {
+					if let Ok(mut last_progress) = arc_progress.lock()
+					{
+						*last_progress = Some(progress);
+					}
+					sender.send(AsyncGitNotification::Blame).ok();
+				}
+
+				is_current
+			},
 		)?;
 
 		let mut notify = false;
@@ -0,0 +1,302 @@
+use crate::{
+	diff::{DiffParams, DiffType},
+	error::Result,
+	hash,
+	sync::{repo, utils::work_dir, RepoPath},
+	FileDiff,
+};
+use git2::{FileMode, ObjectType, Oid, Repository};
+use std::{collections::VecDeque, fs, path::Path};
+
+/// identifies a diff together with the state of the objects it was
+/// computed from, see [`DiffCache::key`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DiffCacheKey {
+	params: u64,
+	path: String,
+	/// id and mode of the file in each of the diffed trees
+	state: Vec<Option<(Oid, u32)>>,
+}
+
+/// least recently used cache of `FileDiff`s
+///
+/// Diffs involving the index or workdir are keyed by the ids and modes
+/// of the file in `HEAD`, the index and the workdir (the checked out
+/// commit for submodules), so a changed file simply misses the cache
+/// and pushes out its outdated entry.
+pub struct DiffCache {
+	entries: VecDeque<(DiffCacheKey, FileDiff)>,
+	capacity: usize,
+}
+
+/// id and mode of a file in the workdir, for a submodule the commit
+/// it has checked out
+fn workdir_state(path: &Path) -> Option<(Oid, u32)> {
+	let meta = fs::symlink_metadata(path).ok()?;
+
+	if meta.is_dir() {
+		let id =
+			Repository::open(path).ok()?.head().ok()?.target()?;
+
+		return Some((id, u32::from(FileMode::Commit)));
+	}
+
+	let id = Oid::hash_file(ObjectType::Blob, path).ok()?;
+
+	#[cfg(unix)]
+	let mode = {
+		use std::os::unix::fs::PermissionsExt;
+		meta.permissions().mode()
+	};
+	#[cfg(not(unix))]
+	let mode = 0;
+
+	Some((id, mode))
+}
+
+impl DiffCache {
+	///
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			entries: VecDeque::with_capacity(capacity),
+			capacity,
+		}
+	}
+
+	/// key of the diff `params` describes in its current state
+	pub fn key(
+		repo_path: &RepoPath,
+		params: &DiffParams,
+	) -> Result<DiffCacheKey> {
+		let state = match params.diff_type {
+			// commits never change
+			DiffType::Commit(_) | DiffType::Commits(_) => Vec::new(),
+			DiffType::Stage | DiffType::WorkDir => {
+				let repo = repo(repo_path)?;
+				let path = Path::new(&params.path);
+
+				let index_state = repo
+					.index()?
+					.get_path(path, 0)
+					.map(|entry| (entry.id, entry.mode));
+
+				if params.diff_type == DiffType::Stage {
+					let head_state = repo
+						.head()
+						.and_then(|head| head.peel_to_tree())
+						.and_then(|tree| tree.get_path(path))
+						.ok()
+						.and_then(|entry| {
+							Some((
+								entry.id(),
+								u32::try_from(entry.filemode())
+									.ok()?,
+							))
+						});
+
+					vec![head_state, index_state]
+				} else {
+					vec![
+						index_state,
+						workdir_state(&work_dir(&repo)?.join(path)),
+					]
+				}
+			}
+		};
+
+		Ok(DiffCacheKey {
+			params: hash(params),
+			path: params.path.clone(),
+			state,
+		})
+	}
+
+	/// cached diff of `key`, marking it as recently used
+	pub fn get(&mut self, key: &DiffCacheKey) -> Option<FileDiff> {
+		let idx = self.entries.iter().position(|(k, _)| k == key)?;
+		let entry = self.entries.remove(idx)?;
+		let diff = entry.1.clone();
+		self.entries.push_front(entry);
+
+		Some(diff)
+	}
+
+	/// adds `diff` replacing outdated entries of the same diff and
+	/// evicting the least recently used one if full
+	pub fn insert(&mut self, key: DiffCacheKey, diff: FileDiff) {
+		if self.capacity == 0 {
+			return;
+		}
+
+		self.entries.retain(|(k, _)| {
+			k.params != key.params || k.path != key.path
+		});
+
+		if self.entries.len() >= self.capacity {
+			self.entries.pop_back();
+		}
+
+		self.entries.push_front((key, diff));
+	}
+
+	///
+	pub fn clear(&mut self) {
+		self.entries.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		diff::{get_diff, DiffOptions},
+		stage_add_file,
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+
+	fn params(diff_type: DiffType) -> DiffParams {
+		DiffParams {
+			path: String::from("foo.txt"),
+			diff_type,
+			options: DiffOptions::default(),
+		}
+	}
+
+	#[test]
+	fn test_hit_and_miss_after_change() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "a\n", "c1");
+		repo_write_file(&repo, "foo.txt", "b\n")?;
+
+		let mut cache = DiffCache::new(10);
+		let workdir = params(DiffType::WorkDir);
+
+		let key = DiffCache::key(repo_path, &workdir)?;
+		assert!(cache.get(&key).is_none());
+
+		cache.insert(
+			key,
+			get_diff(repo_path, "foo.txt", false, None)?,
+		);
+
+		let key = DiffCache::key(repo_path, &workdir)?;
+		assert_eq!(cache.get(&key).map(|diff| diff.lines), Some(3));
+
+		repo_write_file(&repo, "foo.txt", "c\n")?;
+
+		let key = DiffCache::key(repo_path, &workdir)?;
+		assert!(cache.get(&key).is_none());
+
+		let stage = params(DiffType::Stage);
+		let key = DiffCache::key(repo_path, &stage)?;
+		cache
+			.insert(key, get_diff(repo_path, "foo.txt", true, None)?);
+		assert!(cache
+			.get(&DiffCache::key(repo_path, &stage)?)
+			.is_some());
+
+		stage_add_file(repo_path, Path::new("foo.txt"))?;
+
+		assert!(cache
+			.get(&DiffCache::key(repo_path, &stage)?)
+			.is_none());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_evicts_least_recently_used() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = write_commit_file(&repo, "foo.txt", "a\n", "c1");
+		let c2 = write_commit_file(&repo, "foo.txt", "b\n", "c2");
+		let c3 = write_commit_file(&repo, "foo.txt", "c\n", "c3");
+
+		let mut cache = DiffCache::new(2);
+		let keys = [c1, c2, c3]
+			.into_iter()
+			.map(|id| {
+				DiffCache::key(
+					repo_path,
+					&params(DiffType::Commit(id)),
+				)
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		cache.insert(keys[0].clone(), FileDiff::default());
+		cache.insert(keys[1].clone(), FileDiff::default());
+		assert!(cache.get(&keys[0]).is_some());
+
+		cache.insert(keys[2].clone(), FileDiff::default());
+
+		assert!(cache.get(&keys[0]).is_some());
+		assert!(cache.get(&keys[1]).is_none());
+		assert!(cache.get(&keys[2]).is_some());
+
+		Ok(())
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_mode_change_misses() -> Result<()> {
+		use std::os::unix::fs::PermissionsExt;
+
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "a\n", "c1");
+
+		let workdir = params(DiffType::WorkDir);
+		let before = DiffCache::key(repo_path, &workdir)?;
+
+		fs::set_permissions(
+			root.join("foo.txt"),
+			fs::Permissions::from_mode(0o755),
+		)?;
+
+		assert_ne!(DiffCache::key(repo_path, &workdir)?, before);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_submodule_head_change_misses() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let sub = Repository::init(root.join("sub"))?;
+		{
+			let mut config = sub.config()?;
+			config.set_str("user.name", "name")?;
+			config.set_str("user.email", "email")?;
+		}
+		write_commit_file(&sub, "a.txt", "a", "s1");
+		stage_add_file(repo_path, Path::new("sub"))?;
+
+		let workdir = DiffParams {
+			path: String::from("sub"),
+			diff_type: DiffType::WorkDir,
+			options: DiffOptions::default(),
+		};
+		let before = DiffCache::key(repo_path, &workdir)?;
+		assert_eq!(DiffCache::key(repo_path, &workdir)?, before);
+
+		write_commit_file(&sub, "a.txt", "b", "s2");
+
+		assert_ne!(DiffCache::key(repo_path, &workdir)?, before);
+
+		Ok(())
+	}
+}
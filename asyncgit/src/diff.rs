@@ -1,5 +1,6 @@
 use crate::{
-	error::Result,
+	cached::DiffCache,
+	error::{Error, Result},
 	hash,
 	sync::{self, diff::DiffOptions, CommitId, RepoPath},
 	AsyncGitNotification, FileDiff,
@@ -45,10 +46,14 @@ struct LastResult<P, R> {
 	result: R,
 }
 
+/// diffs kept around for files that did not change since
+const DIFF_CACHE_SIZE: usize = 20;
+
 ///
 pub struct AsyncDiff {
 	current: Arc<Mutex<Request<u64, FileDiff>>>,
 	last: Arc<Mutex<Option<LastResult<DiffParams, FileDiff>>>>,
+	cache: Arc<Mutex<DiffCache>>,
 	sender: Sender<AsyncGitNotification>,
 	pending: Arc<AtomicUsize>,
 	repo: RepoPath,
@@ -64,6 +69,9 @@ impl AsyncDiff {
 			repo,
 			current: Arc::new(Mutex::new(Request(0, None))),
 			last: Arc::new(Mutex::new(None)),
+			cache: Arc::new(Mutex::new(DiffCache::new(
+				DIFF_CACHE_SIZE,
+			))),
 			sender: sender.clone(),
 			pending: Arc::new(AtomicUsize::new(0)),
 		}
@@ -90,6 +98,12 @@ impl AsyncDiff {
 		self.pending.load(Ordering::Relaxed) > 0
 	}
 
+	/// stops the currently running diff at its next hunk, a new
+	/// `request` cancels a running one the same way
+	pub fn cancel(&mut self) -> Result<()> {
+		self.clear_current()
+	}
+
 	///
 	pub fn request(
 		&mut self,
@@ -112,6 +126,7 @@ impl AsyncDiff {
 
 		let arc_current = Arc::clone(&self.current);
 		let arc_last = Arc::clone(&self.last);
+		let arc_cache = Arc::clone(&self.cache);
 		let sender = self.sender.clone();
 		let arc_pending = Arc::clone(&self.pending);
 		let repo = self.repo.clone();
@@ -124,10 +139,12 @@ impl AsyncDiff {
 				params,
 				&arc_last,
 				&arc_current,
+				&arc_cache,
 				hash,
 			);
 
 			let notify = match notify {
+				Err(Error::Canceled) => false,
 				Err(err) => {
 					log::error!("get_diff_helper error: {}", err);
 					true
@@ -156,35 +173,77 @@ impl AsyncDiff {
 			Mutex<Option<LastResult<DiffParams, FileDiff>>>,
 		>,
 		arc_current: &Arc<Mutex<Request<u64, FileDiff>>>,
+		arc_cache: &Arc<Mutex<DiffCache>>,
 		hash: u64,
 	) -> Result<bool> {
+		let key = DiffCache::key(repo_path, &params)?;
+
+		let cached = arc_cache.lock()?.get(&key);
+		if let Some(res) = cached {
+			return Self::set_result(
+				res,
+				params,
+				arc_last,
+				arc_current,
+				hash,
+			);
+		}
+
+		let is_canceled = || {
+			arc_current
+				.lock()
+				.map_or(true, |current| current.0 != hash)
+		};
+
 		let res = match params.diff_type {
-			DiffType::Stage => sync::diff::get_diff(
+			DiffType::Stage => sync::diff::get_diff_cancelable(
 				repo_path,
 				&params.path,
 				true,
 				Some(params.options),
+				&is_canceled,
 			)?,
-			DiffType::WorkDir => sync::diff::get_diff(
+			DiffType::WorkDir => sync::diff::get_diff_cancelable(
 				repo_path,
 				&params.path,
 				false,
 				Some(params.options),
+				&is_canceled,
 			)?,
-			DiffType::Commit(id) => sync::diff::get_diff_commit(
-				repo_path,
-				id,
-				params.path.clone(),
-				Some(params.options),
-			)?,
-			DiffType::Commits(ids) => sync::diff::get_diff_commits(
-				repo_path,
-				ids,
-				params.path.clone(),
-				Some(params.options),
-			)?,
+			DiffType::Commit(id) => {
+				sync::diff::get_diff_commit_cancelable(
+					repo_path,
+					id,
+					params.path.clone(),
+					Some(params.options),
+					&is_canceled,
+				)?
+			}
+			DiffType::Commits(ids) => {
+				sync::diff::get_diff_commits_cancelable(
+					repo_path,
+					ids,
+					params.path.clone(),
+					Some(params.options),
+					&is_canceled,
+				)?
+			}
 		};
 
+		arc_cache.lock()?.insert(key, res.clone());
+
+		Self::set_result(res, params, arc_last, arc_current, hash)
+	}
+
+	fn set_result(
+		res: FileDiff,
+		params: DiffParams,
+		arc_last: &Arc<
+			Mutex<Option<LastResult<DiffParams, FileDiff>>>,
+		>,
+		arc_current: &Arc<Mutex<Request<u64, FileDiff>>>,
+		hash: u64,
+	) -> Result<bool> {
 		let mut notify = false;
 		{
 			let mut current = arc_current.lock()?;
@@ -21,6 +21,20 @@ pub enum Error {
 	#[error("git: conflict during rebase")]
 	RebaseConflict,
 
+	///
+	#[error(
+		"git: unresolved conflicts, stage their resolution first"
+	)]
+	UnresolvedConflicts,
+
+	///
+	#[error("git: no interactive rebase in progress")]
+	NoInteractiveRebase,
+
+	///
+	#[error("git: only the HEAD commit can be amended, use an interactive rebase for older commits")]
+	NotHeadCommit,
+
 	///
 	#[error("git: remote url not found")]
 	UnknownRemote,
@@ -37,6 +51,10 @@ pub enum Error {
 	#[error("git: uncommitted changes")]
 	UncommittedChanges,
 
+	///
+	#[error("git: uncommitted changes in: {}", .0.join(", "))]
+	UncommittedChangesIn(Vec<String>),
+
 	///
 	#[error("git: can\u{2019}t run blame on a binary file")]
 	NoBlameOnBinaryFile,
@@ -84,6 +102,34 @@ pub enum Error {
 	///
 	#[error("not on a branch")]
 	NoBranch,
+
+	///
+	#[error("canceled")]
+	Canceled,
+
+	///
+	#[error("git: no difftool configured, set diff.tool and difftool.<tool>.cmd")]
+	NoDifftool,
+
+	///
+	#[error("git: no mergetool configured, set merge.tool and mergetool.<tool>.cmd")]
+	NoMergetool,
+
+	///
+	#[error(
+		"git: shallow clones are not supported by the linked libgit2"
+	)]
+	ShallowCloneUnsupported,
+
+	///
+	#[error("git: signing the commit failed: {0}")]
+	Sign(String),
+
+	///
+	#[error(
+		"git: applying the selected lines does not match the diff"
+	)]
+	PatchApplyMismatch,
 }
 
 ///
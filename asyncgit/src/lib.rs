@@ -64,7 +64,9 @@ pub use crate::{
 	status::{AsyncStatus, StatusParams},
 	sync::{
 		diff::{DiffLine, DiffLineType, FileDiff},
-		remotes::push::PushType,
+		remotes::push::{
+			PushRefStatus, PushRefUpdate, PushResult, PushType,
+		},
 		status::{StatusItem, StatusItemType},
 	},
 	tags::AsyncTags,
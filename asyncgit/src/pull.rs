@@ -22,6 +22,8 @@ pub struct FetchRequest {
 	pub branch: String,
 	///
 	pub basic_credential: Option<BasicAuthCredential>,
+	/// remove remote-tracking refs deleted upstream
+	pub prune: bool,
 }
 
 //TODO: since this is empty we can go with a simple AtomicBool to mark that we are fetching or not
@@ -102,6 +104,7 @@ impl AsyncPull {
 				&params.branch,
 				params.basic_credential,
 				Some(progress_sender.clone()),
+				params.prune,
 			);
 
 			progress_sender
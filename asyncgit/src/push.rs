@@ -113,7 +113,8 @@ impl AsyncPush {
 				params.delete,
 				params.basic_credential.clone(),
 				Some(progress_sender.clone()),
-			);
+			)
+			.and_then(|result| result.check());
 
 			progress_sender
 				.send(ProgressNotification::Done)
@@ -35,6 +35,12 @@ pub struct RemoteProgress {
 	pub state: RemoteProgressState,
 	///
 	pub progress: ProgressPercent,
+	/// objects processed so far
+	pub current: usize,
+	/// objects to process
+	pub total: usize,
+	/// bytes transferred so far, `0` if not transferring
+	pub bytes: usize,
 }
 
 impl RemoteProgress {
@@ -47,6 +53,9 @@ impl RemoteProgress {
 		Self {
 			state,
 			progress: ProgressPercent::new(current, total),
+			current,
+			total,
+			bytes: 0,
 		}
 	}
 
@@ -128,21 +137,27 @@ impl From<ProgressNotification> for RemoteProgress {
 			ProgressNotification::PushTransfer {
 				current,
 				total,
-				..
-			} => Self::new(
-				RemoteProgressState::Pushing,
-				current,
-				total,
-			),
+				bytes,
+			} => Self {
+				bytes,
+				..Self::new(
+					RemoteProgressState::Pushing,
+					current,
+					total,
+				)
+			},
 			ProgressNotification::Transfer {
 				objects,
 				total_objects,
-				..
-			} => Self::new(
-				RemoteProgressState::Transfer,
-				objects,
-				total_objects,
-			),
+				received_bytes,
+			} => Self {
+				bytes: received_bytes,
+				..Self::new(
+					RemoteProgressState::Transfer,
+					objects,
+					total_objects,
+				)
+			},
 			_ => Self::new(RemoteProgressState::Done, 1, 1),
 		}
 	}
@@ -0,0 +1,575 @@
+//! sync git api for bisecting, compatible with `git bisect`
+
+use super::{
+	branch::{checkout_branch, checkout_commit},
+	repository::repo,
+	CommitId, RepoPath,
+};
+use crate::error::{Error, Result};
+use git2::{Oid, Repository, Sort};
+use scopetime::scope_time;
+use std::{
+	collections::{HashMap, HashSet},
+	fs,
+	io::Write,
+	path::PathBuf,
+};
+
+const BISECT_START: &str = "BISECT_START";
+const BISECT_LOG: &str = "BISECT_LOG";
+const BISECT_TERMS: &str = "BISECT_TERMS";
+const BISECT_NAMES: &str = "BISECT_NAMES";
+const BISECT_EXPECTED_REV: &str = "BISECT_EXPECTED_REV";
+const BISECT_ANCESTORS_OK: &str = "BISECT_ANCESTORS_OK";
+
+const REF_BAD: &str = "refs/bisect/bad";
+const REF_GOOD_PREFIX: &str = "refs/bisect/good-";
+const REF_SKIP_PREFIX: &str = "refs/bisect/skip-";
+
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BisectStatus {
+	///
+	Good,
+	///
+	Bad,
+	///
+	Skip,
+}
+
+impl BisectStatus {
+	const fn term(self) -> &'static str {
+		match self {
+			Self::Good => "good",
+			Self::Bad => "bad",
+			Self::Skip => "skip",
+		}
+	}
+}
+
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BisectState {
+	/// commit to test next, `None` once bisecting is done
+	pub candidate: Option<CommitId>,
+	/// the first bad commit once it was found
+	pub first_bad: Option<CommitId>,
+	/// revisions left to test after `candidate`
+	pub remaining: usize,
+	/// rough number of steps left after `candidate`
+	pub steps: usize,
+}
+
+/// starts bisecting between `bad` and `good` and checks out the first
+/// commit to test
+pub fn bisect_start(
+	repo_path: &RepoPath,
+	bad: CommitId,
+	good: &[CommitId],
+) -> Result<BisectState> {
+	scope_time!("bisect_start");
+
+	let repo = repo(repo_path)?;
+
+	if bisect_file(&repo, BISECT_START).exists() {
+		return Err(Error::Generic(
+			"bisect already in progress".into(),
+		));
+	}
+
+	let head = repo.head()?;
+	let start = if head.is_branch() {
+		head.shorthand().unwrap_or_default().to_string()
+	} else {
+		head.peel_to_commit()?.id().to_string()
+	};
+
+	fs::write(
+		bisect_file(&repo, BISECT_START),
+		format!("{start}\n"),
+	)?;
+	fs::write(bisect_file(&repo, BISECT_TERMS), "bad\ngood\n")?;
+	fs::write(bisect_file(&repo, BISECT_NAMES), "\n")?;
+	fs::write(bisect_file(&repo, BISECT_LOG), "")?;
+
+	mark_commit(&repo, bad, BisectStatus::Bad)?;
+	for id in good {
+		mark_commit(&repo, *id, BisectStatus::Good)?;
+	}
+
+	let args = std::iter::once(bad)
+		.chain(good.iter().copied())
+		.map(|id| format!("'{}'", id.to_string()))
+		.collect::<Vec<_>>()
+		.join(" ");
+	append_log(&repo, &format!("git bisect start {args}\n"))?;
+
+	next_step(&repo, repo_path)
+}
+
+/// marks the currently checked out commit and checks out the next
+/// commit to test
+pub fn bisect_mark(
+	repo_path: &RepoPath,
+	status: BisectStatus,
+) -> Result<BisectState> {
+	scope_time!("bisect_mark");
+
+	let repo = repo(repo_path)?;
+
+	if !bisect_file(&repo, BISECT_START).exists() {
+		return Err(Error::Generic("not bisecting".into()));
+	}
+
+	let head = CommitId::new(repo.head()?.peel_to_commit()?.id());
+
+	mark_commit(&repo, head, status)?;
+	append_log(
+		&repo,
+		&format!(
+			"git bisect {} {}\n",
+			status.term(),
+			head.to_string()
+		),
+	)?;
+
+	next_step(&repo, repo_path)
+}
+
+/// returns `None` if no bisect is in progress
+pub fn bisect_state(
+	repo_path: &RepoPath,
+) -> Result<Option<BisectState>> {
+	scope_time!("bisect_state");
+
+	let repo = repo(repo_path)?;
+
+	if !bisect_file(&repo, BISECT_START).exists() {
+		return Ok(None);
+	}
+
+	Ok(Some(find_bisection(&repo)?))
+}
+
+/// stops bisecting and checks out the branch or commit bisecting
+/// started from
+pub fn bisect_reset(repo_path: &RepoPath) -> Result<()> {
+	scope_time!("bisect_reset");
+
+	let repo = repo(repo_path)?;
+
+	let start_file = bisect_file(&repo, BISECT_START);
+	if !start_file.exists() {
+		return Err(Error::Generic("not bisecting".into()));
+	}
+
+	let start = fs::read_to_string(&start_file)?.trim().to_string();
+
+	if let Ok(oid) = Oid::from_str(&start) {
+		if repo.find_commit(oid).is_ok() {
+			checkout_commit(repo_path, oid.into(), false)?;
+		} else {
+			checkout_branch(
+				repo_path,
+				&format!("refs/heads/{start}"),
+			)?;
+		}
+	} else {
+		checkout_branch(repo_path, &format!("refs/heads/{start}"))?;
+	}
+
+	for reference in repo.references_glob("refs/bisect/*")? {
+		reference?.delete()?;
+	}
+
+	for file in [
+		BISECT_START,
+		BISECT_LOG,
+		BISECT_TERMS,
+		BISECT_NAMES,
+		BISECT_EXPECTED_REV,
+		BISECT_ANCESTORS_OK,
+	] {
+		let path = bisect_file(&repo, file);
+		if path.exists() {
+			fs::remove_file(path)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn bisect_file(repo: &Repository, name: &str) -> PathBuf {
+	repo.path().join(name)
+}
+
+fn append_log(repo: &Repository, line: &str) -> Result<()> {
+	let mut file = fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(bisect_file(repo, BISECT_LOG))?;
+
+	file.write_all(line.as_bytes())?;
+
+	Ok(())
+}
+
+fn log_commit(
+	repo: &Repository,
+	label: &str,
+	id: CommitId,
+) -> Result<()> {
+	let commit = repo.find_commit(id.into())?;
+
+	append_log(
+		repo,
+		&format!(
+			"# {label}: [{}] {}\n",
+			id.to_string(),
+			commit.summary().unwrap_or_default()
+		),
+	)
+}
+
+fn mark_commit(
+	repo: &Repository,
+	id: CommitId,
+	status: BisectStatus,
+) -> Result<()> {
+	let name = match status {
+		BisectStatus::Bad => REF_BAD.to_string(),
+		BisectStatus::Good => {
+			format!("{REF_GOOD_PREFIX}{}", id.to_string())
+		}
+		BisectStatus::Skip => {
+			format!("{REF_SKIP_PREFIX}{}", id.to_string())
+		}
+	};
+
+	repo.reference(&name, id.into(), true, "bisect")?;
+
+	log_commit(repo, status.term(), id)
+}
+
+fn refs_with_prefix(
+	repo: &Repository,
+	prefix: &str,
+) -> Result<Vec<Oid>> {
+	let mut oids = Vec::new();
+
+	for reference in repo.references_glob(&format!("{prefix}*"))? {
+		if let Some(oid) = reference?.target() {
+			oids.push(oid);
+		}
+	}
+
+	Ok(oids)
+}
+
+/// `git bisect` estimate of the steps needed to bisect `all`
+/// revisions after the next one
+const fn estimate_steps(all: usize) -> usize {
+	if all < 3 {
+		return 0;
+	}
+
+	let n = (usize::BITS - 1 - all.leading_zeros()) as usize;
+	let e = 1 << n;
+	let x = all - e;
+
+	if e < 3 * x {
+		n
+	} else {
+		n - 1
+	}
+}
+
+/// number of `commits` reachable from each of them (itself included),
+/// `commits` has to be sorted parents first
+///
+/// Like `git rev-list --bisect` a commit with a single parent simply
+/// adds one to the weight of that parent, only merges need a walk.
+fn commit_weights(
+	repo: &Repository,
+	commits: &[Oid],
+) -> Result<Vec<usize>> {
+	let index: HashMap<Oid, usize> = commits
+		.iter()
+		.enumerate()
+		.map(|(idx, oid)| (*oid, idx))
+		.collect();
+
+	let mut parents = Vec::with_capacity(commits.len());
+	for oid in commits {
+		let commit = repo.find_commit(*oid)?;
+		parents.push(
+			commit
+				.parent_ids()
+				.filter_map(|parent| index.get(&parent).copied())
+				.collect::<Vec<_>>(),
+		);
+	}
+
+	let mut weights: Vec<usize> = Vec::with_capacity(commits.len());
+	for idx in 0..commits.len() {
+		let weight = match parents[idx].as_slice() {
+			[] => 1,
+			[parent] => weights[*parent] + 1,
+			_ => count_reachable(idx, &parents),
+		};
+		weights.push(weight);
+	}
+
+	Ok(weights)
+}
+
+fn count_reachable(from: usize, parents: &[Vec<usize>]) -> usize {
+	let mut seen = vec![false; parents.len()];
+	let mut stack = vec![from];
+	let mut count = 0;
+
+	while let Some(idx) = stack.pop() {
+		if std::mem::replace(&mut seen[idx], true) {
+			continue;
+		}
+		count += 1;
+		stack.extend(&parents[idx]);
+	}
+
+	count
+}
+
+fn find_bisection(repo: &Repository) -> Result<BisectState> {
+	let bad = repo.refname_to_id(REF_BAD)?;
+	let goods = refs_with_prefix(repo, REF_GOOD_PREFIX)?;
+	let skips: HashSet<Oid> =
+		refs_with_prefix(repo, REF_SKIP_PREFIX)?
+			.into_iter()
+			.collect();
+
+	// oldest first, like `git bisect` looks for the halfway commit
+	let mut walk = repo.revwalk()?;
+	walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+	walk.push(bad)?;
+	for good in &goods {
+		walk.hide(*good)?;
+	}
+	let commits: Vec<Oid> =
+		walk.collect::<std::result::Result<_, _>>()?;
+	let all = commits.len();
+
+	if all <= 1 {
+		return Ok(BisectState {
+			first_bad: Some(bad.into()),
+			..BisectState::default()
+		});
+	}
+
+	let weights = commit_weights(repo, &commits)?;
+	let mut best: Option<(Oid, usize, usize)> = None;
+
+	for (oid, weight) in commits
+		.iter()
+		.zip(weights)
+		.filter(|(oid, _)| !skips.contains(oid))
+	{
+		let distance = weight.min(all - weight);
+
+		if best.map_or(true, |(_, best_distance, _)| {
+			distance > best_distance
+		}) {
+			best = Some((*oid, distance, weight));
+		}
+	}
+
+	Ok(match best {
+		Some((oid, distance, weight)) if distance > 0 => {
+			BisectState {
+				candidate: Some(oid.into()),
+				first_bad: None,
+				remaining: all - weight - 1,
+				steps: estimate_steps(all),
+			}
+		}
+		// everything but the bad commit was skipped
+		_ => BisectState::default(),
+	})
+}
+
+fn next_step(
+	repo: &Repository,
+	repo_path: &RepoPath,
+) -> Result<BisectState> {
+	let state = find_bisection(repo)?;
+
+	if let Some(candidate) = state.candidate {
+		let head = repo.head()?.peel_to_commit()?.id();
+
+		if head != candidate.into() {
+			checkout_commit(repo_path, candidate, false)?;
+		}
+
+		fs::write(
+			bisect_file(repo, BISECT_EXPECTED_REV),
+			format!("{}\n", candidate.to_string()),
+		)?;
+	} else if let Some(first_bad) = state.first_bad {
+		log_commit(repo, "first bad commit", first_bad)?;
+	}
+
+	Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		tests::{repo_init, write_commit_file},
+		utils::get_head_repo,
+	};
+
+	fn linear_history(repo: &Repository) -> Vec<CommitId> {
+		(1..=10)
+			.map(|i| {
+				write_commit_file(
+					repo,
+					"file.txt",
+					&format!("{i}"),
+					&format!("c{i}"),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_bisect_midpoint() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c = linear_history(&repo);
+
+		assert_eq!(bisect_state(repo_path)?, None);
+
+		let state = bisect_start(repo_path, c[9], &[c[0]])?;
+
+		assert_eq!(state.candidate, Some(c[4]));
+		assert_eq!(state.remaining, 4);
+		assert_eq!(state.steps, 2);
+		assert_eq!(get_head_repo(&repo)?, c[4]);
+		assert_eq!(bisect_state(repo_path)?, Some(state));
+		assert!(repo.find_reference(REF_BAD).is_ok());
+		assert!(repo
+			.find_reference(&format!(
+				"{REF_GOOD_PREFIX}{}",
+				c[0].to_string()
+			))
+			.is_ok());
+
+		let state = bisect_mark(repo_path, BisectStatus::Good)?;
+
+		assert_eq!(state.candidate, Some(c[6]));
+		assert_eq!(state.remaining, 2);
+		assert_eq!(state.steps, 1);
+
+		let state = bisect_mark(repo_path, BisectStatus::Bad)?;
+
+		assert_eq!(state.candidate, Some(c[5]));
+		assert_eq!(state.remaining, 0);
+
+		let state = bisect_mark(repo_path, BisectStatus::Good)?;
+
+		assert_eq!(state.candidate, None);
+		assert_eq!(state.first_bad, Some(c[6]));
+
+		let log = fs::read_to_string(bisect_file(&repo, BISECT_LOG))?;
+
+		assert!(log.starts_with(&format!(
+			"# bad: [{}] c10\n# good: [{}] c1\n",
+			c[9].to_string(),
+			c[0].to_string()
+		)));
+		assert!(log.ends_with(&format!(
+			"# first bad commit: [{}] c7\n",
+			c[6].to_string()
+		)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_bisect_skip_and_reset() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c = linear_history(&repo);
+
+		bisect_start(repo_path, c[9], &[c[0]])?;
+
+		assert!(bisect_start(repo_path, c[9], &[c[0]]).is_err());
+
+		let state = bisect_mark(repo_path, BisectStatus::Skip)?;
+
+		assert!(state.candidate.is_some());
+		assert_ne!(state.candidate, Some(c[4]));
+
+		bisect_reset(repo_path)?;
+
+		assert_eq!(bisect_state(repo_path)?, None);
+		assert_eq!(get_head_repo(&repo)?, c[9]);
+		assert!(repo.head()?.is_branch());
+		assert_eq!(repo.references_glob("refs/bisect/*")?.count(), 0);
+		assert!(!bisect_file(&repo, BISECT_LOG).exists());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_weights_with_merges() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+
+		let sig = repo.signature()?;
+		let tree = repo.head()?.peel_to_tree()?;
+		let commit = |msg: &str, parents: &[Oid]| -> Oid {
+			let parents: Vec<_> = parents
+				.iter()
+				.map(|id| repo.find_commit(*id).unwrap())
+				.collect();
+			let parents: Vec<_> = parents.iter().collect();
+			repo.commit(None, &sig, &sig, msg, &tree, &parents)
+				.unwrap()
+		};
+
+		let base = repo.head()?.peel_to_commit()?.id();
+		let a1 = commit("a1", &[base]);
+		let a2 = commit("a2", &[a1]);
+		let b1 = commit("b1", &[base]);
+		let m1 = commit("m1", &[a2, b1]);
+		let b2 = commit("b2", &[b1]);
+		let m2 = commit("m2", &[m1, b2]);
+		let tip = commit("tip", &[m2]);
+
+		let mut walk = repo.revwalk()?;
+		walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+		walk.push(tip)?;
+		let commits: Vec<Oid> =
+			walk.collect::<std::result::Result<_, _>>()?;
+
+		let expected: Vec<usize> = commits
+			.iter()
+			.map(|oid| {
+				let mut walk = repo.revwalk().unwrap();
+				walk.push(*oid).unwrap();
+				walk.count()
+			})
+			.collect();
+
+		assert_eq!(commit_weights(&repo, &commits)?, expected);
+		assert_eq!(expected.iter().max(), Some(&8));
+
+		Ok(())
+	}
+}
@@ -5,10 +5,11 @@ use crate::{
 	error::{Error, Result},
 	sync::{get_commits_info, repository::repo},
 };
-use git2::BlameOptions;
+use git2::{BlameOptions, Repository};
 use scopetime::scope_time;
 use std::collections::{HashMap, HashSet};
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
 use std::path::Path;
 
 /// A `BlameHunk` contains all the information that will be shown to the user.
@@ -53,6 +54,21 @@ fn fixup_windows_path(path: &str) -> String {
 	}
 }
 
+/// lines blamed at once between two progress reports of
+/// [`blame_file_with_progress`]
+const BLAME_CHUNK_LINES: usize = 500;
+
+/// progress of [`blame_file_with_progress`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlameProgress {
+	/// lines blamed so far
+	pub lines: usize,
+	/// lines to blame in total
+	pub total_lines: usize,
+	/// distinct commits found in the lines blamed so far
+	pub commits: usize,
+}
+
 ///
 pub fn blame_file(
 	repo_path: &RepoPath,
@@ -61,6 +77,236 @@ pub fn blame_file(
 ) -> Result<FileBlame> {
 	scope_time!("blame_file");
 
+	blame(
+		repo_path,
+		file_path,
+		commit_id,
+		None,
+		usize::MAX,
+		&mut |_| true,
+	)
+}
+
+/// like [`blame_file`] but blames `BLAME_CHUNK_LINES` lines at a
+/// time and calls `progress` after each chunk
+///
+/// Returning `false` from `progress` cancels the blame with
+/// [`Error::Canceled`], the remaining chunks are not blamed at all.
+pub fn blame_file_with_progress<F>(
+	repo_path: &RepoPath,
+	file_path: &str,
+	commit_id: Option<CommitId>,
+	mut progress: F,
+) -> Result<FileBlame>
+where
+	F: FnMut(BlameProgress) -> bool,
+{
+	scope_time!("blame_file_with_progress");
+
+	blame(
+		repo_path,
+		file_path,
+		commit_id,
+		None,
+		BLAME_CHUNK_LINES,
+		&mut progress,
+	)
+}
+
+/// blames only the lines `start_line..end_line` (0-based, like
+/// [`BlameHunk::start_line`]) of `file_path` at `HEAD`
+///
+/// `FileBlame::lines` only contains the requested lines, an `end_line`
+/// past the end of the file is clamped to it. An empty range is not
+/// blamed at all and has no lines.
+pub fn blame_file_range(
+	repo_path: &RepoPath,
+	file_path: &str,
+	start_line: usize,
+	end_line: usize,
+) -> Result<FileBlame> {
+	scope_time!("blame_file_range");
+
+	blame(
+		repo_path,
+		file_path,
+		None,
+		Some(start_line..end_line),
+		usize::MAX,
+		&mut |_| true,
+	)
+}
+
+/// commit that last changed the `line` (0-based, like
+/// [`BlameHunk::start_line`]) of `file_path` at `HEAD`, to be shown with
+/// `get_commit_details` or `get_commit_files`
+pub fn get_commit_for_blame_line(
+	repo_path: &RepoPath,
+	file_path: &str,
+	line: usize,
+) -> Result<CommitId> {
+	scope_time!("get_commit_for_blame_line");
+
+	let blame =
+		blame_file_range(repo_path, file_path, line, line + 1)?;
+
+	blame
+		.lines
+		.into_iter()
+		.next()
+		.and_then(|(hunk, _)| hunk)
+		.map(|hunk| hunk.commit_id)
+		.ok_or_else(|| {
+			Error::Generic(format!(
+				"line {} does not exist in '{file_path}'",
+				line + 1
+			))
+		})
+}
+
+/// blames `file_path` as of `commit_id` instead of `HEAD`, starting
+/// at the parent of a [`BlameHunk::commit_id`] shows how its lines
+/// looked before that commit
+pub fn blame_at_commit(
+	repo_path: &RepoPath,
+	file_path: &str,
+	commit_id: CommitId,
+) -> Result<FileBlame> {
+	scope_time!("blame_at_commit");
+
+	let repo = repo(repo_path)?;
+	let tree = repo.find_commit(commit_id.into())?.tree()?;
+
+	if tree
+		.get_path(Path::new(&fixup_windows_path(file_path)))
+		.is_err()
+	{
+		return Err(Error::Generic(format!(
+			"'{file_path}' does not exist in commit {}",
+			commit_id.get_short_string()
+		)));
+	}
+
+	blame(
+		repo_path,
+		file_path,
+		Some(commit_id),
+		None,
+		usize::MAX,
+		&mut |_| true,
+	)
+}
+
+/// the lines `start_line..end_line` (0-based) come from `commit_id`
+/// where they started at `orig_start` (0-based)
+#[derive(Clone, Copy, Debug)]
+struct BlameSegment {
+	commit_id: CommitId,
+	start_line: usize,
+	end_line: usize,
+	orig_start: usize,
+}
+
+/// blames only the lines in `window` (0-based)
+fn blame_window(
+	repo: &Repository,
+	file_path: &str,
+	commit_id: CommitId,
+	window: Range<usize>,
+) -> Result<Vec<BlameSegment>> {
+	let mut opts = BlameOptions::new();
+	opts.newest_commit(commit_id.into());
+	// `BlameOptions` lines are 1-based and inclusive
+	opts.min_line(window.start + 1);
+	opts.max_line(window.end);
+
+	let blame =
+		repo.blame_file(Path::new(file_path), Some(&mut opts))?;
+
+	Ok(blame
+		.iter()
+		.filter_map(|hunk| {
+			// Line indices in a `git2::BlameHunk` are 1-based.
+			let start_line =
+				hunk.final_start_line().saturating_sub(1);
+			let end_line =
+				start_line.saturating_add(hunk.lines_in_hunk());
+
+			let clipped_start = start_line.max(window.start);
+			let clipped_end = end_line.min(window.end);
+
+			(clipped_start < clipped_end).then(|| BlameSegment {
+				commit_id: CommitId::new(hunk.final_commit_id()),
+				start_line: clipped_start,
+				end_line: clipped_end,
+				orig_start: hunk.orig_start_line().saturating_sub(1)
+					+ (clipped_start - start_line),
+			})
+		})
+		.collect())
+}
+
+/// blames `range` calling `blame_window` for one window of
+/// `chunk_lines` lines after the other, `progress` is asked in between
+/// so canceling skips the remaining windows
+fn blame_in_windows(
+	range: Range<usize>,
+	chunk_lines: usize,
+	blame_window: &mut dyn FnMut(
+		Range<usize>,
+	) -> Result<Vec<BlameSegment>>,
+	progress: &mut dyn FnMut(BlameProgress) -> bool,
+) -> Result<Vec<BlameSegment>> {
+	let mut segments: Vec<BlameSegment> = Vec::new();
+	let mut commits = HashSet::new();
+
+	let mut window_start = range.start;
+	while window_start < range.end {
+		let window_end =
+			window_start.saturating_add(chunk_lines).min(range.end);
+
+		for segment in blame_window(window_start..window_end)? {
+			commits.insert(segment.commit_id);
+
+			// a window cuts the hunks crossing its start, glue them
+			// back together
+			match segments.last_mut() {
+				Some(last)
+					if segment.start_line == window_start
+						&& last.end_line == window_start
+						&& last.commit_id == segment.commit_id
+						&& last.orig_start + last.end_line
+							- last.start_line == segment
+							.orig_start =>
+				{
+					last.end_line = segment.end_line;
+				}
+				_ => segments.push(segment),
+			}
+		}
+
+		window_start = window_end;
+
+		if !progress(BlameProgress {
+			lines: window_start - range.start,
+			total_lines: range.len(),
+			commits: commits.len(),
+		}) {
+			return Err(Error::Canceled);
+		}
+	}
+
+	Ok(segments)
+}
+
+fn blame(
+	repo_path: &RepoPath,
+	file_path: &str,
+	commit_id: Option<CommitId>,
+	range: Option<Range<usize>>,
+	chunk_lines: usize,
+	progress: &mut dyn FnMut(BlameProgress) -> bool,
+) -> Result<FileBlame> {
 	let repo = repo(repo_path)?;
 
 	let commit_id = if let Some(commit_id) = commit_id {
@@ -82,18 +328,44 @@ pub fn blame_file(
 		return Err(Error::NoBlameOnBinaryFile);
 	}
 
-	let mut opts = BlameOptions::new();
-	opts.newest_commit(commit_id.into());
-
-	let blame =
-		repo.blame_file(Path::new(file_path), Some(&mut opts))?;
+	let line_count = BufReader::new(blob.content()).lines().count();
+	let range = range.map_or(0..line_count, |range| {
+		range.start..range.end.min(line_count)
+	});
+
+	if range.is_empty() {
+		return Ok(FileBlame {
+			commit_id,
+			path: file_path.into(),
+			lines: Vec::new(),
+		});
+	}
 
-	let reader = BufReader::new(blob.content());
+	let segments = blame_in_windows(
+		range.clone(),
+		chunk_lines,
+		&mut |window| {
+			blame_window(&repo, file_path, commit_id, window)
+		},
+		progress,
+	)?;
+
+	// commit, start and end line of the hunk of each line in `range`
+	let mut line_hunks = vec![None; range.len()];
+	let mut unique_commit_ids = HashSet::new();
+
+	for segment in &segments {
+		unique_commit_ids.insert(segment.commit_id);
+
+		for line in segment.start_line..segment.end_line {
+			line_hunks[line - range.start] = Some((
+				segment.commit_id,
+				segment.start_line,
+				segment.end_line,
+			));
+		}
+	}
 
-	let unique_commit_ids: HashSet<_> = blame
-		.iter()
-		.map(|hunk| CommitId::new(hunk.final_commit_id()))
-		.collect();
 	let mut commit_ids = Vec::with_capacity(unique_commit_ids.len());
 	commit_ids.extend(unique_commit_ids);
 
@@ -103,40 +375,27 @@ pub fn blame_file(
 		.map(|commit_info| (commit_info.id, commit_info))
 		.collect();
 
+	let reader = BufReader::new(blob.content());
+
 	let lines: Vec<(Option<BlameHunk>, String)> = reader
 		.lines()
-		.enumerate()
-		.map(|(i, line)| {
-			// Line indices in a `FileBlame` are 1-based.
-			let corresponding_hunk = blame.get_line(i + 1);
-
-			if let Some(hunk) = corresponding_hunk {
-				let commit_id = CommitId::new(hunk.final_commit_id());
-				// Line indices in a `BlameHunk` are 1-based.
-				let start_line =
-					hunk.final_start_line().saturating_sub(1);
-				let end_line =
-					start_line.saturating_add(hunk.lines_in_hunk());
-
-				if let Some(commit_info) =
-					unique_commit_infos.get(&commit_id)
-				{
-					let hunk = BlameHunk {
-						commit_id,
-						author: commit_info.author.clone(),
-						time: commit_info.time,
-						start_line,
-						end_line,
-					};
-
-					return (
-						Some(hunk),
-						line.unwrap_or_else(|_| String::new()),
-					);
-				}
-			}
-
-			(None, line.unwrap_or_else(|_| String::new()))
+		.skip(range.start)
+		.zip(line_hunks)
+		.map(|(line, hunk)| {
+			let hunk =
+				hunk.and_then(|(commit_id, start_line, end_line)| {
+					unique_commit_infos.get(&commit_id).map(
+						|commit_info| BlameHunk {
+							commit_id,
+							author: commit_info.author.clone(),
+							time: commit_info.time,
+							start_line,
+							end_line,
+						},
+					)
+				});
+
+			(hunk, line.unwrap_or_else(|_| String::new()))
 		})
 		.collect();
 
@@ -154,7 +413,10 @@ mod tests {
 	use super::*;
 	use crate::{
 		error::Result,
-		sync::{commit, stage_add_file, tests::repo_init_empty},
+		sync::{
+			commit, get_commit_details, stage_add_file,
+			tests::repo_init_empty,
+		},
 	};
 	use std::{
 		fs::{File, OpenOptions},
@@ -175,7 +437,7 @@ mod tests {
 		File::create(root.join(file_path))?.write_all(b"line 1\n")?;
 
 		stage_add_file(repo_path, file_path)?;
-		commit(repo_path, "first commit")?;
+		commit(repo_path, "first commit", false)?;
 
 		let blame = blame_file(repo_path, "foo", None)?;
 
@@ -199,7 +461,7 @@ mod tests {
 		file.write(b"line 2\n")?;
 
 		stage_add_file(repo_path, file_path)?;
-		commit(repo_path, "second commit")?;
+		commit(repo_path, "second commit", false)?;
 
 		let blame = blame_file(repo_path, "foo", None)?;
 
@@ -233,7 +495,7 @@ mod tests {
 		assert_eq!(blame.lines.len(), 2);
 
 		stage_add_file(repo_path, file_path)?;
-		commit(repo_path, "third commit")?;
+		commit(repo_path, "third commit", false)?;
 
 		let blame = blame_file(repo_path, "foo", None)?;
 
@@ -258,8 +520,274 @@ mod tests {
 			.unwrap();
 
 		stage_add_file(repo_path, file_path).unwrap();
-		commit(repo_path, "first commit").unwrap();
+		commit(repo_path, "first commit", false).unwrap();
 
 		assert!(blame_file(repo_path, "bar\\foo", None).is_ok());
 	}
+
+	#[test]
+	fn test_blame_range() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let mut file = File::create(root.join(file_path))?;
+		for i in 0..10 {
+			writeln!(file, "line {i}")?;
+
+			stage_add_file(repo_path, file_path)?;
+			commit(repo_path, &format!("commit {i}"), false)?;
+		}
+
+		let full = blame_file(repo_path, "foo", None)?;
+		let range = blame_file_range(repo_path, "foo", 3, 7)?;
+
+		assert_eq!(range.lines.len(), 4);
+
+		for ((range_hunk, range_line), (full_hunk, full_line)) in
+			range.lines.iter().zip(&full.lines[3..7])
+		{
+			assert_eq!(range_line, full_line);
+			assert_eq!(
+				range_hunk.as_ref().map(|hunk| hunk.commit_id),
+				full_hunk.as_ref().map(|hunk| hunk.commit_id)
+			);
+		}
+
+		let range = blame_file_range(repo_path, "foo", 8, 100)?;
+
+		assert_eq!(range.lines.len(), 2);
+		assert_eq!(range.lines[1].1, "line 9");
+
+		assert!(blame_file_range(repo_path, "foo", 12, 20)?
+			.lines
+			.is_empty());
+		assert!(blame_file_range(repo_path, "foo", 5, 5)?
+			.lines
+			.is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_blame_progress() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let mut file = File::create(root.join(file_path))?;
+		for i in 0..20 {
+			writeln!(file, "line {i}")?;
+			stage_add_file(repo_path, file_path)?;
+			commit(repo_path, &format!("commit {i}"), false)?;
+		}
+
+		let mut reported = Vec::new();
+		let chunked = blame(
+			repo_path,
+			"foo",
+			None,
+			None,
+			5,
+			&mut |progress| {
+				reported.push(progress);
+				true
+			},
+		)?;
+
+		assert_eq!(reported.len(), 4);
+		assert_eq!(
+			reported.last(),
+			Some(&BlameProgress {
+				lines: 20,
+				total_lines: 20,
+				commits: 20,
+			})
+		);
+
+		let full = blame_file(repo_path, "foo", None)?;
+
+		assert_eq!(chunked.lines, full.lines);
+
+		let mut calls = 0;
+		let res = blame(repo_path, "foo", None, None, 5, &mut |_| {
+			calls += 1;
+			false
+		});
+
+		assert!(matches!(res, Err(Error::Canceled)));
+		assert_eq!(calls, 1);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_blame_progress_hunk_across_chunks() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let mut file = File::create(root.join(file_path))?;
+		writeln!(file, "a\nb")?;
+		stage_add_file(repo_path, file_path)?;
+		commit(repo_path, "first", false)?;
+
+		// lines 2..6 come from one commit, a chunk size of 3 cuts them
+		writeln!(file, "c\nd\ne\nf")?;
+		stage_add_file(repo_path, file_path)?;
+		let second = commit(repo_path, "second", false)?;
+
+		let chunked =
+			blame(repo_path, "foo", None, None, 3, &mut |_| true)?;
+
+		for (hunk, _) in &chunked.lines[2..] {
+			let hunk = hunk.as_ref().unwrap();
+			assert_eq!(hunk.commit_id, second);
+			assert_eq!((hunk.start_line, hunk.end_line), (2, 6));
+		}
+
+		assert_eq!(
+			chunked.lines,
+			blame_file(repo_path, "foo", None)?.lines
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_blame_cancel_skips_remaining_chunks() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let mut file = File::create(root.join(file_path))?;
+		for i in 0..20 {
+			writeln!(file, "line {i}")?;
+		}
+		stage_add_file(repo_path, file_path)?;
+		let id = commit(repo_path, "commit", false)?;
+
+		let mut windows = Vec::new();
+		let res = blame_in_windows(
+			0..20,
+			5,
+			&mut |window| {
+				windows.push(window.clone());
+				blame_window(&repo, "foo", id, window)
+			},
+			&mut |_| false,
+		);
+
+		assert!(matches!(res, Err(Error::Canceled)));
+		assert_eq!(windows, vec![0..5]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_blame_at_commit() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		File::create(root.join("other"))?.write_all(b"x\n")?;
+		stage_add_file(repo_path, Path::new("other"))?;
+		let c0 = commit(repo_path, "c0", false)?;
+
+		File::create(root.join(file_path))?.write_all(b"a\nb\n")?;
+		stage_add_file(repo_path, file_path)?;
+		let c1 = commit(repo_path, "c1", false)?;
+
+		File::create(root.join(file_path))?.write_all(b"a\nc\n")?;
+		stage_add_file(repo_path, file_path)?;
+		let c2 = commit(repo_path, "c2", false)?;
+
+		let commits = |blame: &FileBlame| {
+			blame
+				.lines
+				.iter()
+				.map(|(hunk, line)| {
+					(hunk.as_ref().map(|h| h.commit_id), line.clone())
+				})
+				.collect::<Vec<_>>()
+		};
+
+		let at_head = blame_at_commit(repo_path, "foo", c2)?;
+		assert_eq!(
+			commits(&at_head),
+			vec![(Some(c1), "a".into()), (Some(c2), "c".into())]
+		);
+
+		let at_parent = blame_at_commit(repo_path, "foo", c1)?;
+		assert_eq!(at_parent.commit_id, c1);
+		assert_eq!(
+			commits(&at_parent),
+			vec![(Some(c1), "a".into()), (Some(c1), "b".into())]
+		);
+
+		assert_eq!(
+			blame_at_commit(repo_path, "foo", c0)
+				.map(|_| ())
+				.map_err(|e| e.to_string()),
+			Err(format!(
+				"`'foo' does not exist in commit {}`",
+				c0.get_short_string()
+			))
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_for_blame_line() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let commit_content = |content: &[u8], msg: &str| {
+			File::create(root.join(file_path))?.write_all(content)?;
+			stage_add_file(repo_path, file_path)?;
+			commit(repo_path, msg, false)
+		};
+
+		let c1 = commit_content(b"a\nb\nc\n", "c1")?;
+		let c2 = commit_content(b"a\nB\nc\n", "c2")?;
+		let c3 = commit_content(b"a\nB\nC\n", "c3")?;
+
+		assert_eq!(
+			get_commit_for_blame_line(repo_path, "foo", 0)?,
+			c1
+		);
+		assert_eq!(
+			get_commit_for_blame_line(repo_path, "foo", 1)?,
+			c2
+		);
+		assert_eq!(
+			get_commit_for_blame_line(repo_path, "foo", 2)?,
+			c3
+		);
+		assert!(
+			get_commit_for_blame_line(repo_path, "foo", 3).is_err()
+		);
+
+		let details = get_commit_details(
+			repo_path,
+			get_commit_for_blame_line(repo_path, "foo", 1)?,
+		)?;
+		assert_eq!(details.message.unwrap().subject, "c2");
+
+		Ok(())
+	}
 }
@@ -117,6 +117,7 @@ pub mod test {
 			"master",
 			None,
 			None,
+			false,
 		)
 		.unwrap();
 		assert!(bytes > 0);
@@ -126,6 +127,7 @@ pub mod test {
 			"master",
 			None,
 			None,
+			false,
 		)
 		.unwrap();
 		assert_eq!(bytes, 0);
@@ -13,7 +13,7 @@ use crate::{
 	error::{Error, Result},
 	sync::{repository::repo, utils::get_head_repo, CommitId},
 };
-use git2::{Branch, BranchType, Repository};
+use git2::{Branch, BranchType, ErrorCode, Repository};
 use scopetime::scope_time;
 use std::collections::HashSet;
 
@@ -285,6 +285,79 @@ pub fn branch_compare_upstream(
 	Ok(BranchCompare { ahead, behind })
 }
 
+/// commits `branch` is ahead and behind of its upstream, `(0, 0)` if
+/// it has no upstream
+pub fn branch_ahead_behind(
+	repo_path: &RepoPath,
+	branch: &str,
+) -> Result<(usize, usize)> {
+	scope_time!("branch_ahead_behind");
+
+	let repo = repo(repo_path)?;
+
+	let branch = repo.find_branch(branch, BranchType::Local)?;
+
+	let upstream = match branch.upstream() {
+		Ok(upstream) => upstream,
+		Err(e) if e.code() == ErrorCode::NotFound => {
+			return Ok((0, 0))
+		}
+		Err(e) => return Err(e.into()),
+	};
+
+	let branch_commit =
+		branch.into_reference().peel_to_commit()?.id();
+
+	let upstream_commit =
+		upstream.into_reference().peel_to_commit()?.id();
+
+	Ok(repo.graph_ahead_behind(branch_commit, upstream_commit)?)
+}
+
+/// upstream tracking state of the checked out branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackingInfo {
+	///
+	pub branch: String,
+	/// like `origin/master`, `None` if no upstream is configured
+	pub upstream: Option<String>,
+	/// commits on `branch` not in `upstream`
+	pub ahead: usize,
+	/// commits in `upstream` not on `branch`
+	pub behind: usize,
+}
+
+/// tracking info of `HEAD`, `None` if it is detached
+pub fn branch_tracking_info(
+	repo_path: &RepoPath,
+) -> Result<Option<TrackingInfo>> {
+	scope_time!("branch_tracking_info");
+
+	let repo = repo(repo_path)?;
+
+	if repo.head_detached()? {
+		return Ok(None);
+	}
+
+	let branch = Branch::wrap(repo.head()?);
+	let name = bytes2string(branch.name_bytes()?)?;
+
+	let upstream = match branch.upstream() {
+		Ok(upstream) => Some(bytes2string(upstream.name_bytes()?)?),
+		Err(e) if e.code() == ErrorCode::NotFound => None,
+		Err(e) => return Err(e.into()),
+	};
+
+	let (ahead, behind) = branch_ahead_behind(repo_path, &name)?;
+
+	Ok(Some(TrackingInfo {
+		branch: name,
+		upstream,
+		ahead,
+		behind,
+	}))
+}
+
 /// Modify HEAD to point to a branch then checkout head, does not work if there are uncommitted changes
 pub fn checkout_branch(
 	repo_path: &RepoPath,
@@ -318,10 +391,18 @@ pub fn checkout_branch(
 	}
 }
 
-/// Detach HEAD to point to a commit then checkout HEAD, does not work if there are uncommitted changes
+/// returns true if `HEAD` points to a commit instead of a branch
+pub fn is_head_detached(repo_path: &RepoPath) -> Result<bool> {
+	let repo = repo(repo_path)?;
+
+	Ok(repo.head_detached()?)
+}
+
+/// Detach HEAD to point to a commit then checkout HEAD, does not work if there are uncommitted changes unless `force` is set which discards them
 pub fn checkout_commit(
 	repo_path: &RepoPath,
 	commit_hash: CommitId,
+	force: bool,
 ) -> Result<()> {
 	scope_time!("checkout_commit");
 
@@ -331,7 +412,7 @@ pub fn checkout_commit(
 		git2::StatusOptions::new().include_ignored(false),
 	))?;
 
-	if statuses.is_empty() {
+	if force || statuses.is_empty() {
 		repo.set_head_detached(commit_hash.into())?;
 
 		if let Err(e) = repo.checkout_head(Some(
@@ -428,6 +509,39 @@ pub fn create_branch(
 	Ok(branch_ref_name)
 }
 
+/// creates branch `name` pointing to the commit of `start_ref`
+/// (e.g. `refs/remotes/origin/feature`) without checking it out
+///
+/// With `set_upstream` the branch tracks `start_ref`, writing
+/// `branch.<name>.remote` and `branch.<name>.merge`.
+pub fn create_branch_from(
+	repo_path: &RepoPath,
+	name: &str,
+	start_ref: &str,
+	set_upstream: bool,
+) -> Result<String> {
+	scope_time!("create_branch_from");
+
+	let repo = repo(repo_path)?;
+
+	let start = repo.find_reference(start_ref)?;
+	let start_commit = start.peel_to_commit()?;
+
+	let mut branch = repo.branch(name, &start_commit, false)?;
+
+	if set_upstream {
+		let upstream = start.shorthand().ok_or_else(|| {
+			Error::Generic(format!("invalid reference: {start_ref}"))
+		})?;
+		branch.set_upstream(Some(upstream))?;
+	}
+
+	let branch_ref = branch.into_reference();
+	let branch_ref_name = bytes2string(branch_ref.name_bytes())?;
+
+	Ok(branch_ref_name)
+}
+
 #[cfg(test)]
 mod tests_branch_name {
 	use super::*;
@@ -481,10 +595,93 @@ mod tests_create_branch {
 	}
 }
 
+#[cfg(test)]
+mod tests_create_branch_from {
+	use super::*;
+	use crate::sync::{
+		remotes::fetch_all,
+		tests::{repo_clone, repo_init, write_commit_file},
+	};
+
+	#[test]
+	fn test_create_tracking_branch() {
+		let (remote_dir, remote) = repo_init().unwrap();
+		let remote_path = remote_dir.path().to_str().unwrap();
+		create_branch(&remote_path.into(), "feature").unwrap();
+		remote.set_head("refs/heads/master").unwrap();
+
+		let (clone_dir, clone) = repo_clone(remote_path).unwrap();
+		let repo_path: &RepoPath =
+			&clone_dir.path().to_str().unwrap().into();
+
+		let reference = create_branch_from(
+			repo_path,
+			"feature",
+			"refs/remotes/origin/feature",
+			true,
+		)
+		.unwrap();
+
+		assert_eq!(reference, "refs/heads/feature");
+		assert_eq!(get_branch_name_repo(&clone).unwrap(), "master");
+
+		let config = clone.config().unwrap().snapshot().unwrap();
+		assert_eq!(
+			config.get_str("branch.feature.remote").unwrap(),
+			"origin"
+		);
+		assert_eq!(
+			config.get_str("branch.feature.merge").unwrap(),
+			"refs/heads/feature"
+		);
+
+		assert_eq!(
+			branch_ahead_behind(repo_path, "feature").unwrap(),
+			(0, 0)
+		);
+
+		remote.set_head("refs/heads/feature").unwrap();
+		write_commit_file(&remote, "foo.txt", "foo", "on feature");
+		fetch_all(repo_path, &None, &None).unwrap();
+
+		assert_eq!(
+			branch_ahead_behind(repo_path, "feature").unwrap(),
+			(0, 1)
+		);
+	}
+
+	#[test]
+	fn test_create_branch_without_upstream() {
+		let (remote_dir, _remote) = repo_init().unwrap();
+		let (clone_dir, clone) =
+			repo_clone(remote_dir.path().to_str().unwrap()).unwrap();
+		let repo_path: &RepoPath =
+			&clone_dir.path().to_str().unwrap().into();
+
+		create_branch_from(
+			repo_path,
+			"copy",
+			"refs/remotes/origin/master",
+			false,
+		)
+		.unwrap();
+
+		let config = clone.config().unwrap().snapshot().unwrap();
+		assert!(config.get_str("branch.copy.remote").is_err());
+		assert_eq!(
+			branch_ahead_behind(repo_path, "copy").unwrap(),
+			(0, 0)
+		);
+	}
+}
+
 #[cfg(test)]
 mod tests_branch_compare {
 	use super::*;
-	use crate::sync::tests::repo_init;
+	use crate::sync::{
+		remotes::fetch,
+		tests::{repo_clone, repo_init, write_commit_file},
+	};
 
 	#[test]
 	fn test_smoke() {
@@ -499,6 +696,97 @@ mod tests_branch_compare {
 
 		assert_eq!(res.is_err(), true);
 	}
+
+	#[test]
+	fn test_ahead_behind_no_upstream() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		create_branch(repo_path, "test").unwrap();
+
+		assert_eq!(
+			branch_ahead_behind(repo_path, "test").unwrap(),
+			(0, 0)
+		);
+		assert!(branch_ahead_behind(repo_path, "missing").is_err());
+	}
+
+	#[test]
+	fn test_ahead_behind_diverged() {
+		let (remote_dir, remote) = repo_init().unwrap();
+		let (clone_dir, clone) =
+			repo_clone(remote_dir.path().to_str().unwrap()).unwrap();
+		let clone_path: &RepoPath =
+			&clone_dir.path().to_str().unwrap().into();
+
+		write_commit_file(&clone, "a.txt", "a", "local 1");
+		write_commit_file(&clone, "b.txt", "b", "local 2");
+		write_commit_file(&remote, "c.txt", "c", "remote 1");
+
+		fetch(clone_path, "master", None, None, false).unwrap();
+
+		assert_eq!(
+			branch_ahead_behind(clone_path, "master").unwrap(),
+			(2, 1)
+		);
+	}
+
+	#[test]
+	fn test_tracking_info() {
+		let (remote_dir, remote) = repo_init().unwrap();
+		let (clone_dir, clone) =
+			repo_clone(remote_dir.path().to_str().unwrap()).unwrap();
+		let clone_path: &RepoPath =
+			&clone_dir.path().to_str().unwrap().into();
+
+		write_commit_file(&clone, "a.txt", "a", "local 1");
+		write_commit_file(&remote, "c.txt", "c", "remote 1");
+
+		fetch(clone_path, "master", None, None, false).unwrap();
+
+		assert_eq!(
+			branch_tracking_info(clone_path).unwrap(),
+			Some(TrackingInfo {
+				branch: String::from("master"),
+				upstream: Some(String::from("origin/master")),
+				ahead: 1,
+				behind: 1,
+			})
+		);
+	}
+
+	#[test]
+	fn test_tracking_info_no_upstream() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert_eq!(
+			branch_tracking_info(repo_path).unwrap(),
+			Some(TrackingInfo {
+				branch: String::from("master"),
+				upstream: None,
+				ahead: 0,
+				behind: 0,
+			})
+		);
+	}
+
+	#[test]
+	fn test_tracking_info_detached() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let commit = write_commit_file(&repo, "a.txt", "a", "c1");
+		checkout_commit(repo_path, commit, false).unwrap();
+
+		assert_eq!(branch_tracking_info(repo_path).unwrap(), None);
+	}
 }
 
 #[cfg(test)]
@@ -716,7 +1004,10 @@ mod tests_checkout {
 mod tests_checkout_commit {
 	use super::*;
 	use crate::sync::tests::{repo_init, write_commit_file};
-	use crate::sync::RepoPath;
+	use crate::sync::{
+		utils::{repo_read_file, repo_write_file},
+		RepoPath,
+	};
 
 	#[test]
 	fn test_smoke() {
@@ -729,7 +1020,7 @@ mod tests_checkout_commit {
 			write_commit_file(&repo, "test_1.txt", "test", "commit1");
 		write_commit_file(&repo, "test_2.txt", "test", "commit2");
 
-		checkout_commit(repo_path, commit).unwrap();
+		checkout_commit(repo_path, commit, false).unwrap();
 
 		assert!(repo.head_detached().unwrap());
 		assert_eq!(
@@ -737,6 +1028,59 @@ mod tests_checkout_commit {
 			commit.get_oid()
 		);
 	}
+
+	#[test]
+	fn test_detach_and_return() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let commit =
+			write_commit_file(&repo, "test_1.txt", "test", "commit1");
+		let head =
+			write_commit_file(&repo, "test_2.txt", "test", "commit2");
+
+		assert!(!is_head_detached(repo_path).unwrap());
+
+		checkout_commit(repo_path, commit, false).unwrap();
+
+		assert!(is_head_detached(repo_path).unwrap());
+		assert_eq!(get_head_repo(&repo).unwrap(), commit);
+		assert!(!root.join("test_2.txt").exists());
+
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+
+		assert!(!is_head_detached(repo_path).unwrap());
+		assert_eq!(get_head_repo(&repo).unwrap(), head);
+	}
+
+	#[test]
+	fn test_dirty_needs_force() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let commit =
+			write_commit_file(&repo, "test_1.txt", "test", "commit1");
+		write_commit_file(&repo, "test_1.txt", "test2", "commit2");
+		repo_write_file(&repo, "test_1.txt", "dirty").unwrap();
+
+		assert!(matches!(
+			checkout_commit(repo_path, commit, false),
+			Err(Error::UncommittedChanges)
+		));
+		assert!(!is_head_detached(repo_path).unwrap());
+
+		checkout_commit(repo_path, commit, true).unwrap();
+
+		assert!(is_head_detached(repo_path).unwrap());
+		assert_eq!(
+			repo_read_file(&repo, "test_1.txt").unwrap(),
+			"test"
+		);
+	}
 }
 
 #[cfg(test)]
@@ -1,12 +1,14 @@
 //! renaming of branches
 
 use crate::{
-	error::Result,
+	error::{Error, Result},
 	sync::{repository::repo, RepoPath},
 };
+use git2::ErrorCode;
 use scopetime::scope_time;
 
-/// Rename the branch reference
+/// Rename the branch reference, its upstream config and reflog move
+/// along with it
 pub fn rename_branch(
 	repo_path: &RepoPath,
 	branch_ref: &str,
@@ -17,7 +19,16 @@ pub fn rename_branch(
 	let repo = repo(repo_path)?;
 	let branch_as_ref = repo.find_reference(branch_ref)?;
 	let mut branch = git2::Branch::wrap(branch_as_ref);
-	branch.rename(new_name, true)?;
+
+	if let Err(e) = branch.rename(new_name, false) {
+		if e.code() == ErrorCode::Exists {
+			return Err(Error::Generic(format!(
+				"a branch named '{new_name}' already exists"
+			)));
+		}
+
+		return Err(e.into());
+	}
 
 	Ok(())
 }
@@ -26,7 +37,11 @@ pub fn rename_branch(
 mod test {
 	use super::super::{checkout_branch, create_branch, RepoPath};
 	use super::rename_branch;
-	use crate::sync::tests::repo_init;
+	use crate::{
+		error::Error,
+		sync::tests::{repo_clone, repo_init},
+	};
+	use git2::BranchType;
 
 	#[test]
 	fn test_rename_branch() {
@@ -68,4 +83,62 @@ mod test {
 			"AnotherName"
 		);
 	}
+
+	#[test]
+	fn test_rename_branch_keeps_upstream() {
+		let (remote_dir, _remote) = repo_init().unwrap();
+		let (clone_dir, clone) =
+			repo_clone(remote_dir.path().to_str().unwrap()).unwrap();
+		let repo_path: &RepoPath =
+			&clone_dir.path().to_str().unwrap().into();
+
+		let reflog_len =
+			clone.reflog("refs/heads/master").unwrap().len();
+		assert!(reflog_len > 0);
+
+		rename_branch(repo_path, "refs/heads/master", "main")
+			.unwrap();
+
+		let branch =
+			clone.find_branch("main", BranchType::Local).unwrap();
+		assert_eq!(
+			branch.upstream().unwrap().name().unwrap(),
+			Some("origin/master")
+		);
+
+		let config = clone.config().unwrap().snapshot().unwrap();
+		assert_eq!(
+			config.get_str("branch.main.remote").unwrap(),
+			"origin"
+		);
+		assert_eq!(
+			config.get_str("branch.main.merge").unwrap(),
+			"refs/heads/master"
+		);
+		assert!(config.get_str("branch.master.remote").is_err());
+
+		assert!(
+			clone.reflog("refs/heads/main").unwrap().len()
+				>= reflog_len
+		);
+	}
+
+	#[test]
+	fn test_rename_branch_existing_name() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		create_branch(repo_path, "other").unwrap();
+
+		assert!(matches!(
+			rename_branch(repo_path, "refs/heads/other", "master"),
+			Err(Error::Generic(msg)) if msg.contains("already exists")
+		));
+		assert!(repo.find_branch("other", BranchType::Local).is_ok());
+		assert!(repo
+			.find_branch("master", BranchType::Local)
+			.is_ok());
+	}
 }
@@ -0,0 +1,244 @@
+//! sync git api for cherry-picking commits
+
+use super::{
+	commit::signature_allow_undefined_name,
+	merge::abort_pending_state, repository::repo,
+	utils::conflicted_paths, CommitId, RepoPath,
+};
+use crate::error::{Error, Result};
+use git2::RepositoryState;
+use scopetime::scope_time;
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CherryPickResult {
+	/// all commits were applied, ids of the newly created commits
+	Applied(Vec<CommitId>),
+	/// cherry-picking `commits[index]` conflicted, the index is left
+	/// with the conflicts for resolution
+	Conflict {
+		///
+		index: usize,
+		/// commits created before the conflict
+		applied: Vec<CommitId>,
+		///
+		conflicted_paths: Vec<String>,
+	},
+}
+
+/// cherry-picks `commits` in order on top of `HEAD`, committing each
+/// with its original author and message
+///
+/// Stops at the first conflict. Once it is resolved and committed
+/// continue by calling this again with the commits after `index`.
+pub fn cherry_pick_range(
+	repo_path: &RepoPath,
+	commits: &[CommitId],
+) -> Result<CherryPickResult> {
+	scope_time!("cherry_pick_range");
+
+	let repo = repo(repo_path)?;
+	let committer = signature_allow_undefined_name(&repo)?;
+
+	let mut applied = Vec::with_capacity(commits.len());
+
+	for (index, id) in commits.iter().enumerate() {
+		let commit = repo.find_commit((*id).into())?;
+
+		repo.cherrypick(&commit, None)?;
+
+		let mut git_index = repo.index()?;
+		if git_index.has_conflicts() {
+			return Ok(CherryPickResult::Conflict {
+				index,
+				applied,
+				conflicted_paths: conflicted_paths(&repo)?,
+			});
+		}
+
+		let tree = repo.find_tree(git_index.write_tree()?)?;
+		let head = repo.head()?.peel_to_commit()?;
+
+		let new_id = repo.commit(
+			Some("HEAD"),
+			&commit.author(),
+			&committer,
+			commit.message().unwrap_or_default(),
+			&tree,
+			&[&head],
+		)?;
+
+		repo.cleanup_state()?;
+
+		applied.push(new_id.into());
+	}
+
+	Ok(CherryPickResult::Applied(applied))
+}
+
+/// aborts a pending cherry-pick restoring the state before it started
+///
+/// Commits `cherry_pick_range` applied before the conflict are kept.
+pub fn abort_cherry_pick(repo_path: &RepoPath) -> Result<()> {
+	scope_time!("abort_cherry_pick");
+
+	let repo = repo(repo_path)?;
+
+	if !matches!(
+		repo.state(),
+		RepositoryState::CherryPick
+			| RepositoryState::CherryPickSequence
+	) {
+		return Err(Error::Generic(
+			"no cherry-pick in progress".into(),
+		));
+	}
+
+	abort_pending_state(repo_path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		checkout_branch, commit, create_branch, get_commits_info,
+		stage_add_file,
+		tests::{repo_init, write_commit_file},
+		utils::{repo_read_file, repo_write_file},
+		LogWalker, RepoState,
+	};
+	use std::path::Path;
+
+	#[test]
+	fn test_cherry_pick_range() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let base =
+			write_commit_file(&repo, "base.txt", "base", "base");
+		create_branch(repo_path, "other")?;
+		checkout_branch(repo_path, "refs/heads/master")?;
+
+		let c1 = write_commit_file(&repo, "a.txt", "a", "c1");
+		let c2 = write_commit_file(&repo, "b.txt", "b", "c2");
+
+		checkout_branch(repo_path, "refs/heads/other")?;
+
+		let res = cherry_pick_range(repo_path, &[c1, c2])?;
+
+		let applied = match res {
+			CherryPickResult::Applied(applied) => applied,
+			CherryPickResult::Conflict { .. } => Vec::new(),
+		};
+		assert_eq!(applied.len(), 2);
+
+		let mut items = Vec::new();
+		LogWalker::new(&repo, 10)?.read(&mut items)?;
+
+		assert_eq!(items.len(), 4);
+		assert_eq!(items[0], applied[1]);
+		assert_eq!(items[1], applied[0]);
+		assert_eq!(items[2], base);
+
+		let infos = get_commits_info(repo_path, &items[..2], 50)?;
+		assert_eq!(infos[0].message, "c2");
+		assert_eq!(infos[1].message, "c1");
+
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a");
+		assert_eq!(repo_read_file(&repo, "b.txt")?, "b");
+		assert_eq!(
+			crate::sync::repo_state(repo_path)?,
+			RepoState::Clean
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_cherry_pick_range_conflict_resume() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "base", "base");
+		create_branch(repo_path, "other")?;
+		write_commit_file(&repo, "a.txt", "other", "on other");
+		checkout_branch(repo_path, "refs/heads/master")?;
+
+		let c1 = write_commit_file(&repo, "a.txt", "master", "c1");
+		let c2 = write_commit_file(&repo, "b.txt", "b", "c2");
+
+		checkout_branch(repo_path, "refs/heads/other")?;
+
+		let res = cherry_pick_range(repo_path, &[c1, c2])?;
+
+		assert_eq!(
+			res,
+			CherryPickResult::Conflict {
+				index: 0,
+				applied: Vec::new(),
+				conflicted_paths: vec![String::from("a.txt")],
+			}
+		);
+
+		repo_write_file(&repo, "a.txt", "resolved")?;
+		stage_add_file(repo_path, Path::new("a.txt"))?;
+		commit(repo_path, "c1 resolved", false)?;
+		repo.cleanup_state()?;
+
+		let res = cherry_pick_range(repo_path, &[c2])?;
+
+		assert!(
+			matches!(res, CherryPickResult::Applied(ids) if ids.len() == 1)
+		);
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "resolved");
+		assert_eq!(repo_read_file(&repo, "b.txt")?, "b");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_abort_cherry_pick() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "base", "base");
+		create_branch(repo_path, "other")?;
+		let head =
+			write_commit_file(&repo, "a.txt", "other", "on other");
+		checkout_branch(repo_path, "refs/heads/master")?;
+
+		let c1 = write_commit_file(&repo, "a.txt", "master", "c1");
+
+		checkout_branch(repo_path, "refs/heads/other")?;
+
+		assert!(abort_cherry_pick(repo_path).is_err());
+
+		let res = cherry_pick_range(repo_path, &[c1])?;
+
+		assert!(matches!(res, CherryPickResult::Conflict { .. }));
+		assert_eq!(
+			crate::sync::repo_state(repo_path)?,
+			RepoState::CherryPick
+		);
+
+		abort_cherry_pick(repo_path)?;
+
+		assert_eq!(
+			crate::sync::repo_state(repo_path)?,
+			RepoState::Clean
+		);
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "other");
+		assert_eq!(
+			CommitId::from(repo.head()?.target().unwrap()),
+			head
+		);
+
+		Ok(())
+	}
+}
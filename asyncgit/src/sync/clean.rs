@@ -0,0 +1,210 @@
+//! removing untracked files like `git clean`
+
+use super::{repository::repo, utils::work_dir, RepoPath};
+use crate::error::Result;
+use git2::{Status, StatusOptions};
+use scopetime::scope_time;
+use std::{
+	collections::{BTreeSet, HashSet},
+	fs,
+	path::{Path, PathBuf},
+};
+
+/// removes untracked files (relative paths are returned), ignored ones
+/// only if `include_ignored` is set
+///
+/// Like `git clean` untracked directories are only removed with
+/// `directories`, otherwise their files are left alone. Directories
+/// holding files that are kept (like ignored ones) are not removed as a
+/// whole, only their other files are. With `dry_run` nothing is removed
+/// but the paths that would be are returned.
+pub fn clean_untracked(
+	repo_path: &RepoPath,
+	include_ignored: bool,
+	directories: bool,
+	dry_run: bool,
+) -> Result<Vec<PathBuf>> {
+	scope_time!("clean_untracked");
+
+	let repo = repo(repo_path)?;
+	let work_dir = work_dir(&repo)?;
+
+	let mut options = StatusOptions::new();
+	options
+		.include_untracked(true)
+		.recurse_untracked_dirs(true)
+		.include_ignored(true)
+		.recurse_ignored_dirs(true);
+
+	let mut removable = Vec::new();
+	let mut kept = Vec::new();
+
+	for entry in repo.statuses(Some(&mut options))?.iter() {
+		let Some(path) = entry.path() else {
+			continue;
+		};
+
+		// nested repositories are reported as directories, git
+		// refuses to clean them without being forced twice
+		if path.ends_with('/') {
+			continue;
+		}
+
+		let status = entry.status();
+		if status.contains(Status::WT_NEW)
+			|| (include_ignored && status.contains(Status::IGNORED))
+		{
+			removable.push(PathBuf::from(path));
+		} else if status.contains(Status::IGNORED) {
+			kept.push(PathBuf::from(path));
+		}
+	}
+
+	let tracked_dirs = repo
+		.index()?
+		.iter()
+		.filter_map(|entry| {
+			String::from_utf8(entry.path).ok().map(PathBuf::from)
+		})
+		.flat_map(|path| {
+			path.ancestors()
+				.skip(1)
+				.map(Path::to_path_buf)
+				.collect::<Vec<_>>()
+		})
+		.collect::<HashSet<_>>();
+
+	let mut paths = BTreeSet::new();
+	for path in removable {
+		// topmost directory of `path` that is not tracked
+		let untracked_dir = path
+			.ancestors()
+			.skip(1)
+			.take_while(|dir| !tracked_dirs.contains(*dir))
+			.last()
+			.map(Path::to_path_buf);
+
+		match untracked_dir {
+			None => {
+				paths.insert(path);
+			}
+			Some(_) if !directories => (),
+			Some(dir) => {
+				if kept.iter().any(|kept| kept.starts_with(&dir)) {
+					paths.insert(path);
+				} else {
+					paths.insert(dir);
+				}
+			}
+		}
+	}
+
+	if !dry_run {
+		for path in &paths {
+			let full_path = work_dir.join(path);
+
+			if full_path.is_dir() {
+				fs::remove_dir_all(full_path)?;
+			} else {
+				fs::remove_file(full_path)?;
+			}
+		}
+	}
+
+	Ok(paths.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+
+	fn setup(root: &Path) -> Result<()> {
+		fs::create_dir(root.join("dir"))?;
+		fs::create_dir(root.join("mixed"))?;
+		fs::create_dir_all(root.join("sub/deeper"))?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_clean_dry_run_matches_removal() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		setup(root)?;
+		write_commit_file(&repo, ".gitignore", "*.log\n", "c1");
+		write_commit_file(&repo, "sub/tracked.txt", "t", "c2");
+
+		repo_write_file(&repo, "a.txt", "a")?;
+		repo_write_file(&repo, "dir/b.txt", "b")?;
+		repo_write_file(&repo, "mixed/c.txt", "c")?;
+		repo_write_file(&repo, "mixed/keep.log", "k")?;
+		repo_write_file(&repo, "sub/d.txt", "d")?;
+		repo_write_file(&repo, "sub/deeper/e.txt", "e")?;
+		repo_write_file(&repo, "x.log", "x")?;
+
+		assert_eq!(
+			clean_untracked(repo_path, false, false, true)?,
+			vec![PathBuf::from("a.txt"), PathBuf::from("sub/d.txt")]
+		);
+
+		let dry_run = clean_untracked(repo_path, false, true, true)?;
+		assert_eq!(
+			dry_run,
+			vec![
+				PathBuf::from("a.txt"),
+				PathBuf::from("dir"),
+				PathBuf::from("mixed/c.txt"),
+				PathBuf::from("sub/d.txt"),
+				PathBuf::from("sub/deeper"),
+			]
+		);
+		assert!(root.join("a.txt").exists());
+
+		assert_eq!(
+			clean_untracked(repo_path, false, true, false)?,
+			dry_run
+		);
+
+		for path in &dry_run {
+			assert!(!root.join(path).exists());
+		}
+		assert!(root.join("mixed/keep.log").exists());
+		assert!(root.join("x.log").exists());
+		assert!(root.join("sub/tracked.txt").exists());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_clean_include_ignored() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		setup(root)?;
+		write_commit_file(&repo, ".gitignore", "*.log\n", "c1");
+
+		repo_write_file(&repo, "mixed/c.txt", "c")?;
+		repo_write_file(&repo, "mixed/keep.log", "k")?;
+		repo_write_file(&repo, "x.log", "x")?;
+
+		assert_eq!(
+			clean_untracked(repo_path, true, true, false)?,
+			vec![PathBuf::from("mixed"), PathBuf::from("x.log")]
+		);
+
+		assert!(!root.join("mixed").exists());
+		assert!(!root.join("x.log").exists());
+		assert!(root.join(".gitignore").exists());
+
+		Ok(())
+	}
+}
@@ -1,16 +1,33 @@
 use super::{CommitId, RepoPath};
 use crate::{
-	error::Result,
-	sync::{repository::repo, utils::get_head_repo},
+	error::{Error, Result},
+	sync::{
+		config::get_config_string_repo, repository::repo,
+		utils::get_head_repo,
+	},
+};
+use git2::{
+	Commit, ErrorCode, ObjectType, Repository, Signature, Time, Tree,
 };
-use git2::{ErrorCode, ObjectType, Repository, Signature};
 use scopetime::scope_time;
+use std::{
+	fs::read_to_string,
+	io::Write,
+	path::PathBuf,
+	process::{Command, Output, Stdio},
+	str::FromStr,
+};
+use tempfile::NamedTempFile;
+
+const PGP_SIGNATURE_HEADER: &[u8] = b"-----BEGIN PGP SIGNATURE-----";
+const SSH_SIGNATURE_HEADER: &[u8] = b"-----BEGIN SSH SIGNATURE-----";
 
 ///
 pub fn amend(
 	repo_path: &RepoPath,
 	id: CommitId,
 	msg: &str,
+	sign_off: bool,
 ) -> Result<CommitId> {
 	scope_time!("amend");
 
@@ -21,18 +38,111 @@ pub fn amend(
 	let tree_id = index.write_tree()?;
 	let tree = repo.find_tree(tree_id)?;
 
+	let msg = if sign_off {
+		sign_off_message(msg, &signature_allow_undefined_name(&repo)?)
+	} else {
+		msg.to_string()
+	};
+
 	let new_id = commit.amend(
 		Some("HEAD"),
 		None,
 		None,
 		None,
-		Some(msg),
+		Some(&msg),
 		Some(&tree),
 	)?;
 
 	Ok(CommitId::new(new_id))
 }
 
+/// Rewrites the author of the HEAD commit `id` keeping its tree,
+/// parents and message.
+///
+/// The original author time is kept if `when` is `None`. Returns
+/// [`Error::NotHeadCommit`] if `id` is not the HEAD commit.
+pub fn amend_commit_author(
+	repo_path: &RepoPath,
+	id: CommitId,
+	name: &str,
+	email: &str,
+	when: Option<Time>,
+) -> Result<CommitId> {
+	scope_time!("amend_commit_author");
+
+	let repo = repo(repo_path)?;
+
+	if get_head_repo(&repo)? != id {
+		return Err(Error::NotHeadCommit);
+	}
+
+	let commit = repo.find_commit(id.into())?;
+	let when = when.unwrap_or_else(|| commit.author().when());
+	let author = Signature::new(name, email, &when)?;
+
+	let new_id = commit.amend(
+		Some("HEAD"),
+		Some(&author),
+		None,
+		None,
+		None,
+		None,
+	)?;
+
+	Ok(CommitId::new(new_id))
+}
+
+/// Replaces the last `n` commits by a single one with `message`, like
+/// `git reset --soft HEAD~n` followed by a commit.
+///
+/// The new commit has the tree of `HEAD` and the author of the oldest
+/// of the squashed commits, the index is left alone. Merge commits can
+/// not be squashed.
+pub fn squash_last(
+	repo_path: &RepoPath,
+	n: usize,
+	message: &str,
+) -> Result<CommitId> {
+	scope_time!("squash_last");
+
+	if n == 0 {
+		return Err(Error::Generic("nothing to squash".into()));
+	}
+
+	let repo = repo(repo_path)?;
+	let head = repo.find_commit(get_head_repo(&repo)?.into())?;
+
+	let mut oldest = head.clone();
+	for i in 0..n {
+		if oldest.parent_count() > 1 {
+			return Err(Error::Generic(format!(
+				"can not squash merge commit {}",
+				CommitId::new(oldest.id()).get_short_string()
+			)));
+		}
+
+		if i + 1 < n {
+			oldest = oldest.parent(0).map_err(|_| Error::NoParent)?;
+		}
+	}
+	let base = oldest.parent(0).map_err(|_| Error::NoParent)?;
+
+	let committer = signature_allow_undefined_name(&repo)?;
+	let id = repo.commit(
+		None,
+		&oldest.author(),
+		&committer,
+		message,
+		&head.tree()?,
+		&[&base],
+	)?;
+
+	// only move `HEAD` once the commit exists
+	repo.head()?.set_target(id, "commit (squash)")?;
+
+	Ok(id.into())
+}
+
 /// Wrap `Repository::signature` to allow unknown user.name.
 ///
 /// See <https://github.com/extrawurst/gitui/issues/79>.
@@ -61,12 +171,29 @@ pub(crate) fn signature_allow_undefined_name(
 }
 
 /// this does not run any git hooks, git-hooks have to be executed manually, checkout `hooks_commit_msg` for example
-pub fn commit(repo_path: &RepoPath, msg: &str) -> Result<CommitId> {
+///
+/// The commit is signed if `commit.gpgsign` is set.
+///
+/// With `sign_off` a `Signed-off-by` trailer of the committer is
+/// appended like `git commit -s` does.
+///
+/// Only what is staged in the index is committed, unstaged changes
+/// stay in the workdir.
+pub fn commit(
+	repo_path: &RepoPath,
+	msg: &str,
+	sign_off: bool,
+) -> Result<CommitId> {
 	scope_time!("commit");
 
 	let repo = repo(repo_path)?;
 
 	let signature = signature_allow_undefined_name(&repo)?;
+	let msg = if sign_off {
+		sign_off_message(msg, &signature)
+	} else {
+		msg.to_string()
+	};
 	let mut index = repo.index()?;
 	let tree_id = index.write_tree()?;
 	let tree = repo.find_tree(tree_id)?;
@@ -79,27 +206,288 @@ pub fn commit(repo_path: &RepoPath, msg: &str) -> Result<CommitId> {
 
 	let parents = parents.iter().collect::<Vec<_>>();
 
+	if repo.config()?.get_bool("commit.gpgsign").unwrap_or(false) {
+		return commit_signed(
+			&repo,
+			&signature,
+			&msg,
+			&tree,
+			parents.as_slice(),
+		);
+	}
+
 	Ok(repo
 		.commit(
 			Some("HEAD"),
 			&signature,
 			&signature,
-			msg,
+			&msg,
 			&tree,
 			parents.as_slice(),
 		)?
 		.into())
 }
 
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trailer {
+	///
+	pub key: String,
+	///
+	pub value: String,
+}
+
+fn parse_trailer_line(line: &str) -> Option<Trailer> {
+	let (key, value) = line.split_once(':')?;
+	let key = key.trim_end();
+
+	if key.is_empty()
+		|| !key.chars().all(|c| c.is_alphanumeric() || c == '-')
+	{
+		return None;
+	}
+
+	Some(Trailer {
+		key: key.to_string(),
+		value: value.trim().to_string(),
+	})
+}
+
+/// returns the trailers (`Key: value` lines) of `msg`
+///
+/// Like git only the last paragraph is considered and only if it is
+/// not the subject and all of its lines are trailers. Lines starting
+/// with whitespace continue the value of the previous trailer.
+pub fn parse_trailers(msg: &str) -> Vec<Trailer> {
+	let Some((_, block)) = msg.trim_end().rsplit_once("\n\n") else {
+		return Vec::new();
+	};
+
+	let mut trailers: Vec<Trailer> = Vec::new();
+	for line in block.trim_start_matches('\n').lines() {
+		if line.starts_with(char::is_whitespace) {
+			match trailers.last_mut() {
+				Some(trailer) => {
+					trailer.value.push(' ');
+					trailer.value.push_str(line.trim());
+				}
+				None => return Vec::new(),
+			}
+		} else if let Some(trailer) = parse_trailer_line(line) {
+			trailers.push(trailer);
+		} else {
+			return Vec::new();
+		}
+	}
+
+	trailers
+}
+
+/// appends the trailer `key: value` to the trailer block of `msg`,
+/// starting a new block if there is none
+///
+/// `msg` is returned unchanged if it already has this trailer.
+pub fn add_trailer(msg: &str, key: &str, value: &str) -> String {
+	let trailer = format!("{key}: {value}");
+
+	let msg = msg.trim_end();
+	if msg.is_empty() {
+		return trailer;
+	}
+
+	let trailers = parse_trailers(msg);
+
+	if trailers.is_empty() {
+		format!("{msg}\n\n{trailer}")
+	} else if trailers.iter().any(|existing| {
+		existing.key.eq_ignore_ascii_case(key)
+			&& existing.value == value
+	}) {
+		msg.to_string()
+	} else {
+		format!("{msg}\n{trailer}")
+	}
+}
+
+/// appends a `Signed-off-by` trailer of `signature` to `msg` unless
+/// its trailer block already contains it
+fn sign_off_message(msg: &str, signature: &Signature) -> String {
+	add_trailer(
+		msg,
+		"Signed-off-by",
+		&format!(
+			"{} <{}>",
+			String::from_utf8_lossy(signature.name_bytes()),
+			String::from_utf8_lossy(signature.email_bytes())
+		),
+	)
+}
+
+fn commit_signed(
+	repo: &Repository,
+	signature: &Signature,
+	msg: &str,
+	tree: &Tree,
+	parents: &[&Commit],
+) -> Result<CommitId> {
+	let buffer = repo.commit_create_buffer(
+		signature, signature, msg, tree, parents,
+	)?;
+	let buffer = buffer.as_str().ok_or_else(|| {
+		Error::Sign(String::from("commit is not valid utf8"))
+	})?;
+
+	let commit_signature = sign_buffer(repo, signature, buffer)?;
+
+	let id = repo.commit_signed(buffer, &commit_signature, None)?;
+
+	// `commit_signed` does not move `HEAD` like `commit` does
+	let log_message =
+		format!("commit: {}", msg.lines().next().unwrap_or_default());
+	match repo.find_reference("HEAD")?.symbolic_target() {
+		Some(branch) => {
+			repo.reference(branch, id, true, &log_message)?;
+		}
+		None => repo.set_head_detached(id)?,
+	}
+
+	Ok(id.into())
+}
+
+/// Signs `buffer` according to `gpg.format` with `user.signingkey`.
+///
+/// Like git, gpg falls back to the committer as key id while ssh
+/// requires `user.signingkey` to be either the path of a key or a
+/// public key (literal or `key::` prefixed) to use from the ssh agent.
+fn sign_buffer(
+	repo: &Repository,
+	signature: &Signature,
+	buffer: &str,
+) -> Result<String> {
+	let signing_key =
+		get_config_string_repo(repo, "user.signingkey")?;
+
+	match get_config_string_repo(repo, "gpg.format")?.as_deref() {
+		None | Some("openpgp") => {
+			let key =
+				signing_key.unwrap_or_else(|| signature.to_string());
+			gpg_sign(repo, &key, buffer)
+		}
+		Some("ssh") => {
+			let key = signing_key.ok_or_else(|| {
+				Error::Sign(String::from(
+					"user.signingkey is not set",
+				))
+			})?;
+			ssh_sign(repo, &key, buffer)
+		}
+		Some(format) => Err(Error::Sign(format!(
+			"unsupported gpg.format: {format}"
+		))),
+	}
+}
+
+fn gpg_sign(
+	repo: &Repository,
+	key: &str,
+	buffer: &str,
+) -> Result<String> {
+	let output = run_with_stdin(
+		Command::new(gpg_program(repo)?).args([
+			"--status-fd=2",
+			"-bsau",
+			key,
+		]),
+		buffer.as_bytes(),
+	)?;
+
+	let status = String::from_utf8_lossy(&output.stderr);
+	let created = status
+		.lines()
+		.any(|line| line.starts_with("[GNUPG:] SIG_CREATED "));
+
+	if !output.status.success() || !created {
+		return Err(Error::Sign(format!("gpg failed: {status}")));
+	}
+
+	signature_from_output(output)
+}
+
+fn ssh_sign(
+	repo: &Repository,
+	key: &str,
+	buffer: &str,
+) -> Result<String> {
+	let mut command = Command::new(ssh_program(repo)?);
+	command.args(["-Y", "sign", "-n", "git"]);
+
+	let literal_key = key
+		.strip_prefix("key::")
+		.or_else(|| key.starts_with("ssh-").then_some(key));
+
+	// kept alive until `ssh-keygen` is done
+	let _key_file = if let Some(literal_key) = literal_key {
+		let key_file = temp_file_with(literal_key.as_bytes())?;
+		command.arg("-U").arg("-f").arg(key_file.path());
+		Some(key_file)
+	} else {
+		command.arg("-f").arg(shellexpand::full(key)?.as_ref());
+		None
+	};
+
+	let output = run_with_stdin(&mut command, buffer.as_bytes())?;
+
+	if !output.status.success() {
+		return Err(Error::Sign(format!(
+			"ssh-keygen failed: {}",
+			String::from_utf8_lossy(&output.stderr)
+		)));
+	}
+
+	signature_from_output(output)
+}
+
+fn signature_from_output(output: Output) -> Result<String> {
+	let signature = String::from_utf8(output.stdout)?;
+
+	if signature.is_empty() {
+		return Err(Error::Sign(String::from("empty signature")));
+	}
+
+	Ok(signature)
+}
+
+fn gpg_program(repo: &Repository) -> Result<String> {
+	let program =
+		match get_config_string_repo(repo, "gpg.openpgp.program")? {
+			Some(program) => program,
+			None => get_config_string_repo(repo, "gpg.program")?
+				.unwrap_or_else(|| String::from("gpg")),
+		};
+
+	Ok(program)
+}
+
+fn ssh_program(repo: &Repository) -> Result<String> {
+	Ok(get_config_string_repo(repo, "gpg.ssh.program")?
+		.unwrap_or_else(|| String::from("ssh-keygen")))
+}
+
 /// Tag a commit.
 ///
+/// With a `message` an annotated tag is created, otherwise a lightweight
+/// one. `sign` signs the annotated tag like `git tag -s` using the same
+/// `gpg.format` and `user.signingkey` config as signed commits.
+///
 /// This function will return an `Err(…)` variant if the tag’s name is refused
-/// by git or if the tag already exists.
+/// by git, if the tag already exists or if `sign` is set without a
+/// `message`.
 pub fn tag_commit(
 	repo_path: &RepoPath,
 	commit_id: &CommitId,
 	tag: &str,
 	message: Option<&str>,
+	sign: bool,
 ) -> Result<CommitId> {
 	scope_time!("tag_commit");
 
@@ -109,32 +497,366 @@ pub fn tag_commit(
 	let target =
 		repo.find_object(object_id, Some(ObjectType::Commit))?;
 
-	let c = if let Some(message) = message {
-		let signature = signature_allow_undefined_name(&repo)?;
-		repo.tag(tag, &target, &signature, message, false)?.into()
-	} else {
-		repo.tag_lightweight(tag, &target, false)?.into()
+	let c = match message {
+		Some(message) if sign => {
+			let signature = signature_allow_undefined_name(&repo)?;
+			signed_tag(&repo, object_id, tag, &signature, message)?
+				.into()
+		}
+		Some(message) => {
+			let signature = signature_allow_undefined_name(&repo)?;
+			repo.tag(tag, &target, &signature, message, false)?.into()
+		}
+		None if sign => {
+			return Err(Error::Sign(String::from(
+				"a signed tag needs a message",
+			)));
+		}
+		None => repo.tag_lightweight(tag, &target, false)?.into(),
 	};
 
 	Ok(c)
 }
 
+/// formats `signature` the way it appears in raw commit and tag objects
+fn raw_signature(signature: &Signature) -> String {
+	let time = signature.when();
+	let offset = time.offset_minutes().abs();
+
+	format!(
+		"{} {} {}{:02}{:02}",
+		signature,
+		time.seconds(),
+		if time.offset_minutes() < 0 { '-' } else { '+' },
+		offset / 60,
+		offset % 60,
+	)
+}
+
+fn signed_tag(
+	repo: &Repository,
+	target: git2::Oid,
+	tag: &str,
+	signature: &Signature,
+	message: &str,
+) -> Result<git2::Oid> {
+	let name = format!("refs/tags/{tag}");
+
+	if repo.find_reference(&name).is_ok() {
+		return Err(Error::Generic(format!(
+			"tag '{tag}' already exists"
+		)));
+	}
+
+	let mut message = message.to_string();
+	if !message.ends_with('\n') {
+		message.push('\n');
+	}
+
+	let buffer = format!(
+		"object {target}\ntype commit\ntag {tag}\ntagger {}\n\n{message}",
+		raw_signature(signature)
+	);
+	let tag_signature = sign_buffer(repo, signature, &buffer)?;
+
+	let tag_id = repo.odb()?.write(
+		ObjectType::Tag,
+		format!("{buffer}{tag_signature}").as_bytes(),
+	)?;
+	repo.reference(&name, tag_id, false, "tag: signed")?;
+
+	Ok(tag_id)
+}
+
+/// Reads the file configured as `commit.template`.
+///
+/// Returns `Ok(None)` if no template is configured. Placeholders are
+/// left as they are, see [`expand_commit_template`].
+pub fn load_commit_template(
+	repo_path: &RepoPath,
+) -> Result<Option<String>> {
+	scope_time!("load_commit_template");
+
+	let repo = repo(repo_path)?;
+
+	let Some(path) =
+		get_config_string_repo(&repo, "commit.template")?
+	else {
+		return Ok(None);
+	};
+
+	let path = shellexpand::full(path.as_str())?;
+	let path = PathBuf::from_str(path.as_ref())
+		.map_err(|_| Error::PathString)?;
+
+	Ok(Some(read_to_string(path)?))
+}
+
+/// Replaces `{branch}` in `template` with `branch_name` and `{ticket}`
+/// with the ticket id (like `ABC-123`) found in the branch name.
+///
+/// `{ticket}` expands to an empty string if the branch name does not
+/// contain a ticket id.
+pub fn expand_commit_template(
+	template: &str,
+	branch_name: &str,
+) -> String {
+	template.replace("{branch}", branch_name).replace(
+		"{ticket}",
+		ticket_from_branch(branch_name).unwrap_or(""),
+	)
+}
+
+/// finds the first `<letters>-<digits>` word in `branch`
+fn ticket_from_branch(branch: &str) -> Option<&str> {
+	let bytes = branch.as_bytes();
+
+	let mut start = 0;
+	while start < bytes.len() {
+		let word_start =
+			start == 0 || !bytes[start - 1].is_ascii_alphanumeric();
+
+		if word_start && bytes[start].is_ascii_alphabetic() {
+			let letters = bytes[start..]
+				.iter()
+				.take_while(|b| b.is_ascii_alphabetic())
+				.count();
+			let dash = start + letters;
+
+			if bytes.get(dash) == Some(&b'-') {
+				let digits = bytes[dash + 1..]
+					.iter()
+					.take_while(|b| b.is_ascii_digit())
+					.count();
+				let end = dash + 1 + digits;
+
+				if digits > 0
+					&& bytes
+						.get(end)
+						.map_or(true, |b| !b.is_ascii_alphanumeric())
+				{
+					return Some(&branch[start..end]);
+				}
+			}
+
+			start = dash;
+		} else {
+			start += 1;
+		}
+	}
+
+	None
+}
+
+/// result of [`verify_commit_signature`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+	/// valid signature
+	Good {
+		/// user id (gpg) or principal (ssh) of the signing key
+		signer: String,
+	},
+	/// signature does not match the commit
+	Bad,
+	/// signature could not be checked, e.g. because the key is unknown
+	Unknown,
+	/// commit is not signed
+	Unsigned,
+}
+
+/// Verifies the gpg or ssh signature of a commit by running the
+/// programs configured in `gpg.program` and `gpg.ssh.program`.
+///
+/// Like git, ssh signatures are checked against the keys in
+/// `gpg.ssh.allowedSignersFile` and are [`SignatureStatus::Unknown`]
+/// if it is not configured.
+pub fn verify_commit_signature(
+	repo_path: &RepoPath,
+	commit_id: CommitId,
+) -> Result<SignatureStatus> {
+	scope_time!("verify_commit_signature");
+
+	let repo = repo(repo_path)?;
+
+	let (signature, content) =
+		match repo.extract_signature(&commit_id.get_oid(), None) {
+			Ok(extracted) => extracted,
+			Err(e) if e.code() == ErrorCode::NotFound => {
+				return Ok(SignatureStatus::Unsigned);
+			}
+			Err(e) => return Err(e.into()),
+		};
+
+	if signature.starts_with(SSH_SIGNATURE_HEADER) {
+		verify_ssh_signature(&repo, &signature, &content)
+	} else if signature.starts_with(PGP_SIGNATURE_HEADER) {
+		verify_gpg_signature(&repo, &signature, &content)
+	} else {
+		Ok(SignatureStatus::Unknown)
+	}
+}
+
+fn verify_gpg_signature(
+	repo: &Repository,
+	signature: &[u8],
+	content: &[u8],
+) -> Result<SignatureStatus> {
+	let signature_file = temp_file_with(signature)?;
+
+	let output = run_with_stdin(
+		Command::new(gpg_program(repo)?)
+			.args(["--status-fd=1", "--verify"])
+			.arg(signature_file.path())
+			.arg("-"),
+		content,
+	)?;
+
+	Ok(parse_gpg_status(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// interprets the `--status-fd` output of `gpg --verify`
+fn parse_gpg_status(status: &str) -> SignatureStatus {
+	for line in status.lines() {
+		let Some(line) = line.strip_prefix("[GNUPG:] ") else {
+			continue;
+		};
+
+		// `GOODSIG <key id> <user id>`
+		let mut parts = line.splitn(3, ' ');
+		match parts.next() {
+			Some("GOODSIG") => {
+				return SignatureStatus::Good {
+					signer: parts.nth(1).unwrap_or_default().into(),
+				};
+			}
+			Some("BADSIG") => return SignatureStatus::Bad,
+			_ => (),
+		}
+	}
+
+	SignatureStatus::Unknown
+}
+
+fn verify_ssh_signature(
+	repo: &Repository,
+	signature: &[u8],
+	content: &[u8],
+) -> Result<SignatureStatus> {
+	let Some(allowed_signers) =
+		get_config_string_repo(repo, "gpg.ssh.allowedSignersFile")?
+	else {
+		return Ok(SignatureStatus::Unknown);
+	};
+	let allowed_signers = shellexpand::full(&allowed_signers)?;
+
+	let program = ssh_program(repo)?;
+
+	let signature_file = temp_file_with(signature)?;
+
+	let principals = Command::new(&program)
+		.args(["-Y", "find-principals", "-f"])
+		.arg(allowed_signers.as_ref())
+		.arg("-s")
+		.arg(signature_file.path())
+		.output()?;
+	let principal = String::from_utf8_lossy(&principals.stdout)
+		.lines()
+		.next()
+		.filter(|_| principals.status.success())
+		.map(String::from);
+
+	let Some(principal) = principal else {
+		// no allowed signer, only check if the signature is valid
+		let check = run_with_stdin(
+			Command::new(&program)
+				.args(["-Y", "check-novalidate", "-n", "git", "-s"])
+				.arg(signature_file.path()),
+			content,
+		)?;
+
+		return Ok(if check.status.success() {
+			SignatureStatus::Unknown
+		} else {
+			SignatureStatus::Bad
+		});
+	};
+
+	let verify = run_with_stdin(
+		Command::new(&program)
+			.args(["-Y", "verify", "-n", "git", "-f"])
+			.arg(allowed_signers.as_ref())
+			.arg("-I")
+			.arg(&principal)
+			.arg("-s")
+			.arg(signature_file.path()),
+		content,
+	)?;
+
+	Ok(if verify.status.success() {
+		SignatureStatus::Good { signer: principal }
+	} else {
+		SignatureStatus::Bad
+	})
+}
+
+fn temp_file_with(content: &[u8]) -> Result<NamedTempFile> {
+	let mut file = NamedTempFile::new()?;
+	file.write_all(content)?;
+	file.flush()?;
+
+	Ok(file)
+}
+
+fn run_with_stdin(
+	command: &mut Command,
+	input: &[u8],
+) -> Result<Output> {
+	let mut child = command
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()?;
+
+	if let Some(mut stdin) = child.stdin.take() {
+		stdin.write_all(input)?;
+	}
+
+	Ok(child.wait_with_output()?)
+}
+
 #[cfg(test)]
 mod tests {
 
-	use crate::error::Result;
+	use crate::error::{Error, Result};
 	use crate::sync::tags::Tag;
-	use crate::sync::RepoPath;
 	use crate::sync::{
-		commit, get_commit_details, get_commit_files, stage_add_file,
+		commit,
+		diff::get_diff,
+		get_commit_details, get_commit_files, stage_add_file,
+		stage_hunk,
 		tags::get_tags,
-		tests::{get_statuses, repo_init, repo_init_empty},
-		utils::get_head,
+		tests::{
+			get_statuses, repo_init, repo_init_empty,
+			write_commit_file,
+		},
+		utils::{get_head, repo_read_file},
 		LogWalker,
 	};
-	use commit::{amend, tag_commit};
-	use git2::Repository;
-	use std::{fs::File, io::Write, path::Path};
+	use crate::sync::{CommitId, RepoPath};
+	use commit::{
+		add_trailer, amend, amend_commit_author,
+		expand_commit_template, load_commit_template,
+		parse_gpg_status, parse_trailers, sign_off_message,
+		squash_last, tag_commit, verify_commit_signature,
+		SignatureStatus, Trailer,
+	};
+	use git2::{Repository, Time};
+	use std::{
+		fs::File,
+		io::Write,
+		path::{Path, PathBuf},
+		process::{Command, Stdio},
+	};
 
 	fn count_commits(repo: &Repository, max: usize) -> usize {
 		let mut items = Vec::new();
@@ -162,11 +884,62 @@ mod tests {
 
 		assert_eq!(get_statuses(repo_path), (0, 1));
 
-		commit(repo_path, "commit msg").unwrap();
+		commit(repo_path, "commit msg", false).unwrap();
 
 		assert_eq!(get_statuses(repo_path), (0, 0));
 	}
 
+	#[test]
+	fn test_commit_only_staged_hunks() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| format!("line{i}"))
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "a.txt", &content, "c1");
+
+		let staged = content.replace("line2\n", "x\n");
+		let changed = staged.replace("line15\n", "y\n");
+		File::create(root.join("a.txt"))?
+			.write_all(changed.as_bytes())?;
+
+		let diff = get_diff(repo_path, "a.txt", false, None)?;
+		assert_eq!(diff.hunks.len(), 2);
+		stage_hunk(
+			repo_path,
+			"a.txt",
+			diff.hunks[0].header_hash,
+			None,
+		)?;
+
+		let id = commit(repo_path, "staged hunk", false)?;
+
+		let tree = repo.find_commit(id.into())?.tree()?;
+		let blob =
+			tree.get_path(Path::new("a.txt"))?.to_object(&repo)?;
+		assert_eq!(
+			blob.as_blob().map(git2::Blob::content),
+			Some(staged.as_bytes())
+		);
+
+		assert_eq!(repo_read_file(&repo, "a.txt")?, changed);
+		assert_eq!(get_statuses(repo_path), (1, 0));
+
+		let diff = get_diff(repo_path, "a.txt", false, None)?;
+		assert_eq!(diff.hunks.len(), 1);
+		assert!(diff.hunks[0]
+			.lines
+			.iter()
+			.any(|line| line.content.trim_end() == "y"));
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_commit_in_empty_repo() {
 		let file_path = Path::new("foo");
@@ -188,7 +961,7 @@ mod tests {
 
 		assert_eq!(get_statuses(repo_path), (0, 1));
 
-		commit(repo_path, "commit msg").unwrap();
+		commit(repo_path, "commit msg", false).unwrap();
 
 		assert_eq!(get_statuses(repo_path), (0, 0));
 	}
@@ -205,7 +978,7 @@ mod tests {
 		File::create(root.join(file_path1))?.write_all(b"test1")?;
 
 		stage_add_file(repo_path, file_path1)?;
-		let id = commit(repo_path, "commit msg")?;
+		let id = commit(repo_path, "commit msg", false)?;
 
 		assert_eq!(count_commits(&repo, 10), 1);
 
@@ -213,7 +986,7 @@ mod tests {
 
 		stage_add_file(repo_path, file_path2)?;
 
-		let new_id = amend(repo_path, id, "amended")?;
+		let new_id = amend(repo_path, id, "amended", false)?;
 
 		assert_eq!(count_commits(&repo, 10), 1);
 
@@ -232,49 +1005,170 @@ mod tests {
 	}
 
 	#[test]
-	fn test_tag() -> Result<()> {
+	fn test_commit_sign_off() -> Result<()> {
 		let file_path = Path::new("foo");
-		let (_td, repo) = repo_init_empty().unwrap();
+		let (_td, repo) = repo_init_empty()?;
 		let root = repo.path().parent().unwrap();
 		let repo_path: &RepoPath =
 			&root.as_os_str().to_str().unwrap().into();
 
-		File::create(root.join(file_path))?
-			.write_all(b"test\nfoo")?;
-
+		File::create(root.join(file_path))?.write_all(b"test")?;
 		stage_add_file(repo_path, file_path)?;
 
-		let new_id = commit(repo_path, "commit msg")?;
+		let id = commit(repo_path, "subject\n\nbody", true)?;
 
-		tag_commit(repo_path, &new_id, "tag", None)?;
+		let message = |id: CommitId| -> Result<String> {
+			Ok(repo
+				.find_commit(id.into())?
+				.message()
+				.unwrap_or_default()
+				.to_string())
+		};
 
 		assert_eq!(
-			get_tags(repo_path).unwrap()[&new_id],
-			vec![Tag::new("tag")]
+			message(id)?,
+			"subject\n\nbody\n\nSigned-off-by: name <email>"
 		);
 
-		assert!(matches!(
-			tag_commit(repo_path, &new_id, "tag", None),
-			Err(_)
-		));
+		let id = amend(repo_path, id, &message(id)?, true)?;
 
 		assert_eq!(
-			get_tags(repo_path).unwrap()[&new_id],
-			vec![Tag::new("tag")]
+			message(id)?,
+			"subject\n\nbody\n\nSigned-off-by: name <email>"
 		);
 
-		tag_commit(repo_path, &new_id, "second-tag", None)?;
+		Ok(())
+	}
+
+	#[test]
+	fn test_sign_off_message() {
+		let signature =
+			git2::Signature::now("name", "email").unwrap();
 
 		assert_eq!(
-			get_tags(repo_path).unwrap()[&new_id],
-			vec![Tag::new("second-tag"), Tag::new("tag")]
+			sign_off_message("subject\n", &signature),
+			"subject\n\nSigned-off-by: name <email>"
+		);
+		assert_eq!(
+			sign_off_message(
+				"subject\n\nCo-authored-by: other <other>",
+				&signature
+			),
+			"subject\n\nCo-authored-by: other <other>\nSigned-off-by: name <email>"
 		);
+		assert_eq!(
+			sign_off_message(
+				"subject\n\nSigned-off-by: name <email>\n",
+				&signature
+			),
+			"subject\n\nSigned-off-by: name <email>"
+		);
+		assert_eq!(
+			sign_off_message("", &signature),
+			"Signed-off-by: name <email>"
+		);
+	}
 
-		Ok(())
+	fn trailer(key: &str, value: &str) -> Trailer {
+		Trailer {
+			key: key.to_string(),
+			value: value.to_string(),
+		}
 	}
 
 	#[test]
-	fn test_tag_with_message() -> Result<()> {
+	fn test_parse_trailers() {
+		assert!(parse_trailers("subject").is_empty());
+		assert!(parse_trailers("Fixes: subject\n").is_empty());
+		assert!(parse_trailers("subject\n\nsome body").is_empty());
+		assert!(parse_trailers(
+			"subject\n\nAcked-by: a\nnot a trailer\n"
+		)
+		.is_empty());
+
+		assert_eq!(
+			parse_trailers(
+				"subject\n\nbody: not\nat the end\n\n\nSigned-off-by: a <a>\nCo-authored-by: b\n  <b>\n"
+			),
+			vec![
+				trailer("Signed-off-by", "a <a>"),
+				trailer("Co-authored-by", "b <b>"),
+			]
+		);
+	}
+
+	#[test]
+	fn test_add_trailer() {
+		assert_eq!(
+			add_trailer(
+				"subject\n\nbody\n",
+				"Co-authored-by",
+				"b <b>"
+			),
+			"subject\n\nbody\n\nCo-authored-by: b <b>"
+		);
+		assert_eq!(
+			add_trailer(
+				"subject\n\nSigned-off-by: a <a>",
+				"Co-authored-by",
+				"b <b>"
+			),
+			"subject\n\nSigned-off-by: a <a>\nCo-authored-by: b <b>"
+		);
+		assert_eq!(
+			add_trailer(
+				"subject\n\nco-authored-by: b <b>",
+				"Co-authored-by",
+				"b <b>"
+			),
+			"subject\n\nco-authored-by: b <b>"
+		);
+	}
+
+	#[test]
+	fn test_tag() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		File::create(root.join(file_path))?
+			.write_all(b"test\nfoo")?;
+
+		stage_add_file(repo_path, file_path)?;
+
+		let new_id = commit(repo_path, "commit msg", false)?;
+
+		tag_commit(repo_path, &new_id, "tag", None, false)?;
+
+		assert_eq!(
+			get_tags(repo_path).unwrap()[&new_id],
+			vec![Tag::new("tag")]
+		);
+
+		assert!(matches!(
+			tag_commit(repo_path, &new_id, "tag", None, false),
+			Err(_)
+		));
+
+		assert_eq!(
+			get_tags(repo_path).unwrap()[&new_id],
+			vec![Tag::new("tag")]
+		);
+
+		tag_commit(repo_path, &new_id, "second-tag", None, false)?;
+
+		assert_eq!(
+			get_tags(repo_path).unwrap()[&new_id],
+			vec![Tag::new("second-tag"), Tag::new("tag")]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_tag_with_message() -> Result<()> {
 		let file_path = Path::new("foo");
 		let (_td, repo) = repo_init_empty().unwrap();
 		let root = repo.path().parent().unwrap();
@@ -286,9 +1180,15 @@ mod tests {
 
 		stage_add_file(repo_path, file_path)?;
 
-		let new_id = commit(repo_path, "commit msg")?;
+		let new_id = commit(repo_path, "commit msg", false)?;
 
-		tag_commit(repo_path, &new_id, "tag", Some("tag-message"))?;
+		tag_commit(
+			repo_path,
+			&new_id,
+			"tag",
+			Some("tag-message"),
+			false,
+		)?;
 
 		assert_eq!(
 			get_tags(repo_path).unwrap()[&new_id][0]
@@ -301,6 +1201,74 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn test_tag_annotated_object() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let head = get_head(repo_path)?;
+
+		let tag_id = tag_commit(
+			repo_path,
+			&head,
+			"v1",
+			Some("release"),
+			false,
+		)?;
+
+		let tag = repo.find_tag(tag_id.into())?;
+		assert_eq!(tag.name(), Some("v1"));
+		assert_eq!(tag.message(), Some("release"));
+		assert_eq!(tag.target_id(), head.get_oid());
+
+		let tagger = tag.tagger().unwrap();
+		assert_eq!(tagger.name(), Some("name"));
+		assert_eq!(tagger.email(), Some("email"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_tag_lightweight() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let head = get_head(repo_path)?;
+
+		let id = tag_commit(repo_path, &head, "v1", None, false)?;
+
+		assert_eq!(id, head);
+		assert!(repo.find_tag(id.into()).is_err());
+		assert_eq!(
+			repo.find_reference("refs/tags/v1")?.target(),
+			Some(head.get_oid())
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_tag_sign_needs_message() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let head = get_head(repo_path)?;
+
+		assert!(matches!(
+			tag_commit(repo_path, &head, "v1", None, true),
+			Err(Error::Sign(_))
+		));
+		assert!(repo.find_reference("refs/tags/v1").is_err());
+
+		Ok(())
+	}
+
 	/// Beware: this test has to be run with a `$HOME/.gitconfig` that has
 	/// `user.email` not set. Otherwise, git falls back to the value of
 	/// `user.email` in `$HOME/.gitconfig` and this test fails.
@@ -322,13 +1290,13 @@ mod tests {
 
 		repo.config()?.remove("user.email")?;
 
-		let error = commit(repo_path, "commit msg");
+		let error = commit(repo_path, "commit msg", false);
 
 		assert!(matches!(error, Err(_)));
 
 		repo.config()?.set_str("user.email", "email")?;
 
-		let success = commit(repo_path, "commit msg");
+		let success = commit(repo_path, "commit msg", false);
 
 		assert!(matches!(success, Ok(_)));
 		assert_eq!(count_commits(&repo, 10), 1);
@@ -358,7 +1326,7 @@ mod tests {
 
 		repo.config()?.remove("user.name")?;
 
-		let mut success = commit(repo_path, "commit msg");
+		let mut success = commit(repo_path, "commit msg", false);
 
 		assert!(matches!(success, Ok(_)));
 		assert_eq!(count_commits(&repo, 10), 1);
@@ -371,7 +1339,7 @@ mod tests {
 
 		repo.config()?.set_str("user.name", "name")?;
 
-		success = commit(repo_path, "commit msg");
+		success = commit(repo_path, "commit msg", false);
 
 		assert!(matches!(success, Ok(_)));
 		assert_eq!(count_commits(&repo, 10), 2);
@@ -384,4 +1352,500 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_amend_author() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		File::create(root.join("foo"))?.write_all(b"a\n")?;
+		stage_add_file(repo_path, Path::new("foo"))?;
+		let first = commit(repo_path, "first", false)?;
+
+		File::create(root.join("foo"))?.write_all(b"b\n")?;
+		stage_add_file(repo_path, Path::new("foo"))?;
+		let id = commit(repo_path, "second", false)?;
+
+		let when = Time::new(1_600_000_000, 60);
+		let new_id = amend_commit_author(
+			repo_path,
+			id,
+			"other",
+			"other@example.com",
+			Some(when),
+		)?;
+
+		assert_eq!(get_head(repo_path)?, new_id);
+
+		let old = repo.find_commit(id.into())?;
+		let new = repo.find_commit(new_id.into())?;
+
+		assert_eq!(new.author().name(), Some("other"));
+		assert_eq!(new.author().email(), Some("other@example.com"));
+		assert_eq!(new.author().when(), when);
+		assert_ne!(old.author().when(), when);
+		assert_eq!(new.tree_id(), old.tree_id());
+		assert_eq!(
+			new.parent_ids().collect::<Vec<_>>(),
+			vec![first.get_oid()]
+		);
+		assert_eq!(new.message(), old.message());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_amend_author_keeps_time() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let id = get_head(repo_path)?;
+		let new_id = amend_commit_author(
+			repo_path, id, "other", "mail", None,
+		)?;
+
+		let old = repo.find_commit(id.into())?;
+		let new = repo.find_commit(new_id.into())?;
+
+		assert_eq!(new.author().name(), Some("other"));
+		assert_eq!(new.author().when(), old.author().when());
+		assert_eq!(new.tree_id(), old.tree_id());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_squash_last() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let base = write_commit_file(&repo, "a.txt", "a", "c1");
+
+		let sig = git2::Signature::new(
+			"oldest",
+			"old@example.com",
+			&Time::new(1, 0),
+		)?;
+		repo.commit(
+			Some("HEAD"),
+			&sig,
+			&sig,
+			"wip 1",
+			&repo.find_commit(base.into())?.tree()?,
+			&[&repo.find_commit(base.into())?],
+		)?;
+		write_commit_file(&repo, "a.txt", "b", "wip 2");
+		let head = write_commit_file(&repo, "b.txt", "c", "wip 3");
+
+		let tree_id = repo.find_commit(head.into())?.tree_id();
+		assert_eq!(count_commits(&repo, 10), 5);
+
+		let id = squash_last(repo_path, 3, "squashed")?;
+
+		assert_eq!(get_head(repo_path)?, id);
+		assert_eq!(count_commits(&repo, 10), 3);
+
+		let commit = repo.find_commit(id.into())?;
+		assert_eq!(commit.message(), Some("squashed"));
+		assert_eq!(commit.tree_id(), tree_id);
+		assert_eq!(
+			commit.parent_ids().collect::<Vec<_>>(),
+			vec![base.into()]
+		);
+		assert_eq!(commit.author().name(), Some("oldest"));
+		assert_eq!(get_statuses(repo_path), (0, 0));
+
+		assert!(squash_last(repo_path, 0, "x").is_err());
+		assert!(squash_last(repo_path, 10, "x").is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_squash_last_refuses_merge() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = write_commit_file(&repo, "a.txt", "a", "c1");
+		let c2 = write_commit_file(&repo, "a.txt", "b", "c2");
+
+		let sig = repo.signature()?;
+		let parents = [
+			repo.find_commit(c2.into())?,
+			repo.find_commit(c1.into())?,
+		];
+		repo.commit(
+			Some("HEAD"),
+			&sig,
+			&sig,
+			"merge",
+			&parents[0].tree()?,
+			&[&parents[0], &parents[1]],
+		)?;
+		let head = write_commit_file(&repo, "a.txt", "c", "c3");
+
+		let msg = squash_last(repo_path, 2, "squashed")
+			.map_err(|e| e.to_string())
+			.err()
+			.unwrap();
+		assert!(msg.contains("merge commit"));
+		assert_eq!(get_head(repo_path)?, head);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_amend_author_not_head() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		File::create(root.join("foo"))?.write_all(b"a\n")?;
+		stage_add_file(repo_path, Path::new("foo"))?;
+		let first = commit(repo_path, "first", false)?;
+
+		File::create(root.join("foo"))?.write_all(b"b\n")?;
+		stage_add_file(repo_path, Path::new("foo"))?;
+		commit(repo_path, "second", false)?;
+
+		assert!(matches!(
+			amend_commit_author(repo_path, first, "a", "b", None),
+			Err(crate::Error::NotHeadCommit)
+		));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_load_commit_template() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert_eq!(load_commit_template(repo_path)?, None);
+
+		let template = root.join("template.txt");
+		File::create(&template)?
+			.write_all(b"[{ticket}] \n\nbranch: {branch}\n")?;
+
+		repo.config()?
+			.set_str("commit.template", template.to_str().unwrap())?;
+
+		let loaded = load_commit_template(repo_path)?.unwrap();
+
+		assert_eq!(loaded, "[{ticket}] \n\nbranch: {branch}\n");
+		assert_eq!(
+			expand_commit_template(&loaded, "feature/ABC-123-login"),
+			"[ABC-123] \n\nbranch: feature/ABC-123-login\n"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_load_commit_template_missing_file() {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		repo.config()
+			.unwrap()
+			.set_str(
+				"commit.template",
+				root.join("missing.txt").to_str().unwrap(),
+			)
+			.unwrap();
+
+		assert!(load_commit_template(repo_path).is_err());
+	}
+
+	#[test]
+	fn test_expand_commit_template_ticket() {
+		assert_eq!(expand_commit_template("{ticket}", "master"), "");
+		assert_eq!(
+			expand_commit_template("{ticket}", "fix-12"),
+			"fix-12"
+		);
+		assert_eq!(
+			expand_commit_template("{ticket}", "v2-x/PROJ-7_typo"),
+			"PROJ-7"
+		);
+		assert_eq!(expand_commit_template("{ticket}", "ab-12c"), "");
+	}
+
+	#[test]
+	fn test_verify_unsigned() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let id = get_head(repo_path)?;
+
+		assert_eq!(
+			verify_commit_signature(repo_path, id)?,
+			SignatureStatus::Unsigned
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_parse_gpg_status() {
+		assert_eq!(
+			parse_gpg_status(
+				"[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF name <mail@example.com>\n[GNUPG:] VALIDSIG 0123\n"
+			),
+			SignatureStatus::Good {
+				signer: String::from("name <mail@example.com>")
+			}
+		);
+		assert_eq!(
+			parse_gpg_status(
+				"[GNUPG:] BADSIG 0123456789ABCDEF name <mail@example.com>\n"
+			),
+			SignatureStatus::Bad
+		);
+		assert_eq!(
+			parse_gpg_status(
+				"[GNUPG:] ERRSIG 0123456789ABCDEF 1 10 00 1 9\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n"
+			),
+			SignatureStatus::Unknown
+		);
+	}
+
+	/// creates an ssh key in `dir` and an allowed signers file
+	/// containing it for `name@example.com`
+	fn ssh_keygen(dir: &Path) -> (PathBuf, PathBuf) {
+		let key = dir.join("key");
+		let keygen = Command::new("ssh-keygen")
+			.args(["-q", "-t", "ed25519", "-N", "", "-f"])
+			.arg(&key)
+			.status()
+			.unwrap();
+		assert!(keygen.success());
+
+		let public_key =
+			std::fs::read_to_string(key.with_extension("pub"))
+				.unwrap();
+		let allowed_signers = dir.join("allowed_signers");
+		std::fs::write(
+			&allowed_signers,
+			format!("name@example.com {public_key}"),
+		)
+		.unwrap();
+
+		(key, allowed_signers)
+	}
+
+	/// signs `content` with the ssh key at `key` like git does
+	fn ssh_sign(key: &Path, content: &str) -> String {
+		let mut child = Command::new("ssh-keygen")
+			.args(["-Y", "sign", "-n", "git", "-f"])
+			.arg(key)
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::null())
+			.spawn()
+			.unwrap();
+
+		child
+			.stdin
+			.take()
+			.unwrap()
+			.write_all(content.as_bytes())
+			.unwrap();
+
+		let output = child.wait_with_output().unwrap();
+		assert!(output.status.success());
+
+		String::from_utf8(output.stdout).unwrap()
+	}
+
+	#[test]
+	fn test_verify_ssh_signature() -> Result<()> {
+		let (td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let (key, allowed_signers) = ssh_keygen(td.path());
+
+		let head = repo.head()?.peel_to_commit()?;
+		let tree = head.tree()?;
+		let sig = repo.signature()?;
+
+		let signed_commit = |content: &str, signed: &str| {
+			let signature = ssh_sign(&key, signed);
+			let id = repo
+				.commit_signed(content, &signature, None)
+				.unwrap();
+			crate::sync::CommitId::new(id)
+		};
+
+		let buffer = repo.commit_create_buffer(
+			&sig,
+			&sig,
+			"signed",
+			&tree,
+			&[&head],
+		)?;
+		let buffer = buffer.as_str().unwrap();
+
+		let good = signed_commit(buffer, buffer);
+		let bad = signed_commit(buffer, "something else");
+
+		assert_eq!(
+			verify_commit_signature(repo_path, good)?,
+			SignatureStatus::Unknown
+		);
+
+		repo.config()?.set_str(
+			"gpg.ssh.allowedSignersFile",
+			allowed_signers.to_str().unwrap(),
+		)?;
+
+		assert_eq!(
+			verify_commit_signature(repo_path, good)?,
+			SignatureStatus::Good {
+				signer: String::from("name@example.com")
+			}
+		);
+		assert_eq!(
+			verify_commit_signature(repo_path, bad)?,
+			SignatureStatus::Bad
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_ssh_signed() -> Result<()> {
+		let file_path = Path::new("foo");
+		let (td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let (key, allowed_signers) = ssh_keygen(td.path());
+
+		let mut config = repo.config()?;
+		config.set_bool("commit.gpgsign", true)?;
+		config.set_str("gpg.format", "ssh")?;
+		config.set_str("user.signingkey", key.to_str().unwrap())?;
+		config.set_str(
+			"gpg.ssh.allowedSignersFile",
+			allowed_signers.to_str().unwrap(),
+		)?;
+
+		File::create(root.join(file_path))?.write_all(b"a")?;
+		stage_add_file(repo_path, file_path)?;
+		let first = commit(repo_path, "first", false)?;
+
+		File::create(root.join(file_path))?.write_all(b"b")?;
+		stage_add_file(repo_path, file_path)?;
+		let second = commit(repo_path, "second", false)?;
+
+		assert_eq!(get_head(repo_path)?, second);
+		assert_eq!(count_commits(&repo, 10), 2);
+		assert!(repo
+			.extract_signature(&second.get_oid(), None)
+			.is_ok());
+		assert_eq!(
+			repo.find_commit(second.into())?.parent_id(0)?,
+			first.get_oid()
+		);
+		assert_eq!(
+			verify_commit_signature(repo_path, second)?,
+			SignatureStatus::Good {
+				signer: String::from("name@example.com")
+			}
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_tag_ssh_signed() -> Result<()> {
+		let (td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let (key, _) = ssh_keygen(td.path());
+
+		let mut config = repo.config()?;
+		config.set_str("gpg.format", "ssh")?;
+		config.set_str("user.signingkey", key.to_str().unwrap())?;
+
+		let head = get_head(repo_path)?;
+
+		let tag_id =
+			tag_commit(repo_path, &head, "v1", Some("signed"), true)?;
+
+		let tag = repo.find_tag(tag_id.into())?;
+		assert_eq!(tag.name(), Some("v1"));
+		assert_eq!(tag.target_id(), head.get_oid());
+		assert_eq!(tag.tagger().unwrap().name(), Some("name"));
+
+		let message = tag.message().unwrap();
+		assert!(message.starts_with("signed\n"));
+		assert!(message.contains("-----BEGIN SSH SIGNATURE-----"));
+
+		assert_eq!(
+			repo.find_reference("refs/tags/v1")?.target(),
+			Some(tag_id.get_oid())
+		);
+		assert!(tag_commit(repo_path, &head, "v1", Some("x"), true)
+			.is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_signing_fails() -> Result<()> {
+		let (td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let head = get_head(repo_path)?;
+
+		let mut config = repo.config()?;
+		config.set_bool("commit.gpgsign", true)?;
+		config.set_str("gpg.format", "ssh")?;
+		config.set_str(
+			"user.signingkey",
+			td.path().join("missing").to_str().unwrap(),
+		)?;
+
+		File::create(root.join("foo"))?.write_all(b"a")?;
+		stage_add_file(repo_path, Path::new("foo"))?;
+
+		assert!(matches!(
+			commit(repo_path, "msg", false),
+			Err(crate::Error::Sign(_))
+		));
+		assert_eq!(get_head(repo_path)?, head);
+
+		config.set_str("gpg.format", "x509")?;
+
+		assert!(matches!(
+			commit(repo_path, "msg", false),
+			Err(crate::Error::Sign(_))
+		));
+
+		Ok(())
+	}
 }
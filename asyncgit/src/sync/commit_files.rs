@@ -3,7 +3,7 @@
 use super::{diff::DiffOptions, CommitId, RepoPath};
 use crate::{
 	error::Result,
-	sync::{get_stashes, repository::repo},
+	sync::{get_stashes, repository::repo, status::renamed_from},
 	StatusItem, StatusItemType,
 };
 use git2::{Diff, Repository};
@@ -18,15 +18,66 @@ pub fn get_commit_files(
 ) -> Result<Vec<StatusItem>> {
 	scope_time!("get_commit_files");
 
+	commit_files(repo_path, id, other, None)
+}
+
+/// line change stats of a commit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CommitStats {
+	///
+	pub files: usize,
+	///
+	pub insertions: usize,
+	///
+	pub deletions: usize,
+}
+
+/// returns the stats of the diff of `id` to its first parent
+pub fn get_commit_stats(
+	repo_path: &RepoPath,
+	id: CommitId,
+) -> Result<CommitStats> {
+	scope_time!("get_commit_stats");
+
+	let repo = repo(repo_path)?;
+	let stats =
+		get_commit_diff(&repo, id, None, None, None)?.stats()?;
+
+	Ok(CommitStats {
+		files: stats.files_changed(),
+		insertions: stats.insertions(),
+		deletions: stats.deletions(),
+	})
+}
+
+/// like `get_commit_files` but only returns files below `path_prefix`,
+/// other trees are not even traversed
+pub fn get_commit_files_under(
+	repo_path: &RepoPath,
+	id: CommitId,
+	other: Option<CommitId>,
+	path_prefix: &str,
+) -> Result<Vec<StatusItem>> {
+	scope_time!("get_commit_files_under");
+
+	commit_files(repo_path, id, other, Some(path_prefix.to_string()))
+}
+
+fn commit_files(
+	repo_path: &RepoPath,
+	id: CommitId,
+	other: Option<CommitId>,
+	pathspec: Option<String>,
+) -> Result<Vec<StatusItem>> {
 	let repo = repo(repo_path)?;
 
 	let diff = if let Some(other) = other {
-		get_compare_commits_diff(&repo, (id, other), None, None)?
+		get_compare_commits_diff(&repo, (id, other), pathspec, None)?
 	} else {
 		get_commit_diff(
 			&repo,
 			id,
-			None,
+			pathspec,
 			None,
 			Some(&get_stashes(repo_path)?.into_iter().collect()),
 		)?
@@ -43,6 +94,7 @@ pub fn get_commit_files(
 					.path()
 					.map(|p| p.to_str().unwrap_or("").to_string())
 					.unwrap_or_default(),
+				old_path: renamed_from(&delta),
 				status,
 			}
 		})
@@ -80,6 +132,10 @@ pub fn get_compare_commits_diff(
 	if let Some(options) = options {
 		opts.context_lines(options.context);
 		opts.ignore_whitespace(options.ignore_whitespace);
+		opts.ignore_whitespace_change(
+			options.ignore_whitespace_change,
+		);
+		opts.ignore_whitespace_eol(options.ignore_whitespace_eol);
 		opts.interhunk_lines(options.interhunk_lines);
 	}
 	if let Some(p) = &pathspec {
@@ -121,6 +177,10 @@ pub(crate) fn get_commit_diff<'a>(
 	if let Some(options) = options {
 		opts.context_lines(options.context);
 		opts.ignore_whitespace(options.ignore_whitespace);
+		opts.ignore_whitespace_change(
+			options.ignore_whitespace_change,
+		);
+		opts.ignore_whitespace_eol(options.ignore_whitespace_eol);
 		opts.interhunk_lines(options.interhunk_lines);
 	}
 	if let Some(p) = &pathspec {
@@ -156,17 +216,24 @@ pub(crate) fn get_commit_diff<'a>(
 
 #[cfg(test)]
 mod tests {
-	use super::get_commit_files;
+	use super::{
+		get_commit_files, get_commit_files_under, get_commit_stats,
+		CommitStats,
+	};
 	use crate::{
 		error::Result,
 		sync::{
 			commit, stage_add_file, stash_save,
-			tests::{get_statuses, repo_init},
+			tests::{get_statuses, repo_init, write_commit_file},
 			RepoPath,
 		},
 		StatusItemType,
 	};
-	use std::{fs::File, io::Write, path::Path};
+	use std::{
+		fs::{self, File},
+		io::Write,
+		path::Path,
+	};
 
 	#[test]
 	fn test_smoke() -> Result<()> {
@@ -181,7 +248,7 @@ mod tests {
 
 		stage_add_file(repo_path, file_path)?;
 
-		let id = commit(repo_path, "commit msg")?;
+		let id = commit(repo_path, "commit msg", false)?;
 
 		let diff = get_commit_files(repo_path, id, None)?;
 
@@ -223,7 +290,7 @@ mod tests {
 
 		File::create(root.join(file_path1))?.write_all(b"test")?;
 		stage_add_file(repo_path, file_path1)?;
-		commit(repo_path, "c1")?;
+		commit(repo_path, "c1", false)?;
 
 		File::create(root.join(file_path1))?
 			.write_all(b"modified")?;
@@ -241,4 +308,60 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_files_under_prefix() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		fs::create_dir(root.join("src"))?;
+		fs::create_dir(root.join("docs"))?;
+		File::create(root.join("src/a"))?.write_all(b"a")?;
+		File::create(root.join("docs/b"))?.write_all(b"b")?;
+
+		stage_add_file(repo_path, Path::new("src/a"))?;
+		stage_add_file(repo_path, Path::new("docs/b"))?;
+
+		let id = commit(repo_path, "commit msg", false)?;
+
+		assert_eq!(get_commit_files(repo_path, id, None)?.len(), 2);
+
+		let files =
+			get_commit_files_under(repo_path, id, None, "src/")?;
+
+		assert_eq!(files.len(), 1);
+		assert_eq!(files[0].path, "src/a");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_stats() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "1\n2\n", "c1");
+		write_commit_file(&repo, "b.txt", "x\n", "c2");
+
+		File::create(root.join("a.txt"))?.write_all(b"1\n3\n4\n")?;
+		File::create(root.join("c.txt"))?.write_all(b"5\n")?;
+		stage_add_file(repo_path, Path::new("a.txt"))?;
+		stage_add_file(repo_path, Path::new("c.txt"))?;
+		let id = commit(repo_path, "c3", false)?;
+
+		assert_eq!(
+			get_commit_stats(repo_path, id)?,
+			CommitStats {
+				files: 2,
+				insertions: 3,
+				deletions: 1,
+			}
+		);
+
+		Ok(())
+	}
 }
@@ -159,10 +159,10 @@ mod tests {
 
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		let c1 = commit(repo_path, "commit1").unwrap();
+		let c1 = commit(repo_path, "commit1", false).unwrap();
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		let c2 = commit(repo_path, "commit2").unwrap();
+		let c2 = commit(repo_path, "commit2", false).unwrap();
 
 		let res = get_commits_info(repo_path, &[c2, c1], 50).unwrap();
 
@@ -184,7 +184,7 @@ mod tests {
 
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		let c1 = commit(repo_path, "subject\nbody").unwrap();
+		let c1 = commit(repo_path, "subject\nbody", false).unwrap();
 
 		let res = get_commits_info(repo_path, &[c1], 50).unwrap();
 
@@ -206,7 +206,7 @@ mod tests {
 		stage_add_file(repo_path, file_path).unwrap();
 
 		let msg = invalidstring::invalid_utf8("test msg");
-		commit(repo_path, msg.as_str()).unwrap();
+		commit(repo_path, msg.as_str(), false).unwrap();
 
 		let res = get_commits_info(
 			repo_path,
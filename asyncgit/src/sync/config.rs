@@ -2,7 +2,7 @@
 #![allow(clippy::use_self)]
 
 use crate::error::Result;
-use git2::Repository;
+use git2::{ConfigLevel, ErrorCode, Repository};
 use scopetime::scope_time;
 use serde::{Deserialize, Serialize};
 
@@ -101,6 +101,42 @@ pub fn get_config_string_repo(
 	}
 }
 
+/// sets `key` to `value` in the config file of `level`
+///
+/// Like libgit2 this fails if there is no config file for `level`
+/// (e.g. no `~/.gitconfig` for `ConfigLevel::Global`).
+pub fn set_config_string(
+	repo_path: &RepoPath,
+	key: &str,
+	value: &str,
+	level: ConfigLevel,
+) -> Result<()> {
+	scope_time!("set_config_string");
+
+	let repo = repo(repo_path)?;
+	repo.config()?.open_level(level)?.set_str(key, value)?;
+
+	Ok(())
+}
+
+/// removes `key` from the config file of `level`, a missing `key` is
+/// no error
+pub fn unset_config(
+	repo_path: &RepoPath,
+	key: &str,
+	level: ConfigLevel,
+) -> Result<()> {
+	scope_time!("unset_config");
+
+	let repo = repo(repo_path)?;
+	let mut cfg = repo.config()?.open_level(level)?;
+
+	match cfg.remove(key) {
+		Err(e) if e.code() != ErrorCode::NotFound => Err(e.into()),
+		_ => Ok(()),
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -126,4 +162,58 @@ mod tests {
 		assert!(good_cfg.is_ok());
 		assert!(good_cfg.unwrap().is_some());
 	}
+
+	#[test]
+	fn test_set_unset_config() {
+		let (_td, repo) = repo_init().unwrap();
+		let path = repo.path();
+		let rpath: &RepoPath =
+			&path.as_os_str().to_str().unwrap().into();
+
+		set_config_string(
+			rpath,
+			"gitui.test",
+			"foo",
+			ConfigLevel::Local,
+		)
+		.unwrap();
+		assert_eq!(
+			get_config_string(rpath, "gitui.test")
+				.unwrap()
+				.as_deref(),
+			Some("foo")
+		);
+		assert_eq!(
+			repo.config()
+				.unwrap()
+				.open_level(ConfigLevel::Local)
+				.unwrap()
+				.get_string("gitui.test")
+				.unwrap(),
+			"foo"
+		);
+
+		set_config_string(
+			rpath,
+			"gitui.test",
+			"bar",
+			ConfigLevel::Local,
+		)
+		.unwrap();
+		assert_eq!(
+			get_config_string(rpath, "gitui.test")
+				.unwrap()
+				.as_deref(),
+			Some("bar")
+		);
+
+		unset_config(rpath, "gitui.test", ConfigLevel::Local)
+			.unwrap();
+		assert!(get_config_string(rpath, "gitui.test")
+			.unwrap()
+			.is_none());
+
+		unset_config(rpath, "gitui.test", ConfigLevel::Local)
+			.unwrap();
+	}
 }
@@ -0,0 +1,316 @@
+//! listing merge conflicts and resolving them by picking one side
+
+use super::{repository::repo, utils::work_dir, RepoPath};
+use crate::error::{Error, Result};
+use git2::{Index, IndexConflict, Repository};
+use scopetime::scope_time;
+use std::{fs, path::Path};
+
+/// side of a conflict to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+	/// the version of the current branch (stage 2)
+	Ours,
+	/// the version being merged in (stage 3)
+	Theirs,
+}
+
+/// kind of a conflict as `git status` reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+	///
+	BothModified,
+	///
+	BothAdded,
+	///
+	BothDeleted,
+	///
+	DeletedByUs,
+	///
+	DeletedByThem,
+	///
+	AddedByUs,
+	///
+	AddedByThem,
+}
+
+/// conflicted path and the index stages present for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictEntry {
+	///
+	pub path: String,
+	/// common ancestor (stage 1)
+	pub base: bool,
+	/// current branch (stage 2)
+	pub ours: bool,
+	/// branch being merged in (stage 3)
+	pub theirs: bool,
+}
+
+impl ConflictEntry {
+	///
+	pub const fn kind(&self) -> ConflictKind {
+		match (self.base, self.ours, self.theirs) {
+			(true, true, true) => ConflictKind::BothModified,
+			(true, false, false) => ConflictKind::BothDeleted,
+			(true, false, true) => ConflictKind::DeletedByUs,
+			(true, true, false) => ConflictKind::DeletedByThem,
+			(false, true, false) => ConflictKind::AddedByUs,
+			(false, false, true) => ConflictKind::AddedByThem,
+			(false, _, _) => ConflictKind::BothAdded,
+		}
+	}
+}
+
+/// returns all conflicted paths sorted by path
+pub fn get_conflicts(
+	repo_path: &RepoPath,
+) -> Result<Vec<ConflictEntry>> {
+	scope_time!("get_conflicts");
+
+	let repo = repo(repo_path)?;
+
+	let mut entries = Vec::new();
+	for conflict in repo.index()?.conflicts()? {
+		let conflict = conflict?;
+
+		let Some(path) = conflict
+			.our
+			.as_ref()
+			.or(conflict.their.as_ref())
+			.or(conflict.ancestor.as_ref())
+			.map(|entry| entry.path.clone())
+		else {
+			continue;
+		};
+
+		entries.push(ConflictEntry {
+			path: String::from_utf8(path)?,
+			base: conflict.ancestor.is_some(),
+			ours: conflict.our.is_some(),
+			theirs: conflict.their.is_some(),
+		});
+	}
+
+	entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+	Ok(entries)
+}
+
+/// returns the conflict of `path` if any
+pub(crate) fn find_conflict(
+	index: &Index,
+	path: &str,
+) -> Result<Option<IndexConflict>> {
+	for conflict in index.conflicts()? {
+		let conflict = conflict?;
+
+		let conflict_path = conflict
+			.our
+			.as_ref()
+			.or(conflict.their.as_ref())
+			.or(conflict.ancestor.as_ref())
+			.map(|entry| entry.path.as_slice());
+
+		if conflict_path == Some(path.as_bytes()) {
+			return Ok(Some(conflict));
+		}
+	}
+
+	Ok(None)
+}
+
+/// resolves the conflict of `path` by writing the `resolution` side to
+/// the workdir and staging it
+///
+/// If that side deleted the file it is removed from workdir and index.
+pub fn resolve_conflict(
+	repo_path: &RepoPath,
+	path: &str,
+	resolution: Resolution,
+) -> Result<()> {
+	scope_time!("resolve_conflict");
+
+	let repo = repo(repo_path)?;
+	let mut index = repo.index()?;
+
+	let conflict = find_conflict(&index, path)?.ok_or_else(|| {
+		Error::Generic(format!("'{path}' is not conflicted"))
+	})?;
+
+	let entry = match resolution {
+		Resolution::Ours => conflict.our,
+		Resolution::Theirs => conflict.their,
+	};
+
+	let file_path = work_dir(&repo)?.join(path);
+
+	if let Some(entry) = entry {
+		write_blob(&repo, entry.id, &file_path)?;
+		index.add_path(Path::new(path))?;
+	} else {
+		if file_path.exists() {
+			fs::remove_file(&file_path)?;
+		}
+		index.remove_path(Path::new(path))?;
+	}
+
+	index.write()?;
+
+	Ok(())
+}
+
+fn write_blob(
+	repo: &Repository,
+	id: git2::Oid,
+	file_path: &Path,
+) -> Result<()> {
+	let blob = repo.find_blob(id)?;
+
+	if let Some(parent) = file_path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	fs::write(file_path, blob.content())?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		checkout_branch, create_branch, merge_branch,
+		stage_addremoved,
+		tests::{repo_init, write_commit_file},
+		utils::{repo_read_file, repo_write_file, stage_add_file},
+		BranchType,
+	};
+	use tempfile::TempDir;
+
+	/// creates a merge with `a.txt` and `b.txt` changed on both sides
+	fn conflicted_repo() -> (TempDir, Repository) {
+		let (td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		repo_write_file(&repo, "a.txt", "base a").unwrap();
+		stage_add_file(repo_path, Path::new("a.txt")).unwrap();
+		write_commit_file(&repo, "b.txt", "base b", "c1");
+
+		create_branch(repo_path, "other").unwrap();
+		repo_write_file(&repo, "a.txt", "theirs a").unwrap();
+		stage_add_file(repo_path, Path::new("a.txt")).unwrap();
+		write_commit_file(&repo, "b.txt", "theirs b", "c2");
+
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		repo_write_file(&repo, "a.txt", "ours a").unwrap();
+		stage_add_file(repo_path, Path::new("a.txt")).unwrap();
+		write_commit_file(&repo, "b.txt", "ours b", "c3");
+
+		merge_branch(repo_path, "other", BranchType::Local).unwrap();
+
+		(td, repo)
+	}
+
+	#[test]
+	fn test_resolve_ours_and_theirs() {
+		let (_td, repo) = conflicted_repo();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let index = || {
+			let mut index = repo.index().unwrap();
+			index.read(true).unwrap();
+			index
+		};
+
+		assert!(find_conflict(&index(), "a.txt").unwrap().is_some());
+		assert!(find_conflict(&index(), "b.txt").unwrap().is_some());
+
+		resolve_conflict(repo_path, "a.txt", Resolution::Ours)
+			.unwrap();
+
+		assert_eq!(repo_read_file(&repo, "a.txt").unwrap(), "ours a");
+		assert!(find_conflict(&index(), "a.txt").unwrap().is_none());
+		assert!(find_conflict(&index(), "b.txt").unwrap().is_some());
+
+		resolve_conflict(repo_path, "b.txt", Resolution::Theirs)
+			.unwrap();
+
+		assert_eq!(
+			repo_read_file(&repo, "b.txt").unwrap(),
+			"theirs b"
+		);
+		assert!(!index().has_conflicts());
+
+		let index = index();
+		for (path, content) in
+			[("a.txt", "ours a"), ("b.txt", "theirs b")]
+		{
+			let entry = index.get_path(Path::new(path), 0).unwrap();
+			assert_eq!(
+				repo.find_blob(entry.id).unwrap().content(),
+				content.as_bytes()
+			);
+		}
+
+		assert!(resolve_conflict(
+			repo_path,
+			"a.txt",
+			Resolution::Ours
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn test_get_conflicts() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert!(get_conflicts(repo_path).unwrap().is_empty());
+
+		repo_write_file(&repo, "a.txt", "base a").unwrap();
+		stage_add_file(repo_path, Path::new("a.txt")).unwrap();
+		write_commit_file(&repo, "b.txt", "base b", "c1");
+
+		// modifies `a.txt`, deletes `b.txt`
+		create_branch(repo_path, "other").unwrap();
+		fs::remove_file(root.join("b.txt")).unwrap();
+		stage_addremoved(repo_path, Path::new("b.txt")).unwrap();
+		write_commit_file(&repo, "a.txt", "theirs a", "c2");
+
+		// modifies both
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		repo_write_file(&repo, "a.txt", "ours a").unwrap();
+		stage_add_file(repo_path, Path::new("a.txt")).unwrap();
+		write_commit_file(&repo, "b.txt", "ours b", "c3");
+
+		merge_branch(repo_path, "other", BranchType::Local).unwrap();
+
+		let conflicts = get_conflicts(repo_path).unwrap();
+
+		assert_eq!(
+			conflicts,
+			vec![
+				ConflictEntry {
+					path: String::from("a.txt"),
+					base: true,
+					ours: true,
+					theirs: true,
+				},
+				ConflictEntry {
+					path: String::from("b.txt"),
+					base: true,
+					ours: true,
+					theirs: false,
+				},
+			]
+		);
+		assert_eq!(conflicts[0].kind(), ConflictKind::BothModified);
+		assert_eq!(conflicts[1].kind(), ConflictKind::DeletedByThem);
+	}
+}
@@ -2,7 +2,11 @@
 
 use super::{
 	commit_files::{get_commit_diff, get_compare_commits_diff},
+	status::{
+		renamed_from_path, sparse_checkout_excluded, StatusType,
+	},
 	utils::{get_head_repo, work_dir},
+	word_diff::word_diff,
 	CommitId, RepoPath,
 };
 use crate::{
@@ -13,11 +17,22 @@ use crate::{
 };
 use easy_cast::Conv;
 use git2::{
-	Delta, Diff, DiffDelta, DiffFormat, DiffHunk, Patch, Repository,
+	Delta, Diff, DiffDelta, DiffFindOptions, DiffFormat, DiffHunk,
+	FileMode, Patch, Repository, Tree,
 };
 use scopetime::scope_time;
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, fs, path::Path, rc::Rc};
+use std::{
+	cell::{Cell, RefCell},
+	fs,
+	ops::Range,
+	path::Path,
+	rc::Rc,
+};
+
+/// lines of a single hunk collected between checks whether a
+/// cancelable diff got canceled
+const CANCEL_CHECK_LINES: usize = 1000;
 
 /// type of diff of a single line
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
@@ -60,6 +75,12 @@ pub struct DiffLine {
 	pub line_type: DiffLineType,
 	///
 	pub position: DiffLinePosition,
+	/// line is the last of its file and lacks a trailing newline
+	/// (followed by a "no newline at end of file" marker)
+	pub missing_newline: bool,
+	/// byte ranges of `content` that differ from the paired
+	/// added/deleted line (see `DiffOptions::word_diff`)
+	pub changed_ranges: Vec<Range<usize>>,
 }
 
 ///
@@ -106,6 +127,18 @@ impl From<DiffHunk<'_>> for HunkHeader {
 	}
 }
 
+/// staging state of a `Hunk`
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub enum HunkState {
+	/// change is only in the workdir
+	#[default]
+	Unstaged,
+	/// change is completely in the index
+	Staged,
+	/// some lines of the change are in the index
+	PartiallyStaged,
+}
+
 /// single diff hunk
 #[derive(Default, Clone, Hash, Debug)]
 pub struct Hunk {
@@ -113,6 +146,8 @@ pub struct Hunk {
 	pub header_hash: u64,
 	/// list of `DiffLine`s
 	pub lines: Vec<DiffLine>,
+	/// see `get_diff_hunk_states`, diffs of the index are `Staged`
+	pub state: HunkState,
 }
 
 /// collection of hunks, sum of all diff lines
@@ -130,10 +165,44 @@ pub struct FileDiff {
 	pub size_delta: i64,
 }
 
+/// line counts of a single file, like a line of `git diff --numstat`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileStat {
+	///
+	pub path: String,
+	///
+	pub insertions: usize,
+	///
+	pub deletions: usize,
+	/// binary files have no line counts
+	pub binary: bool,
+}
+
+/// per file line counts and their sum
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DiffStat {
+	///
+	pub files: Vec<FileStat>,
+	///
+	pub insertions: usize,
+	///
+	pub deletions: usize,
+}
+
+/// position of a line matching `search_diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffSearchMatch {
+	/// index into `FileDiff::hunks`
+	pub hunk: usize,
+	/// index into `Hunk::lines`
+	pub line: usize,
+}
+
 /// see <https://libgit2.org/libgit2/#HEAD/type/git_diff_options>
 #[derive(
 	Debug, Hash, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
 )]
+#[allow(clippy::struct_excessive_bools)]
 pub struct DiffOptions {
 	/// see <https://libgit2.org/libgit2/#HEAD/type/git_diff_options>
 	pub ignore_whitespace: bool,
@@ -141,6 +210,22 @@ pub struct DiffOptions {
 	pub context: u32,
 	/// see <https://libgit2.org/libgit2/#HEAD/type/git_diff_options>
 	pub interhunk_lines: u32,
+	/// fill `DiffLine::changed_ranges` of modified lines
+	#[serde(default)]
+	pub word_diff: bool,
+	/// ignore changes in the amount of whitespace, like `git diff -b`
+	#[serde(default)]
+	pub ignore_whitespace_change: bool,
+	/// ignore whitespace at the end of lines
+	#[serde(default)]
+	pub ignore_whitespace_eol: bool,
+	/// diff a renamed file against its old path, see `get_diff`
+	#[serde(default)]
+	pub detect_renames: bool,
+	/// similarity in percent for `detect_renames`, 50 like git if
+	/// `None`
+	#[serde(default)]
+	pub rename_threshold: Option<u16>,
 }
 
 impl Default for DiffOptions {
@@ -149,28 +234,65 @@ impl Default for DiffOptions {
 			ignore_whitespace: false,
 			context: 3,
 			interhunk_lines: 0,
+			word_diff: false,
+			ignore_whitespace_change: false,
+			ignore_whitespace_eol: false,
+			detect_renames: false,
+			rename_threshold: None,
 		}
 	}
 }
 
-pub(crate) fn get_diff_raw<'a>(
-	repo: &'a Repository,
+impl DiffOptions {
+	/// lines of a diff ignoring whitespace do not map to the content
+	/// anymore, hunks of such diffs can not be staged or reset
+	pub const fn ignores_whitespace(&self) -> bool {
+		self.ignore_whitespace
+			|| self.ignore_whitespace_change
+			|| self.ignore_whitespace_eol
+	}
+}
+
+fn raw_diff_options(
 	p: &str,
-	stage: bool,
 	reverse: bool,
 	options: Option<DiffOptions>,
-) -> Result<Diff<'a>> {
-	// scope_time!("get_diff_raw");
-
+) -> git2::DiffOptions {
 	let mut opt = git2::DiffOptions::new();
 	if let Some(options) = options {
 		opt.context_lines(options.context);
 		opt.ignore_whitespace(options.ignore_whitespace);
+		opt.ignore_whitespace_change(
+			options.ignore_whitespace_change,
+		);
+		opt.ignore_whitespace_eol(options.ignore_whitespace_eol);
 		opt.interhunk_lines(options.interhunk_lines);
 	}
 	opt.pathspec(p);
 	opt.reverse(reverse);
 
+	opt
+}
+
+pub(crate) fn get_diff_raw<'a>(
+	repo: &'a Repository,
+	p: &str,
+	stage: bool,
+	reverse: bool,
+	options: Option<DiffOptions>,
+) -> Result<Diff<'a>> {
+	// scope_time!("get_diff_raw");
+
+	let mut opt = raw_diff_options(p, reverse, options);
+
+	diff_with_options(repo, stage, &mut opt)
+}
+
+fn diff_with_options<'a>(
+	repo: &'a Repository,
+	stage: bool,
+	opt: &mut git2::DiffOptions,
+) -> Result<Diff<'a>> {
 	let diff = if stage {
 		// diff against head
 		if let Ok(id) = get_head_repo(repo) {
@@ -180,38 +302,294 @@ pub(crate) fn get_diff_raw<'a>(
 			repo.diff_tree_to_index(
 				Some(&tree),
 				Some(&repo.index()?),
-				Some(&mut opt),
+				Some(opt),
 			)?
 		} else {
 			repo.diff_tree_to_index(
 				None,
 				Some(&repo.index()?),
-				Some(&mut opt),
+				Some(opt),
 			)?
 		}
 	} else {
 		opt.include_untracked(true);
 		opt.recurse_untracked_dirs(true);
-		repo.diff_index_to_workdir(None, Some(&mut opt))?
+		repo.diff_index_to_workdir(None, Some(opt))?
 	};
 
 	Ok(diff)
 }
 
+/// like the workdir variant of `get_diff_raw` but untracked files
+/// come with hunks (diffed against empty content)
+pub(crate) fn get_diff_raw_untracked_content<'a>(
+	repo: &'a Repository,
+	p: &str,
+	reverse: bool,
+	options: Option<DiffOptions>,
+) -> Result<Diff<'a>> {
+	let mut opt = raw_diff_options(p, reverse, options);
+
+	opt.include_untracked(true);
+	opt.recurse_untracked_dirs(true);
+	opt.show_untracked_content(true);
+
+	Ok(repo.diff_index_to_workdir(None, Some(&mut opt))?)
+}
+
 /// returns diff of a specific file either in `stage` or workdir
 pub fn get_diff(
 	repo_path: &RepoPath,
 	p: &str,
 	stage: bool,
 	options: Option<DiffOptions>,
+) -> Result<FileDiff> {
+	get_diff_cancelable(repo_path, p, stage, options, &|| false)
+}
+
+/// like `get_diff` but fails with [`Error::Canceled`] as soon as
+/// `is_canceled` returns `true`, which is checked while collecting
+/// the hunks
+pub(crate) fn get_diff_cancelable(
+	repo_path: &RepoPath,
+	p: &str,
+	stage: bool,
+	options: Option<DiffOptions>,
+	is_canceled: &dyn Fn() -> bool,
 ) -> Result<FileDiff> {
 	scope_time!("get_diff");
 
 	let repo = repo(repo_path)?;
 	let work_dir = work_dir(&repo)?;
-	let diff = get_diff_raw(&repo, p, stage, false, options)?;
 
-	raw_diff_to_file_diff(&diff, work_dir)
+	if !stage && sparse_checkout_excluded(&repo)?.contains(p) {
+		return Ok(FileDiff::default());
+	}
+
+	let old_path = if options.map_or(false, |o| o.detect_renames) {
+		renamed_from_path(
+			&repo,
+			p,
+			if stage {
+				StatusType::Stage
+			} else {
+				StatusType::WorkingDir
+			},
+			options.and_then(|o| o.rename_threshold),
+		)?
+	} else {
+		None
+	};
+
+	let diff = if let Some(old_path) = old_path {
+		get_diff_raw_renamed(&repo, p, &old_path, stage, options)?
+	} else {
+		get_diff_raw(&repo, p, stage, false, options)?
+	};
+
+	let mut res = match submodule_diff(&repo, &diff) {
+		Some(res) => res,
+		None => raw_diff_to_file_diff_cancelable(
+			&diff,
+			work_dir,
+			options,
+			is_canceled,
+		)?,
+	};
+	if stage {
+		for hunk in &mut res.hunks {
+			hunk.state = HunkState::Staged;
+		}
+	}
+
+	Ok(res)
+}
+
+/// diff of `p` paired up with its `old_path`
+fn get_diff_raw_renamed<'a>(
+	repo: &'a Repository,
+	p: &str,
+	old_path: &str,
+	stage: bool,
+	options: Option<DiffOptions>,
+) -> Result<Diff<'a>> {
+	let mut opt = raw_diff_options(p, false, options);
+	opt.pathspec(old_path);
+
+	let mut diff = diff_with_options(repo, stage, &mut opt)?;
+
+	let mut find = DiffFindOptions::new();
+	find.renames(true).for_untracked(true);
+	if let Some(threshold) = options.and_then(|o| o.rename_threshold)
+	{
+		find.rename_threshold(threshold);
+	}
+	diff.find_similar(Some(&mut find))?;
+
+	Ok(diff)
+}
+
+/// a changed submodule pointer as a single line summary like
+/// `Submodule path: oldsha..newsha (N commits)` instead of the diff of
+/// the commit ids, `None` if `diff` is not about a submodule
+fn submodule_diff(
+	repo: &Repository,
+	diff: &Diff,
+) -> Option<FileDiff> {
+	let delta = diff.deltas().next()?;
+
+	if diff.deltas().len() != 1
+		|| (delta.old_file().mode() != FileMode::Commit
+			&& delta.new_file().mode() != FileMode::Commit)
+	{
+		return None;
+	}
+
+	let path = delta
+		.new_file()
+		.path()
+		.or_else(|| delta.old_file().path())
+		.map(|p| p.to_string_lossy().to_string())
+		.unwrap_or_default();
+	let old = CommitId::new(delta.old_file().id());
+	let new = CommitId::new(delta.new_file().id());
+
+	let summary = if old == new {
+		format!(
+			"Submodule {path}: {} contains modified content",
+			old.get_short_string()
+		)
+	} else {
+		let count = submodule_commit_count(repo, &path, old, new);
+
+		format!(
+			"Submodule {path}: {}..{}{}",
+			old.get_short_string(),
+			new.get_short_string(),
+			count.map_or_else(String::new, |count| format!(
+				" ({count} commit{})",
+				if count == 1 { "" } else { "s" }
+			)),
+		)
+	};
+
+	let line = DiffLine {
+		content: summary.as_str().into(),
+		line_type: DiffLineType::Header,
+		..DiffLine::default()
+	};
+
+	Some(FileDiff {
+		hunks: vec![Hunk {
+			header_hash: hash(&summary),
+			lines: vec![line],
+			state: HunkState::default(),
+		}],
+		lines: 1,
+		..FileDiff::default()
+	})
+}
+
+/// commits reachable from `new` but not `old` in the submodule at
+/// `path`, `None` if it is not checked out or `new` is missing
+fn submodule_commit_count(
+	repo: &Repository,
+	path: &str,
+	old: CommitId,
+	new: CommitId,
+) -> Option<usize> {
+	let sub_repo = repo.find_submodule(path).ok()?.open().ok()?;
+
+	if new.get_oid().is_zero() {
+		return None;
+	}
+
+	let mut walk = sub_repo.revwalk().ok()?;
+	walk.push(new.into()).ok()?;
+	if !old.get_oid().is_zero() {
+		walk.hide(old.into()).ok()?;
+	}
+
+	Some(walk.count())
+}
+
+/// returns the diff of `p` between `HEAD` and the workdir with the
+/// state of every hunk telling whether its change is staged
+///
+/// Deleted lines are staged if the index diff deletes the same `HEAD`
+/// line, added lines are unstaged if the workdir diff adds the same
+/// workdir line.
+pub fn get_diff_hunk_states(
+	repo_path: &RepoPath,
+	p: &str,
+	options: Option<DiffOptions>,
+) -> Result<FileDiff> {
+	scope_time!("get_diff_hunk_states");
+
+	let repo = repo(repo_path)?;
+	let work_dir = work_dir(&repo)?;
+
+	let head_tree = match get_head_repo(&repo) {
+		Ok(id) => Some(repo.find_commit(id.into())?.tree()?),
+		Err(_) => None,
+	};
+	let mut opt = raw_diff_options(p, false, options);
+	let diff = repo.diff_tree_to_workdir_with_index(
+		head_tree.as_ref(),
+		Some(&mut opt),
+	)?;
+	let mut res = raw_diff_to_file_diff(&diff, work_dir, options)?;
+
+	let changed_lines = |stage: bool, line_type: DiffLineType| {
+		get_diff_raw(&repo, p, stage, false, options)
+			.and_then(|diff| {
+				raw_diff_to_file_diff(&diff, work_dir, options)
+			})
+			.map(|diff| {
+				diff.hunks
+					.into_iter()
+					.flat_map(|hunk| hunk.lines)
+					.filter(|line| line.line_type == line_type)
+					.map(|line| line.position)
+					.collect::<Vec<_>>()
+			})
+	};
+	let staged_deletes = changed_lines(true, DiffLineType::Delete)?;
+	let unstaged_adds = changed_lines(false, DiffLineType::Add)?;
+
+	for hunk in &mut res.hunks {
+		let (mut staged, mut unstaged) = (false, false);
+
+		for line in &hunk.lines {
+			let is_staged = match line.line_type {
+				DiffLineType::Delete => {
+					staged_deletes.iter().any(|pos| {
+						pos.old_lineno == line.position.old_lineno
+					})
+				}
+				DiffLineType::Add => {
+					!unstaged_adds.iter().any(|pos| {
+						pos.new_lineno == line.position.new_lineno
+					})
+				}
+				_ => continue,
+			};
+
+			if is_staged {
+				staged = true;
+			} else {
+				unstaged = true;
+			}
+		}
+
+		hunk.state = match (staged, unstaged) {
+			(true, false) => HunkState::Staged,
+			(true, true) => HunkState::PartiallyStaged,
+			_ => HunkState::Unstaged,
+		};
+	}
+
+	Ok(res)
 }
 
 /// returns diff of a specific file inside a commit
@@ -221,6 +599,17 @@ pub fn get_diff_commit(
 	id: CommitId,
 	p: String,
 	options: Option<DiffOptions>,
+) -> Result<FileDiff> {
+	get_diff_commit_cancelable(repo_path, id, p, options, &|| false)
+}
+
+/// like `get_diff_commit`, see `get_diff_cancelable`
+pub(crate) fn get_diff_commit_cancelable(
+	repo_path: &RepoPath,
+	id: CommitId,
+	p: String,
+	options: Option<DiffOptions>,
+	is_canceled: &dyn Fn() -> bool,
 ) -> Result<FileDiff> {
 	scope_time!("get_diff_commit");
 
@@ -234,7 +623,12 @@ pub fn get_diff_commit(
 		Some(&get_stashes(repo_path)?.into_iter().collect()),
 	)?;
 
-	raw_diff_to_file_diff(&diff, work_dir)
+	raw_diff_to_file_diff_cancelable(
+		&diff,
+		work_dir,
+		options,
+		is_canceled,
+	)
 }
 
 /// get file changes of a diff between two commits
@@ -243,6 +637,17 @@ pub fn get_diff_commits(
 	ids: (CommitId, CommitId),
 	p: String,
 	options: Option<DiffOptions>,
+) -> Result<FileDiff> {
+	get_diff_commits_cancelable(repo_path, ids, p, options, &|| false)
+}
+
+/// like `get_diff_commits`, see `get_diff_cancelable`
+pub(crate) fn get_diff_commits_cancelable(
+	repo_path: &RepoPath,
+	ids: (CommitId, CommitId),
+	p: String,
+	options: Option<DiffOptions>,
+	is_canceled: &dyn Fn() -> bool,
 ) -> Result<FileDiff> {
 	scope_time!("get_diff_commits");
 
@@ -255,18 +660,185 @@ pub fn get_diff_commits(
 		options,
 	)?;
 
-	raw_diff_to_file_diff(&diff, work_dir)
+	raw_diff_to_file_diff_cancelable(
+		&diff,
+		work_dir,
+		options,
+		is_canceled,
+	)
+}
+
+/// returns the diff from `from` to `to`, optionally restricted to
+/// `path`
+///
+/// Unlike `get_diff_commits` the direction is kept as given.
+pub fn diff_commits(
+	repo_path: &RepoPath,
+	from: CommitId,
+	to: CommitId,
+	path: Option<&str>,
+) -> Result<FileDiff> {
+	scope_time!("diff_commits");
+
+	let repo = repo(repo_path)?;
+
+	let from_tree = repo.find_commit(from.into())?.tree()?;
+	let to_tree = repo.find_commit(to.into())?.tree()?;
+
+	diff_trees(&repo, &from_tree, &to_tree, path)
+}
+
+/// changes between two trees, limited to `path` if set
+pub(crate) fn diff_trees(
+	repo: &Repository,
+	from_tree: &Tree,
+	to_tree: &Tree,
+	path: Option<&str>,
+) -> Result<FileDiff> {
+	let work_dir = work_dir(repo)?;
+
+	let mut opt = git2::DiffOptions::new();
+	if let Some(path) = path {
+		opt.pathspec(path);
+	}
+	opt.show_binary(true);
+
+	let diff = repo.diff_tree_to_tree(
+		Some(from_tree),
+		Some(to_tree),
+		Some(&mut opt),
+	)?;
+
+	raw_diff_to_file_diff(&diff, work_dir, None)
+}
+
+/// returns the changes of `topic` since it diverged from `base`, like
+/// `git diff base...topic`
+pub fn diff_since_merge_base(
+	repo_path: &RepoPath,
+	base: CommitId,
+	topic: CommitId,
+	path: Option<&str>,
+) -> Result<FileDiff> {
+	scope_time!("diff_since_merge_base");
+
+	let merge_base =
+		repo(repo_path)?.merge_base(base.into(), topic.into())?;
+
+	diff_commits(repo_path, merge_base.into(), topic, path)
+}
+
+/// returns the lines of `diff` containing `query`, hunk headers are
+/// skipped
+///
+/// With `regex` the `query` is used as a regular expression, an
+/// invalid one is an error.
+pub fn search_diff(
+	diff: &FileDiff,
+	query: &str,
+	case_sensitive: bool,
+	regex: bool,
+) -> Result<Vec<DiffSearchMatch>> {
+	if query.is_empty() {
+		return Ok(Vec::new());
+	}
+
+	let pattern = if regex {
+		query.to_string()
+	} else {
+		regex::escape(query)
+	};
+	let re = regex::RegexBuilder::new(&pattern)
+		.case_insensitive(!case_sensitive)
+		.build()
+		.map_err(|e| {
+			Error::Generic(format!("invalid search pattern: {e}"))
+		})?;
+
+	let mut matches = Vec::new();
+	for (hunk_idx, hunk) in diff.hunks.iter().enumerate() {
+		for (line_idx, line) in hunk.lines.iter().enumerate() {
+			if line.line_type != DiffLineType::Header
+				&& re.is_match(&line.content)
+			{
+				matches.push(DiffSearchMatch {
+					hunk: hunk_idx,
+					line: line_idx,
+				});
+			}
+		}
+	}
+
+	Ok(matches)
+}
+
+/// returns the line counts of the files matching `p` either in
+/// `stage` or workdir without collecting their diff lines
+pub fn get_diff_stat(
+	repo_path: &RepoPath,
+	p: &str,
+	stage: bool,
+) -> Result<DiffStat> {
+	scope_time!("get_diff_stat");
+
+	let repo = repo(repo_path)?;
+	let diff = get_diff_raw(&repo, p, stage, false, None)?;
+
+	let mut stat = DiffStat::default();
+	for idx in 0..diff.deltas().len() {
+		let Some(patch) = Patch::from_diff(&diff, idx)? else {
+			continue;
+		};
+
+		let delta = patch.delta();
+		let file_path = delta
+			.new_file()
+			.path()
+			.or_else(|| delta.old_file().path())
+			.map(|path| path.to_string_lossy().to_string())
+			.unwrap_or_default();
+		let binary = delta.flags().is_binary();
+		let (_, insertions, deletions) = patch.line_stats()?;
+
+		stat.insertions += insertions;
+		stat.deletions += deletions;
+		stat.files.push(FileStat {
+			path: file_path,
+			insertions,
+			deletions,
+			binary,
+		});
+	}
+
+	Ok(stat)
 }
 
 ///
+fn raw_diff_to_file_diff(
+	diff: &Diff,
+	work_dir: &Path,
+	options: Option<DiffOptions>,
+) -> Result<FileDiff> {
+	raw_diff_to_file_diff_cancelable(diff, work_dir, options, &|| {
+		false
+	})
+}
+
+/// `is_canceled` is asked at every hunk and every
+/// `CANCEL_CHECK_LINES` lines
 //TODO: refactor into helper type with the inline closures as dedicated functions
 #[allow(clippy::too_many_lines)]
-fn raw_diff_to_file_diff(
+fn raw_diff_to_file_diff_cancelable(
 	diff: &Diff,
 	work_dir: &Path,
+	options: Option<DiffOptions>,
+	is_canceled: &dyn Fn() -> bool,
 ) -> Result<FileDiff> {
 	let res = Rc::new(RefCell::new(FileDiff::default()));
+	let canceled = Cell::new(false);
 	{
+		let mut lines_since_check = 0;
+
 		let mut current_lines = Vec::new();
 		let mut current_hunk: Option<HunkHeader> = None;
 
@@ -277,6 +849,7 @@ fn raw_diff_to_file_diff(
 			res.hunks.push(Hunk {
 				header_hash: hash(header),
 				lines: lines.clone(),
+				state: HunkState::default(),
 			});
 			res.lines += lines.len();
 		};
@@ -285,6 +858,7 @@ fn raw_diff_to_file_diff(
 		let mut put = |delta: DiffDelta,
 		               hunk: Option<DiffHunk>,
 		               line: git2::DiffLine| {
+			lines_since_check += 1;
 			{
 				let mut res = res_cell.borrow_mut();
 				res.sizes = (
@@ -298,28 +872,44 @@ fn raw_diff_to_file_diff(
 			if let Some(hunk) = hunk {
 				let hunk_header = HunkHeader::from(hunk);
 
-				match current_hunk {
-					None => current_hunk = Some(hunk_header),
-					Some(h) => {
-						if h != hunk_header {
-							adder(&h, &current_lines);
-							current_lines.clear();
-							current_hunk = Some(hunk_header);
-						}
+				let new_hunk = match current_hunk {
+					None => {
+						current_hunk = Some(hunk_header);
+						true
+					}
+					Some(h) if h != hunk_header => {
+						adder(&h, &current_lines);
+						current_lines.clear();
+						current_hunk = Some(hunk_header);
+						true
+					}
+					Some(_) => false,
+				};
+
+				if new_hunk || lines_since_check >= CANCEL_CHECK_LINES
+				{
+					lines_since_check = 0;
+					if is_canceled() {
+						canceled.set(true);
+						return false;
 					}
 				}
 
 				let diff_line = DiffLine {
 					position: DiffLinePosition::from(&line),
+					missing_newline: !line.content().ends_with(b"\n"),
 					content: String::from_utf8_lossy(line.content())
 						//Note: trim await trailing newline characters
 						.trim_matches(is_newline)
 						.into(),
 					line_type: line.origin_value().into(),
+					changed_ranges: Vec::new(),
 				};
 
 				current_lines.push(diff_line);
 			}
+
+			true
 		};
 
 		let new_file_diff = if diff.deltas().len() == 1 {
@@ -346,14 +936,17 @@ fn raw_diff_to_file_diff(
 							None,
 						)?;
 
-						patch.print(
+						let printed = patch.print(
 							&mut |delta,
 							      hunk: Option<DiffHunk>,
 							      line: git2::DiffLine| {
-								put(delta, hunk, line);
-								true
+								put(delta, hunk, line)
 							},
-						)?;
+						);
+						if canceled.get() {
+							return Err(Error::Canceled);
+						}
+						printed?;
 
 						true
 					} else {
@@ -370,13 +963,16 @@ fn raw_diff_to_file_diff(
 		};
 
 		if !new_file_diff {
-			diff.print(
+			let printed = diff.print(
 				DiffFormat::Patch,
 				move |delta, hunk, line: git2::DiffLine| {
-					put(delta, hunk, line);
-					true
+					put(delta, hunk, line)
 				},
-			)?;
+			);
+			if canceled.get() {
+				return Err(Error::Canceled);
+			}
+			printed?;
 		}
 
 		if !current_lines.is_empty() {
@@ -393,9 +989,68 @@ fn raw_diff_to_file_diff(
 			res.borrow_mut().untracked = true;
 		}
 	}
-	let res = Rc::try_unwrap(res)
-		.map_err(|_| Error::Generic("rc unwrap error".to_owned()))?;
-	Ok(res.into_inner())
+	let mut res = Rc::try_unwrap(res)
+		.map_err(|_| Error::Generic("rc unwrap error".to_owned()))?
+		.into_inner();
+
+	if options.map_or(false, |options| options.word_diff) {
+		for hunk in &mut res.hunks {
+			hunk_word_diff(&mut hunk.lines);
+		}
+	}
+
+	Ok(res)
+}
+
+/// pairs up runs of deleted lines with the directly following runs of
+/// added lines and fills in their `changed_ranges`
+fn hunk_word_diff(lines: &mut [DiffLine]) {
+	fn pair_up(
+		lines: &mut [DiffLine],
+		deleted: &[usize],
+		added: &[usize],
+	) {
+		for (&old, &new) in deleted.iter().zip(added) {
+			let (removed, inserted) =
+				word_diff(&lines[old].content, &lines[new].content);
+			lines[old].changed_ranges = removed;
+			lines[new].changed_ranges = inserted;
+		}
+	}
+
+	let mut deleted = Vec::new();
+	let mut added = Vec::new();
+	let mut is_marker = false;
+
+	for idx in 0..lines.len() {
+		// "no newline at end of file" markers carry no content
+		if is_marker {
+			is_marker = false;
+			continue;
+		}
+		is_marker = lines[idx].missing_newline;
+
+		match lines[idx].line_type {
+			DiffLineType::Delete if added.is_empty() => {
+				deleted.push(idx);
+			}
+			DiffLineType::Add if !deleted.is_empty() => {
+				added.push(idx);
+			}
+			DiffLineType::Delete => {
+				pair_up(lines, &deleted, &added);
+				deleted = vec![idx];
+				added.clear();
+			}
+			_ => {
+				pair_up(lines, &deleted, &added);
+				deleted.clear();
+				added.clear();
+			}
+		}
+	}
+
+	pair_up(lines, &deleted, &added);
 }
 
 const fn is_newline(c: char) -> bool {
@@ -422,13 +1077,21 @@ fn new_file_content(path: &Path) -> Option<Vec<u8>> {
 
 #[cfg(test)]
 mod tests {
-	use super::{get_diff, get_diff_commit};
+	use super::{
+		diff_commits, diff_since_merge_base, get_diff,
+		get_diff_cancelable, get_diff_commit, get_diff_hunk_states,
+		get_diff_stat, search_diff, DiffLineType, DiffOptions,
+		DiffSearchMatch, DiffStat, FileDiff, FileStat, HunkState,
+	};
 	use crate::{
 		error::Result,
 		sync::{
-			commit, stage_add_file,
+			commit, stage_add_file, stage_hunk,
 			status::{get_status, StatusType},
-			tests::{get_statuses, repo_init, repo_init_empty},
+			tests::{
+				get_statuses, repo_init, repo_init_empty,
+				write_commit_file,
+			},
 			RepoPath,
 		},
 	};
@@ -599,7 +1262,7 @@ mod tests {
 
 		stage_add_file(repo_path, file_path).unwrap();
 
-		commit(repo_path, "commit").unwrap();
+		commit(repo_path, "commit", false).unwrap();
 
 		File::create(root.join(file_path))?.write_all(b"\x00\x02")?;
 
@@ -655,13 +1318,13 @@ mod tests {
 
 		stage_add_file(repo_path, file_path).unwrap();
 
-		commit(repo_path, "").unwrap();
+		commit(repo_path, "", false).unwrap();
 
 		File::create(root.join(file_path))?.write_all(b"\x00\x02")?;
 
 		stage_add_file(repo_path, file_path).unwrap();
 
-		let id = commit(repo_path, "").unwrap();
+		let id = commit(repo_path, "", false).unwrap();
 
 		let diff =
 			get_diff_commit(repo_path, id, String::new(), None)
@@ -673,4 +1336,630 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_diff_missing_newline() -> Result<()> {
+		let file_path = Path::new("bar.txt");
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		File::create(root.join(file_path))?.write_all(b"a\n")?;
+
+		stage_add_file(repo_path, file_path).unwrap();
+
+		commit(repo_path, "commit", false).unwrap();
+
+		File::create(root.join(file_path))?.write_all(b"a\nb")?;
+
+		let diff = get_diff(
+			repo_path,
+			file_path.to_str().unwrap(),
+			false,
+			None,
+		)
+		.unwrap();
+
+		let lines = &diff.hunks[0].lines;
+		assert_eq!(&*lines[2].content, "b");
+		assert!(lines[2].missing_newline);
+		assert_eq!(
+			lines.iter().filter(|l| l.missing_newline).count(),
+			1
+		);
+
+		File::create(root.join(file_path))?.write_all(b"a\nb\n")?;
+
+		let diff = get_diff(
+			repo_path,
+			file_path.to_str().unwrap(),
+			false,
+			None,
+		)
+		.unwrap();
+
+		assert!(!diff.hunks[0]
+			.lines
+			.iter()
+			.any(|l| l.missing_newline));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_word_diff() -> Result<()> {
+		let file_path = Path::new("bar.txt");
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		File::create(root.join(file_path))?
+			.write_all(b"start\nfoo bar baz\nend\n")?;
+
+		stage_add_file(repo_path, file_path).unwrap();
+
+		commit(repo_path, "commit", false).unwrap();
+
+		File::create(root.join(file_path))?
+			.write_all(b"start\nfoo qux baz\nend\n")?;
+
+		let options = DiffOptions {
+			word_diff: true,
+			..DiffOptions::default()
+		};
+
+		let diff = get_diff(
+			repo_path,
+			file_path.to_str().unwrap(),
+			false,
+			Some(options),
+		)
+		.unwrap();
+
+		let lines = &diff.hunks[0].lines;
+		assert_eq!(&*lines[2].content, "foo bar baz");
+		assert_eq!(lines[2].changed_ranges, vec![4..7]);
+		assert_eq!(&*lines[3].content, "foo qux baz");
+		assert_eq!(lines[3].changed_ranges, vec![4..7]);
+		assert!(lines[1].changed_ranges.is_empty());
+
+		let diff = get_diff(
+			repo_path,
+			file_path.to_str().unwrap(),
+			false,
+			None,
+		)
+		.unwrap();
+
+		assert!(diff.hunks[0]
+			.lines
+			.iter()
+			.all(|l| l.changed_ranges.is_empty()));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_stat() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "1\n2\n3\n", "c1");
+		write_commit_file(&repo, "b.txt", "x\n", "c2");
+		write_commit_file(&repo, "c.bin", "\0", "c3");
+
+		fs::write(root.join("a.txt"), "1\n3\n4\n5\n")?;
+		fs::write(root.join("b.txt"), "")?;
+		fs::write(root.join("c.bin"), "\0\0")?;
+
+		let file =
+			|path: &str, insertions, deletions, binary| FileStat {
+				path: String::from(path),
+				insertions,
+				deletions,
+				binary,
+			};
+
+		assert_eq!(
+			get_diff_stat(repo_path, "*", false)?,
+			DiffStat {
+				files: vec![
+					file("a.txt", 2, 1, false),
+					file("b.txt", 0, 1, false),
+					file("c.bin", 0, 0, true),
+				],
+				insertions: 2,
+				deletions: 2,
+			}
+		);
+
+		stage_add_file(repo_path, Path::new("a.txt"))?;
+
+		assert_eq!(
+			get_diff_stat(repo_path, "*", true)?,
+			DiffStat {
+				files: vec![file("a.txt", 2, 1, false)],
+				insertions: 2,
+				deletions: 1,
+			}
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_hunk_states() -> Result<()> {
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| format!("line{i}"))
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "a.txt", &content, "c1");
+		fs::write(
+			root.join("a.txt"),
+			content
+				.replace("line2\n", "x\n")
+				.replace("line15\n", "y\n"),
+		)?;
+
+		let diff = get_diff(repo_path, "a.txt", false, None)?;
+		assert_eq!(diff.hunks.len(), 2);
+		assert!(diff
+			.hunks
+			.iter()
+			.all(|hunk| hunk.state == HunkState::Unstaged));
+
+		stage_hunk(
+			repo_path,
+			"a.txt",
+			diff.hunks[0].header_hash,
+			None,
+		)?;
+
+		let states = |diff: &FileDiff| {
+			diff.hunks
+				.iter()
+				.map(|hunk| hunk.state)
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(
+			states(&get_diff_hunk_states(repo_path, "a.txt", None)?),
+			vec![HunkState::Staged, HunkState::Unstaged]
+		);
+		assert_eq!(
+			states(&get_diff(repo_path, "a.txt", true, None)?),
+			vec![HunkState::Staged]
+		);
+
+		fs::write(
+			root.join("a.txt"),
+			content
+				.replace("line2\n", "z\n")
+				.replace("line15\n", "y\n"),
+		)?;
+
+		assert_eq!(
+			states(&get_diff_hunk_states(repo_path, "a.txt", None)?),
+			vec![HunkState::PartiallyStaged, HunkState::Unstaged]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_context_lines() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| i.to_string())
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "a.txt", &content, "c1");
+		fs::write(
+			root.join("a.txt"),
+			content.replace("10\n", "x\n"),
+		)?;
+
+		for context in [0, 1, 5] {
+			let diff = get_diff(
+				repo_path,
+				"a.txt",
+				false,
+				Some(DiffOptions {
+					context,
+					..DiffOptions::default()
+				}),
+			)?;
+
+			assert_eq!(diff.hunks.len(), 1);
+			let context_lines = diff.hunks[0]
+				.lines
+				.iter()
+				.filter(|line| line.line_type == DiffLineType::None)
+				.count();
+			assert_eq!(context_lines, usize::try_from(context * 2)?);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_ignore_whitespace() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(
+			&repo,
+			"a.txt",
+			"fn a() {\n\tb c;\n}\n",
+			"c1",
+		);
+
+		let hunks = |content: &str, options: DiffOptions| {
+			fs::write(root.join("a.txt"), content).unwrap();
+			get_diff(repo_path, "a.txt", false, Some(options))
+				.unwrap()
+				.hunks
+				.len()
+		};
+
+		let reindent = "fn a() {\n    b c;\n}\n";
+		let amount = "fn a() {\n\tb   c;\n}\n";
+		let eol = "fn a() {\n\tb c;  \n}\n";
+
+		let none = DiffOptions::default();
+		let all = DiffOptions {
+			ignore_whitespace: true,
+			..none
+		};
+		let change = DiffOptions {
+			ignore_whitespace_change: true,
+			..none
+		};
+		let at_eol = DiffOptions {
+			ignore_whitespace_eol: true,
+			..none
+		};
+
+		for content in [reindent, amount, eol] {
+			assert_eq!(hunks(content, none), 1);
+			assert_eq!(hunks(content, all), 0);
+		}
+
+		assert_eq!(hunks(reindent, change), 0);
+		assert_eq!(hunks(amount, change), 0);
+		assert_eq!(hunks(reindent, at_eol), 1);
+		assert_eq!(hunks(eol, at_eol), 0);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_commits() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = write_commit_file(&repo, "a.txt", "a\nb\n", "c1");
+		write_commit_file(&repo, "b.txt", "x\n", "c2");
+		let c3 = write_commit_file(&repo, "a.txt", "a\nc\n", "c3");
+
+		let lines = |from, to, path| {
+			diff_commits(repo_path, from, to, path)
+				.unwrap()
+				.hunks
+				.iter()
+				.flat_map(|hunk| hunk.lines.iter())
+				.filter(|line| line.line_type != DiffLineType::Header)
+				.map(|line| {
+					(
+						line.line_type,
+						line.content.trim_end().to_string(),
+					)
+				})
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(
+			lines(c1, c3, Some("a.txt")),
+			vec![
+				(DiffLineType::None, String::from("a")),
+				(DiffLineType::Delete, String::from("b")),
+				(DiffLineType::Add, String::from("c")),
+			]
+		);
+		assert_eq!(
+			lines(c3, c1, Some("a.txt")),
+			vec![
+				(DiffLineType::None, String::from("a")),
+				(DiffLineType::Delete, String::from("c")),
+				(DiffLineType::Add, String::from("b")),
+			]
+		);
+		assert_eq!(
+			lines(c1, c3, Some("b.txt")),
+			vec![(DiffLineType::Add, String::from("x"))]
+		);
+		assert_eq!(lines(c1, c3, None).len(), 4);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_search_diff() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| format!("line {i}"))
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "a.txt", &content, "c1");
+		fs::write(
+			root.join("a.txt"),
+			content
+				.replace("line 2\n", "Foo 2\n")
+				.replace("line 15\n", "foo 15\n"),
+		)?;
+
+		let diff = get_diff(
+			repo_path,
+			"a.txt",
+			false,
+			Some(DiffOptions {
+				context: 1,
+				..DiffOptions::default()
+			}),
+		)?;
+		assert_eq!(diff.hunks.len(), 2);
+
+		let at = |hunk, line| DiffSearchMatch { hunk, line };
+
+		// lines of each hunk: header, context, delete, add, context
+		assert_eq!(
+			search_diff(&diff, "foo", true, false)?,
+			vec![at(1, 3)]
+		);
+		assert_eq!(
+			search_diff(&diff, "foo", false, false)?,
+			vec![at(0, 3), at(1, 3)]
+		);
+		assert_eq!(
+			search_diff(&diff, "line 1.", true, false)?,
+			Vec::new()
+		);
+		assert_eq!(
+			search_diff(&diff, r"line 1\d", true, true)?,
+			vec![at(1, 1), at(1, 2), at(1, 4)]
+		);
+		assert_eq!(
+			search_diff(&diff, r"^f\w+ \d$", false, true)?,
+			vec![at(0, 3)]
+		);
+		assert!(search_diff(&diff, "(", true, true).is_err());
+		assert!(search_diff(&diff, "", true, false)?.is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_submodule_pointer_summary() -> Result<()> {
+		let (sub_dir, sub_repo) = repo_init()?;
+		let c1 = write_commit_file(&sub_repo, "a.txt", "a", "sub c1");
+
+		let (dir, repo) = repo_init()?;
+		let repo_path: &RepoPath =
+			&dir.path().to_str().unwrap().into();
+		{
+			let mut s = repo.submodule(
+				sub_dir.path().to_str().unwrap(),
+				Path::new("sub"),
+				false,
+			)?;
+			s.clone(None)?;
+			s.add_finalize()?;
+		}
+		write_commit_file(&repo, "p.txt", "p", "add submodule");
+
+		let cloned_sub =
+			git2::Repository::open(dir.path().join("sub"))?;
+		{
+			let mut config = cloned_sub.config()?;
+			config.set_str("user.name", "name")?;
+			config.set_str("user.email", "email")?;
+		}
+		write_commit_file(&cloned_sub, "a.txt", "b", "sub c2");
+		let c3 =
+			write_commit_file(&cloned_sub, "a.txt", "c", "sub c3");
+
+		let diff = get_diff(repo_path, "sub", false, None)?;
+
+		assert_eq!(diff.hunks.len(), 1);
+		assert_eq!(diff.hunks[0].lines.len(), 1);
+		assert_eq!(
+			diff.hunks[0].lines[0].line_type,
+			DiffLineType::Header
+		);
+		assert_eq!(
+			diff.hunks[0].lines[0].content.as_ref(),
+			format!(
+				"Submodule sub: {}..{} (2 commits)",
+				c1.get_short_string(),
+				c3.get_short_string()
+			)
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_since_merge_base() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c0 = write_commit_file(&repo, "a.txt", "a\n", "c0");
+		let base = write_commit_file(&repo, "base.txt", "x\n", "c1");
+
+		repo.reset(
+			&repo.find_object(c0.into(), None)?,
+			git2::ResetType::Hard,
+			None,
+		)?;
+		let topic =
+			write_commit_file(&repo, "topic.txt", "y\n", "c2");
+
+		let contents = |diff: FileDiff| {
+			diff.hunks
+				.iter()
+				.flat_map(|hunk| hunk.lines.iter())
+				.filter(|line| line.line_type != DiffLineType::Header)
+				.map(|line| {
+					(line.line_type, line.content.to_string())
+				})
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(
+			contents(diff_since_merge_base(
+				repo_path, base, topic, None
+			)?),
+			vec![(DiffLineType::Add, String::from("y"))]
+		);
+		assert_eq!(
+			contents(diff_commits(repo_path, base, topic, None)?)
+				.len(),
+			2
+		);
+		assert_eq!(
+			contents(diff_since_merge_base(
+				repo_path,
+				base,
+				topic,
+				Some("a.txt")
+			)?),
+			Vec::new()
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_cancel() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = |changed: bool| {
+			(0..20_000)
+				.map(|i| {
+					if changed && i % 20 == 0 {
+						format!("changed {i}\n")
+					} else {
+						format!("line {i}\n")
+					}
+				})
+				.collect::<String>()
+		};
+		write_commit_file(&repo, "large.txt", &content(false), "c1");
+		File::create(root.join("large.txt"))?
+			.write_all(content(true).as_bytes())?;
+
+		let calls = std::cell::Cell::new(0);
+		let res = get_diff_cancelable(
+			repo_path,
+			"large.txt",
+			false,
+			None,
+			&|| {
+				calls.set(calls.get() + 1);
+				calls.get() == 3
+			},
+		);
+
+		assert!(matches!(res, Err(crate::Error::Canceled)));
+		assert_eq!(calls.get(), 3);
+
+		let diff = get_diff(repo_path, "large.txt", false, None)?;
+		assert_eq!(diff.hunks.len(), 1000);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff_detect_renames() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| format!("line {i}"))
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "old.txt", &content, "c1");
+
+		fs::remove_file(root.join("old.txt"))?;
+		fs::write(
+			root.join("new.txt"),
+			content.replace("line 10\n", "changed\n"),
+		)?;
+
+		let options = DiffOptions {
+			detect_renames: true,
+			..DiffOptions::default()
+		};
+
+		let diff =
+			get_diff(repo_path, "new.txt", false, Some(options))?;
+		assert_eq!(diff.hunks.len(), 1);
+		let changed = diff.hunks[0]
+			.lines
+			.iter()
+			.filter(|line| {
+				matches!(
+					line.line_type,
+					DiffLineType::Add | DiffLineType::Delete
+				)
+			})
+			.count();
+		assert_eq!(changed, 2);
+
+		// without detection the whole file is new
+		let diff = get_diff(repo_path, "new.txt", false, None)?;
+		assert_eq!(diff.hunks[0].lines.len(), 21);
+
+		stage_add_file(repo_path, Path::new("new.txt"))?;
+		crate::sync::stage_add_all(repo_path, "old.txt", None)?;
+
+		let diff =
+			get_diff(repo_path, "new.txt", true, Some(options))?;
+		assert_eq!(diff.hunks.len(), 1);
+		assert_eq!(diff.hunks[0].lines.len(), 9);
+
+		Ok(())
+	}
 }
@@ -0,0 +1,394 @@
+//! open external diff and merge tools configured in git config
+
+use super::{
+	config::get_config_string_repo, conflict::find_conflict,
+	repository::repo, utils::work_dir, RepoPath,
+};
+use crate::error::{Error, Result};
+use git2::Repository;
+use scopetime::scope_time;
+use std::{io::Write, path::Path, process::Command};
+use tempfile::NamedTempFile;
+
+/// quotes `path` for use as a single `sh` argument
+fn shell_quote(path: &Path) -> String {
+	format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// replaces `$NAME` and `${NAME}` in `cmd` with the quoted paths
+fn expand_command(cmd: &str, vars: &[(&str, &Path)]) -> String {
+	let mut cmd = cmd.to_string();
+	for (name, path) in vars {
+		let value = shell_quote(path);
+		cmd = cmd
+			.replace(&format!("${{{name}}}"), &value)
+			.replace(&format!("${name}"), &value);
+	}
+
+	cmd
+}
+
+/// resolves the configured difftool command and expands `$LOCAL`,
+/// `$REMOTE` and `$MERGED` like git does
+fn difftool_command(
+	repo: &Repository,
+	file_path: &str,
+	local: &Path,
+	remote: &Path,
+) -> Result<String> {
+	let tool = if let Some(tool) =
+		get_config_string_repo(repo, "diff.tool")?
+	{
+		tool
+	} else {
+		get_config_string_repo(repo, "merge.tool")?
+			.ok_or(Error::NoDifftool)?
+	};
+
+	let cmd = get_config_string_repo(
+		repo,
+		&format!("difftool.{tool}.cmd"),
+	)?
+	.ok_or(Error::NoDifftool)?;
+
+	Ok(expand_command(
+		&cmd,
+		&[
+			("LOCAL", local),
+			("REMOTE", remote),
+			("MERGED", Path::new(file_path)),
+		],
+	))
+}
+
+/// resolves the configured mergetool command and expands `$BASE`,
+/// `$LOCAL`, `$REMOTE` and `$MERGED` like git does
+fn mergetool_command(
+	repo: &Repository,
+	file_path: &str,
+	base: &Path,
+	local: &Path,
+	remote: &Path,
+) -> Result<String> {
+	let tool = get_config_string_repo(repo, "merge.tool")?
+		.ok_or(Error::NoMergetool)?;
+
+	let cmd = get_config_string_repo(
+		repo,
+		&format!("mergetool.{tool}.cmd"),
+	)?
+	.ok_or(Error::NoMergetool)?;
+
+	Ok(expand_command(
+		&cmd,
+		&[
+			("BASE", base),
+			("LOCAL", local),
+			("REMOTE", remote),
+			("MERGED", Path::new(file_path)),
+		],
+	))
+}
+
+/// writes `content` to a temp file named after `file_path` and
+/// `label`, like `foo_LOCAL_1234.txt` in git
+fn temp_file(
+	file_path: &str,
+	label: &str,
+	content: &[u8],
+) -> Result<NamedTempFile> {
+	let path = Path::new(file_path);
+	let stem = path
+		.file_stem()
+		.map(|stem| stem.to_string_lossy().to_string())
+		.unwrap_or_default();
+	let extension = path
+		.extension()
+		.map(|ext| format!(".{}", ext.to_string_lossy()))
+		.unwrap_or_default();
+
+	let mut file = tempfile::Builder::new()
+		.prefix(&format!("{stem}_{label}_"))
+		.suffix(&extension)
+		.tempfile()?;
+	file.write_all(content)?;
+	file.flush()?;
+
+	Ok(file)
+}
+
+/// runs `cmd` with `sh` in `work_dir`
+fn run_tool(cmd: &str, work_dir: &Path) -> Result<()> {
+	log::trace!("run external tool '{cmd}' in '{work_dir:?}'");
+
+	let status = Command::new("sh")
+		.args(["-c", cmd])
+		.current_dir(work_dir)
+		.status()?;
+
+	if status.success() {
+		Ok(())
+	} else {
+		Err(Error::Generic(format!("external tool failed: {status}")))
+	}
+}
+
+/// opens the diff of `file_path` between index and workdir in the
+/// configured difftool and waits for it to exit
+///
+/// Both versions are written to temp files that are removed
+/// afterwards. Returns [`Error::NoDifftool`] if no tool is set up.
+pub fn open_difftool(
+	repo_path: &RepoPath,
+	file_path: &str,
+) -> Result<()> {
+	scope_time!("open_difftool");
+
+	let repo = repo(repo_path)?;
+	let work_dir = work_dir(&repo)?.to_path_buf();
+
+	let index_content = repo
+		.index()?
+		.get_path(Path::new(file_path), 0)
+		.map(|entry| repo.find_blob(entry.id))
+		.transpose()?
+		.map(|blob| blob.content().to_vec())
+		.unwrap_or_default();
+	let work_content =
+		std::fs::read(work_dir.join(file_path)).unwrap_or_default();
+
+	let local = temp_file(file_path, "LOCAL", &index_content)?;
+	let remote = temp_file(file_path, "REMOTE", &work_content)?;
+
+	let cmd = difftool_command(
+		&repo,
+		file_path,
+		local.path(),
+		remote.path(),
+	)?;
+
+	run_tool(&cmd, &work_dir)
+}
+
+/// runs the configured mergetool on the conflicted `file_path` and
+/// marks it resolved once the tool exits successfully
+///
+/// The base, ours and theirs stages are written to temp files, the
+/// tool is expected to write the result to the workdir file.
+/// Returns [`Error::NoMergetool`] if no tool is set up.
+pub fn launch_mergetool(
+	repo_path: &RepoPath,
+	file_path: &str,
+) -> Result<()> {
+	scope_time!("launch_mergetool");
+
+	let repo = repo(repo_path)?;
+	let work_dir = work_dir(&repo)?.to_path_buf();
+	let mut index = repo.index()?;
+
+	let conflict =
+		find_conflict(&index, file_path)?.ok_or_else(|| {
+			Error::Generic(format!("'{file_path}' is not conflicted"))
+		})?;
+
+	let stage_content = |entry: Option<git2::IndexEntry>| {
+		entry.map_or_else(
+			|| Ok(Vec::new()),
+			|entry| {
+				repo.find_blob(entry.id)
+					.map(|blob| blob.content().to_vec())
+			},
+		)
+	};
+
+	let base = temp_file(
+		file_path,
+		"BASE",
+		&stage_content(conflict.ancestor)?,
+	)?;
+	let local =
+		temp_file(file_path, "LOCAL", &stage_content(conflict.our)?)?;
+	let remote = temp_file(
+		file_path,
+		"REMOTE",
+		&stage_content(conflict.their)?,
+	)?;
+
+	let cmd = mergetool_command(
+		&repo,
+		file_path,
+		base.path(),
+		local.path(),
+		remote.path(),
+	)?;
+
+	run_tool(&cmd, &work_dir)?;
+
+	if work_dir.join(file_path).exists() {
+		index.add_path(Path::new(file_path))?;
+	} else {
+		index.remove_path(Path::new(file_path))?;
+	}
+	index.write()?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		checkout_branch, create_branch, merge_branch,
+		tests::{repo_init, write_commit_file},
+		utils::{repo_read_file, repo_write_file},
+		BranchType,
+	};
+
+	#[test]
+	fn test_difftool_command_expansion() {
+		let (_td, repo) = repo_init().unwrap();
+
+		let mut config = repo.config().unwrap();
+		config.set_str("diff.tool", "dummy").unwrap();
+		config
+			.set_str(
+				"difftool.dummy.cmd",
+				"dummy --left $LOCAL --right ${REMOTE} -- $MERGED",
+			)
+			.unwrap();
+
+		let cmd = difftool_command(
+			&repo,
+			"foo.txt",
+			Path::new("/tmp/a_foo.txt"),
+			Path::new("/tmp/it's"),
+		)
+		.unwrap();
+
+		assert_eq!(
+			cmd,
+			r"dummy --left '/tmp/a_foo.txt' --right '/tmp/it'\''s' -- 'foo.txt'"
+		);
+	}
+
+	#[test]
+	fn test_difftool_not_configured() {
+		let (_td, repo) = repo_init().unwrap();
+
+		assert!(matches!(
+			difftool_command(
+				&repo,
+				"foo.txt",
+				Path::new("a"),
+				Path::new("b")
+			),
+			Err(Error::NoDifftool)
+		));
+
+		repo.config().unwrap().set_str("diff.tool", "none").unwrap();
+
+		assert!(matches!(
+			difftool_command(
+				&repo,
+				"foo.txt",
+				Path::new("a"),
+				Path::new("b")
+			),
+			Err(Error::NoDifftool)
+		));
+	}
+
+	#[test]
+	fn test_open_difftool() {
+		let (td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "old", "c1");
+		repo_write_file(&repo, "foo.txt", "new").unwrap();
+
+		let out = td.path().join("out");
+		repo.config().unwrap().set_str("diff.tool", "cat").unwrap();
+		repo.config()
+			.unwrap()
+			.set_str(
+				"difftool.cat.cmd",
+				&format!(
+					"cat $LOCAL $REMOTE > {}",
+					shell_quote(&out)
+				),
+			)
+			.unwrap();
+
+		open_difftool(repo_path, "foo.txt").unwrap();
+
+		assert_eq!(std::fs::read_to_string(out).unwrap(), "oldnew");
+	}
+
+	#[test]
+	fn test_launch_mergetool() {
+		let (td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "base", "c1");
+		create_branch(repo_path, "other").unwrap();
+		write_commit_file(&repo, "foo.txt", "theirs", "c2");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		write_commit_file(&repo, "foo.txt", "ours", "c3");
+		merge_branch(repo_path, "other", BranchType::Local).unwrap();
+
+		let has_conflicts = || {
+			Repository::open(root)
+				.unwrap()
+				.index()
+				.unwrap()
+				.has_conflicts()
+		};
+
+		assert!(has_conflicts());
+
+		assert!(matches!(
+			launch_mergetool(repo_path, "foo.txt"),
+			Err(Error::NoMergetool)
+		));
+
+		let out = td.path();
+		let mut config = repo.config().unwrap();
+		config.set_str("merge.tool", "copy").unwrap();
+		config
+			.set_str(
+				"mergetool.copy.cmd",
+				&format!(
+					"cp $BASE {base} && cp $LOCAL {local} && \
+					cp $REMOTE {remote} && cp $MERGED {merged} && \
+					echo resolved > $MERGED",
+					base = shell_quote(&out.join("base")),
+					local = shell_quote(&out.join("local")),
+					remote = shell_quote(&out.join("remote")),
+					merged = shell_quote(&out.join("merged")),
+				),
+			)
+			.unwrap();
+
+		assert!(launch_mergetool(repo_path, "bar.txt").is_err());
+
+		launch_mergetool(repo_path, "foo.txt").unwrap();
+
+		let read = |name: &str| {
+			std::fs::read_to_string(out.join(name)).unwrap()
+		};
+		assert_eq!(read("base"), "base");
+		assert_eq!(read("local"), "ours");
+		assert_eq!(read("remote"), "theirs");
+		assert!(read("merged").contains("<<<<<<<"));
+
+		assert_eq!(
+			repo_read_file(&repo, "foo.txt").unwrap(),
+			"resolved\n"
+		);
+		assert!(!has_conflicts());
+	}
+}
@@ -0,0 +1,119 @@
+//! fuzzy finding files of the repository
+
+use super::{repository::repo, RepoPath};
+use crate::error::Result;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use git2::{Repository, StatusOptions};
+use scopetime::scope_time;
+use std::collections::BTreeSet;
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatch {
+	///
+	pub path: String,
+	/// higher is better
+	pub score: i64,
+}
+
+/// tracked and untracked but not ignored files
+fn repo_files(repo: &Repository) -> Result<BTreeSet<String>> {
+	let mut files = BTreeSet::new();
+
+	for entry in repo.index()?.iter() {
+		files.insert(String::from_utf8(entry.path)?);
+	}
+
+	let mut options = StatusOptions::new();
+	options
+		.include_untracked(true)
+		.recurse_untracked_dirs(true)
+		.include_ignored(false);
+
+	for status in repo.statuses(Some(&mut options))?.iter() {
+		if status.status().is_wt_new() {
+			if let Some(path) = status.path() {
+				files.insert(path.to_string());
+			}
+		}
+	}
+
+	Ok(files)
+}
+
+/// returns the `limit` best matches of `query` among the tracked and
+/// untracked files, best first
+pub fn find_files(
+	repo_path: &RepoPath,
+	query: &str,
+	limit: usize,
+) -> Result<Vec<FileMatch>> {
+	scope_time!("find_files");
+
+	let repo = repo(repo_path)?;
+	let matcher = SkimMatcherV2::default();
+
+	let mut found = repo_files(&repo)?
+		.into_iter()
+		.filter_map(|path| {
+			matcher
+				.fuzzy_match(&path, query)
+				.map(|score| FileMatch { path, score })
+		})
+		.collect::<Vec<_>>();
+
+	found.sort_by(|a, b| {
+		b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path))
+	});
+	found.truncate(limit);
+
+	Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		stage_add_all,
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+	use std::fs;
+
+	#[test]
+	fn test_find_files() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		fs::create_dir_all(root.join("src")).unwrap();
+		fs::create_dir_all(root.join("docs")).unwrap();
+		fs::create_dir_all(root.join("target")).unwrap();
+
+		repo_write_file(&repo, ".gitignore", "target/\n").unwrap();
+		repo_write_file(&repo, "src/main.rs", "").unwrap();
+		repo_write_file(&repo, "docs/manual.md", "").unwrap();
+		stage_add_all(repo_path, "*", None).unwrap();
+		write_commit_file(&repo, "src/lib.rs", "", "c1");
+
+		repo_write_file(&repo, "src/domain.rs", "").unwrap();
+		repo_write_file(&repo, "target/main.rs", "").unwrap();
+
+		let paths = |query, limit| {
+			find_files(repo_path, query, limit)
+				.unwrap()
+				.into_iter()
+				.map(|m| m.path)
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(
+			paths("main", 10),
+			vec!["src/main.rs", "src/domain.rs"]
+		);
+		assert_eq!(paths("main", 1), vec!["src/main.rs"]);
+		assert_eq!(paths("", 10).len(), 5);
+		assert!(paths("xyz", 10).is_empty());
+	}
+}
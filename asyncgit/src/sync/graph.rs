@@ -0,0 +1,214 @@
+//! lane layout for drawing a commit graph next to the log
+
+use super::{repository::repo, CommitId, RepoPath};
+use crate::error::Result;
+use scopetime::scope_time;
+
+/// layout of a single row of the commit graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphRow {
+	///
+	pub id: CommitId,
+	/// lane the commit is drawn in
+	pub column: usize,
+	/// lanes passing this row without touching the commit
+	pub passing: Vec<usize>,
+	/// other lanes from above ending in this commit
+	pub merging: Vec<usize>,
+	/// lanes the parents continue in below this row, first parent
+	/// first
+	pub parents: Vec<usize>,
+}
+
+/// assigns lanes to `commits` which are expected newest first in
+/// topological order, like the log returns them
+///
+/// A lane is kept by the first parent of a commit, other parents get
+/// an existing lane already waiting for them or the leftmost free
+/// one. Parents outside of `commits` keep their lane open.
+pub fn get_commit_graph(
+	repo_path: &RepoPath,
+	commits: &[CommitId],
+) -> Result<Vec<GraphRow>> {
+	scope_time!("get_commit_graph");
+
+	let repo = repo(repo_path)?;
+
+	// commit each lane is waiting for
+	let mut lanes: Vec<Option<CommitId>> = Vec::new();
+	let mut rows = Vec::with_capacity(commits.len());
+
+	for id in commits {
+		let parents = repo
+			.find_commit(id.get_oid())?
+			.parent_ids()
+			.map(CommitId::new)
+			.collect::<Vec<_>>();
+
+		let column = lanes
+			.iter()
+			.position(|lane| lane.as_ref() == Some(id))
+			.unwrap_or_else(|| free_lane(&mut lanes));
+
+		let mut merging = Vec::new();
+		let mut passing = Vec::new();
+		for (idx, lane) in lanes.iter_mut().enumerate() {
+			if idx == column {
+				continue;
+			}
+
+			match lane {
+				Some(lane_id) if lane_id == id => {
+					merging.push(idx);
+					*lane = None;
+				}
+				Some(_) => passing.push(idx),
+				None => (),
+			}
+		}
+
+		lanes[column] = parents.first().copied();
+
+		let mut parent_lanes = Vec::with_capacity(parents.len());
+		if !parents.is_empty() {
+			parent_lanes.push(column);
+		}
+		for parent in parents.iter().skip(1) {
+			let lane = lanes
+				.iter()
+				.position(|lane| lane.as_ref() == Some(parent))
+				.unwrap_or_else(|| {
+					let lane = free_lane(&mut lanes);
+					lanes[lane] = Some(*parent);
+					lane
+				});
+			parent_lanes.push(lane);
+		}
+
+		while lanes.last().map_or(false, Option::is_none) {
+			lanes.pop();
+		}
+
+		rows.push(GraphRow {
+			id: *id,
+			column,
+			passing,
+			merging,
+			parents: parent_lanes,
+		});
+	}
+
+	Ok(rows)
+}
+
+/// returns the leftmost unused lane, adding one if all are taken
+fn free_lane(lanes: &mut Vec<Option<CommitId>>) -> usize {
+	lanes.iter().position(Option::is_none).unwrap_or_else(|| {
+		lanes.push(None);
+		lanes.len() - 1
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::tests::repo_init_empty;
+	use git2::{Commit, Oid, Repository};
+
+	fn commit(repo: &Repository, parents: &[Oid], msg: &str) -> Oid {
+		let sig = repo.signature().unwrap();
+		let tree_id = repo.index().unwrap().write_tree().unwrap();
+		let tree = repo.find_tree(tree_id).unwrap();
+		let parents = parents
+			.iter()
+			.map(|id| repo.find_commit(*id).unwrap())
+			.collect::<Vec<_>>();
+		let parents = parents.iter().collect::<Vec<&Commit>>();
+
+		repo.commit(None, &sig, &sig, msg, &tree, &parents).unwrap()
+	}
+
+	fn row(
+		id: Oid,
+		column: usize,
+		passing: &[usize],
+		merging: &[usize],
+		parents: &[usize],
+	) -> GraphRow {
+		GraphRow {
+			id: id.into(),
+			column,
+			passing: passing.to_vec(),
+			merging: merging.to_vec(),
+			parents: parents.to_vec(),
+		}
+	}
+
+	#[test]
+	fn test_linear() {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = commit(&repo, &[], "c1");
+		let c2 = commit(&repo, &[c1], "c2");
+
+		assert_eq!(
+			get_commit_graph(repo_path, &[c2.into(), c1.into()])
+				.unwrap(),
+			vec![
+				row(c2, 0, &[], &[], &[0]),
+				row(c1, 0, &[], &[], &[])
+			]
+		);
+	}
+
+	#[test]
+	fn test_merges() {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		//   m2
+		//   | \
+		//   m1 \
+		//   | \ \
+		//   c2 f1 g1
+		//   | /  /
+		//   c1--
+
+		let c1 = commit(&repo, &[], "c1");
+		let c2 = commit(&repo, &[c1], "c2");
+		let f1 = commit(&repo, &[c1], "f1");
+		let g1 = commit(&repo, &[c1], "g1");
+		let m1 = commit(&repo, &[c2, f1], "m1");
+		let m2 = commit(&repo, &[m1, g1], "m2");
+
+		let commits = [m2, m1, g1, f1, c2, c1]
+			.into_iter()
+			.map(CommitId::new)
+			.collect::<Vec<_>>();
+
+		let rows = get_commit_graph(repo_path, &commits).unwrap();
+
+		assert_eq!(
+			rows,
+			vec![
+				row(m2, 0, &[], &[], &[0, 1]),
+				row(m1, 0, &[1], &[], &[0, 2]),
+				row(g1, 1, &[0, 2], &[], &[1]),
+				row(f1, 2, &[0, 1], &[], &[2]),
+				row(c2, 0, &[1, 2], &[], &[0]),
+				row(c1, 0, &[], &[1, 2], &[]),
+			]
+		);
+
+		// the layout only depends on the window
+		assert_eq!(
+			get_commit_graph(repo_path, &commits).unwrap(),
+			rows
+		);
+	}
+}
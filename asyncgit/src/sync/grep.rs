@@ -0,0 +1,203 @@
+//! searching the contents of tracked files
+
+use super::{
+	repository::repo, utils::INDEX_ENTRY_STAGE_MASK, RepoPath,
+};
+use crate::error::{Error, Result};
+use git2::FileMode;
+use scopetime::scope_time;
+
+///
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GrepOptions {
+	///
+	pub case_insensitive: bool,
+	/// stop searching after this many matches
+	pub max_results: Option<usize>,
+}
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+	///
+	pub path: String,
+	/// 1-based
+	pub line_number: usize,
+	/// matched line without its line ending
+	pub line: String,
+}
+
+/// searches the staged content of all files in the index for the
+/// regular expression `pattern`, binary files, submodules and
+/// conflicted files are skipped
+///
+/// Searching the index only covers tracked files and therefore
+/// respects `.gitignore`.
+pub fn grep(
+	repo_path: &RepoPath,
+	pattern: &str,
+	options: GrepOptions,
+) -> Result<Vec<GrepMatch>> {
+	scope_time!("grep");
+
+	let re = regex::RegexBuilder::new(pattern)
+		.case_insensitive(options.case_insensitive)
+		.build()
+		.map_err(|e| {
+			Error::Generic(format!("invalid search pattern: {e}"))
+		})?;
+	let max_results = options.max_results.unwrap_or(usize::MAX);
+
+	let repo = repo(repo_path)?;
+	let index = repo.index()?;
+
+	let mut matches = Vec::new();
+	if max_results == 0 {
+		return Ok(matches);
+	}
+
+	for entry in index.iter() {
+		// submodules have no blob, the ancestor/ours/theirs stages of
+		// a conflicted file would report the same match several times
+		if entry.mode == u32::from(FileMode::Commit)
+			|| entry.flags & INDEX_ENTRY_STAGE_MASK != 0
+		{
+			continue;
+		}
+
+		let blob = repo.find_blob(entry.id)?;
+		if blob.is_binary() {
+			continue;
+		}
+
+		let path = String::from_utf8(entry.path)?;
+		let content = String::from_utf8_lossy(blob.content());
+
+		for (idx, line) in content.lines().enumerate() {
+			if re.is_match(line) {
+				matches.push(GrepMatch {
+					path: path.clone(),
+					line_number: idx + 1,
+					line: line.to_string(),
+				});
+
+				if matches.len() >= max_results {
+					return Ok(matches);
+				}
+			}
+		}
+	}
+
+	Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		checkout_branch, create_branch, merge_branch, stage_add_file,
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+	use git2::BranchType;
+	use std::path::Path;
+
+	fn found(matches: &[GrepMatch]) -> Vec<(&str, usize)> {
+		matches
+			.iter()
+			.map(|m| (m.path.as_str(), m.line_number))
+			.collect()
+	}
+
+	#[test]
+	fn test_grep() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(
+			&repo,
+			"a.txt",
+			"foo\nbar\nFoo bar\n",
+			"c1",
+		);
+		repo_write_file(&repo, "b.txt", "nothing\n\nfoobar\n")
+			.unwrap();
+		stage_add_file(repo_path, Path::new("b.txt")).unwrap();
+		repo_write_file(&repo, "untracked.txt", "foo\n").unwrap();
+
+		let matches =
+			grep(repo_path, "foo", GrepOptions::default()).unwrap();
+
+		assert_eq!(found(&matches), vec![("a.txt", 1), ("b.txt", 3)]);
+		assert_eq!(matches[1].line, "foobar");
+
+		let matches = grep(
+			repo_path,
+			"^foo",
+			GrepOptions {
+				case_insensitive: true,
+				max_results: None,
+			},
+		)
+		.unwrap();
+
+		assert_eq!(
+			found(&matches),
+			vec![("a.txt", 1), ("a.txt", 3), ("b.txt", 3)]
+		);
+
+		let matches = grep(
+			repo_path,
+			"bar",
+			GrepOptions {
+				case_insensitive: false,
+				max_results: Some(2),
+			},
+		)
+		.unwrap();
+
+		assert_eq!(found(&matches), vec![("a.txt", 2), ("a.txt", 3)]);
+
+		assert!(grep(repo_path, "(", GrepOptions::default()).is_err());
+	}
+
+	#[test]
+	fn test_grep_skips_gitlinks_and_conflicts() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "foo base\n", "c1");
+		create_branch(repo_path, "other").unwrap();
+		write_commit_file(&repo, "a.txt", "foo other\n", "c2");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		let head =
+			write_commit_file(&repo, "a.txt", "foo master\n", "c3");
+
+		merge_branch(repo_path, "other", BranchType::Local).unwrap();
+
+		let mut index = repo.index().unwrap();
+		index.read(true).unwrap();
+		assert!(index.has_conflicts());
+
+		// a submodule pointing to a commit, not a blob
+		let mut gitlink = index
+			.iter()
+			.find(|entry| entry.flags & INDEX_ENTRY_STAGE_MASK != 0)
+			.unwrap();
+		gitlink.mode = u32::from(FileMode::Commit);
+		gitlink.flags &= !INDEX_ENTRY_STAGE_MASK;
+		gitlink.id = head.into();
+		gitlink.path = b"sub".to_vec();
+		index.add(&gitlink).unwrap();
+		index.write().unwrap();
+
+		let matches =
+			grep(repo_path, "foo", GrepOptions::default()).unwrap();
+
+		assert!(matches.is_empty());
+	}
+}
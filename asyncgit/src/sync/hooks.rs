@@ -1,17 +1,18 @@
-use super::{repository::repo, RepoPath};
+use super::{repository::repo, CommitId, RepoPath};
 use crate::error::{self, Result};
 use scopetime::scope_time;
 use std::{
 	fs::File,
 	io::{Read, Write},
 	path::{Path, PathBuf},
-	process::Command,
+	process::{Command, Stdio},
 	str::FromStr,
 };
 
 const HOOK_POST_COMMIT: &str = "post-commit";
 const HOOK_PRE_COMMIT: &str = "pre-commit";
 const HOOK_COMMIT_MSG: &str = "commit-msg";
+const HOOK_PRE_PUSH: &str = "pre-push";
 const HOOK_COMMIT_MSG_TEMP_FILE: &str = "COMMIT_EDITMSG";
 
 struct HookPaths {
@@ -65,6 +66,15 @@ impl HookPaths {
 	/// this function calls hook scripts based on conventions documented here
 	/// see <https://git-scm.com/docs/githooks>
 	pub fn run_hook(&self, args: &[&str]) -> Result<HookResult> {
+		self.run_hook_with_input(args, &[])
+	}
+
+	/// like `run_hook` but writes `input` to the hooks stdin
+	pub fn run_hook_with_input(
+		&self,
+		args: &[&str],
+		input: &[u8],
+	) -> Result<HookResult> {
 		let arg_str = format!("{:?} {}", self.hook, args.join(" "));
 		// Use -l to avoid "command not found" on Windows.
 		let bash_args =
@@ -74,7 +84,7 @@ impl HookPaths {
 
 		let git_bash = find_bash_executable()
 			.unwrap_or_else(|| PathBuf::from("bash"));
-		let output = Command::new(git_bash)
+		let mut child = Command::new(git_bash)
 			.args(bash_args)
 			.current_dir(&self.pwd)
 			// This call forces Command to handle the Path environment correctly on windows,
@@ -84,7 +94,21 @@ impl HookPaths {
 				"DUMMY_ENV_TO_FIX_WINDOWS_CMD_RUNS",
 				"FixPathHandlingOnWindows",
 			)
-			.output()?;
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()?;
+
+		if let Some(mut stdin) = child.stdin.take() {
+			// hooks are free to not read their input
+			if let Err(e) = stdin.write_all(input) {
+				if e.kind() != std::io::ErrorKind::BrokenPipe {
+					return Err(e.into());
+				}
+			}
+		}
+
+		let output = child.wait_with_output()?;
 
 		if output.status.success() {
 			Ok(HookResult::Ok)
@@ -156,6 +180,72 @@ pub fn hooks_post_commit(repo_path: &RepoPath) -> Result<HookResult> {
 	}
 }
 
+/// a ref update of a push, see `hooks_pre_push`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrePushRef {
+	///
+	pub local_ref: String,
+	/// `None` if the remote ref is deleted
+	pub local_id: Option<CommitId>,
+	///
+	pub remote_ref: String,
+	/// `None` if the remote ref does not exist yet
+	pub remote_id: Option<CommitId>,
+}
+
+impl PrePushRef {
+	fn hook_line(&self) -> String {
+		let id = |id: Option<CommitId>| {
+			id.map_or_else(
+				|| git2::Oid::zero().to_string(),
+				|id| id.to_string(),
+			)
+		};
+
+		format!(
+			"{} {} {} {}\n",
+			self.local_ref,
+			id(self.local_id),
+			self.remote_ref,
+			id(self.remote_id)
+		)
+	}
+}
+
+/// this hook is documented here <https://git-scm.com/docs/githooks#_pre_push>
+///
+/// The hook gets the name and url of `remote` as parameters and one
+/// line per entry of `refs` on stdin.
+pub fn hooks_pre_push(
+	repo_path: &RepoPath,
+	remote: &str,
+	refs: &[PrePushRef],
+) -> Result<HookResult> {
+	scope_time!("hooks_pre_push");
+
+	let hook = HookPaths::new(repo_path, HOOK_PRE_PUSH)?;
+
+	if hook.is_executable() {
+		let url = repo(repo_path)?
+			.find_remote(remote)
+			.ok()
+			.and_then(|remote| remote.url().map(String::from))
+			.unwrap_or_else(|| remote.to_string());
+
+		let input = refs
+			.iter()
+			.map(PrePushRef::hook_line)
+			.collect::<String>();
+
+		Ok(hook.run_hook_with_input(
+			&[remote, url.as_str()],
+			input.as_bytes(),
+		)?)
+	} else {
+		Ok(HookResult::Ok)
+	}
+}
+
 ///
 #[derive(Debug, PartialEq, Eq)]
 pub enum HookResult {
@@ -340,6 +430,50 @@ exit 1
 		assert!(res != HookResult::Ok);
 	}
 
+	#[test]
+	fn test_pre_push() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		repo.remote("origin", "https://example.com/repo.git")
+			.unwrap();
+
+		let head =
+			CommitId::new(repo.head().unwrap().target().unwrap());
+		let refs = [PrePushRef {
+			local_ref: String::from("refs/heads/master"),
+			local_id: Some(head),
+			remote_ref: String::from("refs/heads/master"),
+			remote_id: None,
+		}];
+
+		assert_eq!(
+			hooks_pre_push(repo_path, "origin", &refs).unwrap(),
+			HookResult::Ok
+		);
+
+		let hook = b"#!/bin/sh
+echo \"$1 $2\" >&2
+cat >&2
+exit 1
+	";
+
+		create_hook(repo_path, HOOK_PRE_PUSH, hook);
+		let result =
+			hooks_pre_push(repo_path, "origin", &refs).unwrap();
+
+		let expected = format!(
+			"origin https://example.com/repo.git\nrefs/heads/master {} refs/heads/master {}\n",
+			head.to_string(),
+			git2::Oid::zero()
+		);
+		assert!(
+			matches!(result, HookResult::NotOk(out) if out.ends_with(&expected))
+		);
+	}
+
 	#[test]
 	fn test_pre_commit_fail_hookspath() {
 		let (_td, repo) = repo_init().unwrap();
@@ -19,6 +19,8 @@ pub fn stage_hunk(
 ) -> Result<()> {
 	scope_time!("stage_hunk");
 
+	check_whitespace_options(options)?;
+
 	let repo = repo(repo_path)?;
 
 	let diff = get_diff_raw(&repo, file_path, false, false, options)?;
@@ -45,6 +47,8 @@ pub fn reset_hunk(
 ) -> Result<()> {
 	scope_time!("reset_hunk");
 
+	check_whitespace_options(options)?;
+
 	let repo = repo(repo_path)?;
 
 	let diff = get_diff_raw(&repo, file_path, false, false, options)?;
@@ -69,6 +73,18 @@ pub fn reset_hunk(
 	}
 }
 
+fn check_whitespace_options(
+	options: Option<DiffOptions>,
+) -> Result<()> {
+	if options.map_or(false, |options| options.ignores_whitespace()) {
+		return Err(Error::Generic(String::from(
+			"hunks can not be staged while ignoring whitespace",
+		)));
+	}
+
+	Ok(())
+}
+
 fn find_hunk_index(diff: &Diff, hunk_hash: u64) -> Option<usize> {
 	let mut result = None;
 
@@ -104,6 +120,8 @@ pub fn unstage_hunk(
 ) -> Result<bool> {
 	scope_time!("revert_hunk");
 
+	check_whitespace_options(options)?;
+
 	let repo = repo(repo_path)?;
 
 	let diff = get_diff_raw(&repo, file_path, true, false, options)?;
@@ -153,7 +171,10 @@ mod tests {
 	use super::*;
 	use crate::{
 		error::Result,
-		sync::{diff::get_diff, tests::repo_init_empty},
+		sync::{
+			diff::get_diff,
+			tests::{repo_init_empty, write_commit_file},
+		},
 	};
 	use std::{
 		fs::{self, File},
@@ -191,4 +212,113 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_stage_hunk_zero_context() -> Result<()> {
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| format!("line{i}"))
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "a.txt", &content, "c1");
+		fs::write(
+			root.join("a.txt"),
+			content
+				.replace("line2\n", "x\n")
+				.replace("line15\n", "y\n"),
+		)?;
+
+		let options = Some(DiffOptions {
+			context: 0,
+			..DiffOptions::default()
+		});
+
+		let diff = get_diff(repo_path, "a.txt", false, options)?;
+		assert_eq!(diff.hunks.len(), 2);
+
+		stage_hunk(
+			repo_path,
+			"a.txt",
+			diff.hunks[1].header_hash,
+			options,
+		)?;
+
+		let staged = get_diff(repo_path, "a.txt", true, options)?;
+		assert_eq!(staged.hunks.len(), 1);
+		assert_eq!(
+			staged.hunks[0].header_hash,
+			diff.hunks[1].header_hash
+		);
+		assert_eq!(
+			get_diff(repo_path, "a.txt", false, options)?.hunks.len(),
+			1
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_stage_hunk_ignoring_whitespace() -> Result<()> {
+		let (_td, repo) = repo_init_empty()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a\nb\n", "c1");
+		fs::write(root.join("a.txt"), "a\nc\n")?;
+
+		let diff = get_diff(repo_path, "a.txt", false, None)?;
+		let hash = diff.hunks[0].header_hash;
+
+		for options in [
+			DiffOptions {
+				ignore_whitespace: true,
+				..DiffOptions::default()
+			},
+			DiffOptions {
+				ignore_whitespace_change: true,
+				..DiffOptions::default()
+			},
+			DiffOptions {
+				ignore_whitespace_eol: true,
+				..DiffOptions::default()
+			},
+		] {
+			assert!(stage_hunk(
+				repo_path,
+				"a.txt",
+				hash,
+				Some(options)
+			)
+			.is_err());
+			assert!(reset_hunk(
+				repo_path,
+				"a.txt",
+				hash,
+				Some(options)
+			)
+			.is_err());
+			assert!(unstage_hunk(
+				repo_path,
+				"a.txt",
+				hash,
+				Some(options)
+			)
+			.is_err());
+		}
+
+		stage_hunk(repo_path, "a.txt", hash, None)?;
+
+		assert_eq!(
+			get_diff(repo_path, "a.txt", true, None)?.hunks.len(),
+			1
+		);
+
+		Ok(())
+	}
 }
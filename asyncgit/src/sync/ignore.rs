@@ -3,25 +3,48 @@ use crate::{
 	error::{Error, Result},
 	sync::repository::repo,
 };
+use git2::Repository;
 use scopetime::scope_time;
 use std::{
-	fs::{File, OpenOptions},
+	env,
+	fs::{self, File, OpenOptions},
 	io::{Read, Seek, SeekFrom, Write},
-	path::Path,
+	path::{Path, PathBuf},
 };
 
 static GITIGNORE: &str = ".gitignore";
 
+/// which ignore file a pattern is added to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitignoreScope {
+	/// `.gitignore` in the root of the repository
+	Repo,
+	/// `core.excludesFile`, `$XDG_CONFIG_HOME/git/ignore` if unset
+	Global,
+	/// `.git/info/exclude`, not shared with others
+	Local,
+}
+
 /// add file or path to root ignore file
 pub fn add_to_ignore(
 	repo_path: &RepoPath,
 	path_to_ignore: &str,
 ) -> Result<()> {
-	scope_time!("add_to_ignore");
+	add_to_gitignore(repo_path, path_to_ignore, GitignoreScope::Repo)
+}
+
+/// appends `pattern` to the ignore file of `scope` unless it is
+/// already listed there
+pub fn add_to_gitignore(
+	repo_path: &RepoPath,
+	pattern: &str,
+	scope: GitignoreScope,
+) -> Result<()> {
+	scope_time!("add_to_gitignore");
 
 	let repo = repo(repo_path)?;
 
-	if Path::new(path_to_ignore).file_name()
+	if Path::new(pattern).file_name()
 		== Path::new(GITIGNORE).file_name()
 	{
 		return Err(Error::Generic(String::from(
@@ -29,7 +52,17 @@ pub fn add_to_ignore(
 		)));
 	}
 
-	let ignore_file = work_dir(&repo)?.join(GITIGNORE);
+	let ignore_file = ignore_file_path(&repo, scope)?;
+
+	if ignore_file.exists() {
+		let content = fs::read_to_string(&ignore_file)?;
+
+		if content.lines().any(|line| line.trim_end() == pattern) {
+			return Ok(());
+		}
+	} else if let Some(parent) = ignore_file.parent() {
+		fs::create_dir_all(parent)?;
+	}
 
 	let optional_newline = ignore_file.exists()
 		&& !file_ends_with_newline(&ignore_file)?;
@@ -43,12 +76,260 @@ pub fn add_to_ignore(
 		file,
 		"{}{}",
 		if optional_newline { "\n" } else { "" },
-		path_to_ignore
+		pattern
 	)?;
 
 	Ok(())
 }
 
+fn ignore_file_path(
+	repo: &Repository,
+	scope: GitignoreScope,
+) -> Result<PathBuf> {
+	match scope {
+		GitignoreScope::Repo => Ok(work_dir(repo)?.join(GITIGNORE)),
+		GitignoreScope::Local => {
+			Ok(repo.path().join("info").join("exclude"))
+		}
+		GitignoreScope::Global => {
+			if let Ok(path) =
+				repo.config()?.get_path("core.excludesFile")
+			{
+				return Ok(path);
+			}
+
+			let config_home = env::var_os("XDG_CONFIG_HOME")
+				.filter(|dir| !dir.is_empty())
+				.map(PathBuf::from)
+				.or_else(|| {
+					env::var_os("HOME").map(|home| {
+						PathBuf::from(home).join(".config")
+					})
+				})
+				.ok_or_else(|| {
+					Error::Generic(String::from(
+						"no global ignore file configured",
+					))
+				})?;
+
+			Ok(config_home.join("git").join("ignore"))
+		}
+	}
+}
+
+/// the rule an ignored path matched, like `git check-ignore -v`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreMatch {
+	/// ignore file relative to the workdir, absolute if outside
+	pub source: PathBuf,
+	/// 1-based line of the pattern in `source`
+	pub line: usize,
+	///
+	pub pattern: String,
+}
+
+/// returns the rule ignoring `path`, `None` if it is not ignored
+///
+/// libgit2 does not tell which rule matched so the ignore files are
+/// searched in the order git applies them: `.gitignore` files from
+/// the innermost directory outward, `.git/info/exclude` and the
+/// global excludes file. Like in git the last matching pattern of a
+/// file wins and an ignored parent directory ignores everything
+/// below it.
+pub fn check_ignore(
+	repo_path: &RepoPath,
+	path: &str,
+) -> Result<Option<IgnoreMatch>> {
+	scope_time!("check_ignore");
+
+	let repo = repo(repo_path)?;
+
+	if !repo.is_path_ignored(path)? {
+		return Ok(None);
+	}
+
+	let work_dir = work_dir(&repo)?;
+	let path = Path::new(path);
+
+	let mut sources = Vec::new();
+	for scope in [GitignoreScope::Local, GitignoreScope::Global] {
+		if let Ok(file) = ignore_file_path(&repo, scope) {
+			sources.push(file);
+		}
+	}
+
+	// parents first, git does not look into ignored directories
+	let mut candidates: Vec<&Path> =
+		path.ancestors().filter(|p| *p != Path::new("")).collect();
+	candidates.reverse();
+
+	for candidate in candidates {
+		let is_dir =
+			candidate != path || work_dir.join(candidate).is_dir();
+
+		let per_dir = candidate.ancestors().skip(1).map(|dir| {
+			(work_dir.join(dir).join(GITIGNORE), dir.to_path_buf())
+		});
+		let global =
+			sources.iter().map(|file| (file.clone(), PathBuf::new()));
+
+		for (file, base) in per_dir.chain(global) {
+			let Some((line, pattern)) =
+				last_match(&file, &base, candidate, is_dir)
+			else {
+				continue;
+			};
+
+			if pattern.starts_with('!') {
+				break;
+			}
+
+			let source = file
+				.strip_prefix(work_dir)
+				.map_or_else(|_| file.clone(), Path::to_path_buf);
+
+			return Ok(Some(IgnoreMatch {
+				source,
+				line,
+				pattern,
+			}));
+		}
+	}
+
+	Ok(None)
+}
+
+/// last pattern in `file` (applying to paths below `base`) that
+/// matches `path`, with its 1-based line
+fn last_match(
+	file: &Path,
+	base: &Path,
+	path: &Path,
+	is_dir: bool,
+) -> Option<(usize, String)> {
+	let content = fs::read_to_string(file).ok()?;
+
+	let relative = path
+		.strip_prefix(base)
+		.ok()?
+		.components()
+		.map(|c| c.as_os_str().to_str())
+		.collect::<Option<Vec<_>>>()?
+		.join("/");
+	let file_name = relative.rsplit('/').next().unwrap_or_default();
+
+	let mut found = None;
+	for (idx, line) in content.lines().enumerate() {
+		let pattern = line.trim_end();
+		if pattern.is_empty() || pattern.starts_with('#') {
+			continue;
+		}
+
+		let glob = pattern.strip_prefix('!').unwrap_or(pattern);
+		let (glob, dir_only) = glob
+			.strip_suffix('/')
+			.map_or((glob, false), |glob| (glob, true));
+
+		if dir_only && !is_dir {
+			continue;
+		}
+
+		let (glob, subject) = if glob.contains('/') {
+			(glob.trim_start_matches('/'), relative.as_str())
+		} else {
+			(glob, file_name)
+		};
+
+		let matches = regex::Regex::new(&glob_to_regex(glob))
+			.map_or(false, |re| re.is_match(subject));
+
+		if matches {
+			found = Some((idx + 1, pattern.to_string()));
+		}
+	}
+
+	found
+}
+
+/// translates a gitignore glob into an anchored regular expression
+fn glob_to_regex(glob: &str) -> String {
+	let chars: Vec<char> = glob.chars().collect();
+	let mut re = String::from("^");
+	let mut idx = 0;
+
+	while idx < chars.len() {
+		match chars[idx] {
+			'*' if chars.get(idx + 1) == Some(&'*') => {
+				let at_start = idx == 0 || chars[idx - 1] == '/';
+
+				match chars.get(idx + 2) {
+					Some('/') if at_start => {
+						re.push_str("(?:.*/)?");
+						idx += 3;
+					}
+					None if at_start => {
+						re.push_str(".*");
+						idx += 2;
+					}
+					_ => {
+						re.push_str("[^/]*");
+						idx += 2;
+					}
+				}
+				continue;
+			}
+			'*' => re.push_str("[^/]*"),
+			'?' => re.push_str("[^/]"),
+			'[' => {
+				let start = idx + 1;
+				let first = match chars.get(start) {
+					Some('!' | '^') => start + 1,
+					_ => start,
+				};
+				// a `]` right after the opening bracket is literal
+				let close = chars
+					.iter()
+					.skip(first + 1)
+					.position(|c| *c == ']')
+					.map(|pos| pos + first + 1);
+
+				if let Some(close) = close {
+					re.push('[');
+					if first > start {
+						re.push('^');
+					}
+					for c in &chars[first..close] {
+						if matches!(
+							c,
+							'\\' | '[' | ']' | '&' | '~' | '^'
+						) {
+							re.push('\\');
+						}
+						re.push(*c);
+					}
+					re.push(']');
+					idx = close + 1;
+					continue;
+				}
+
+				re.push_str("\\[");
+			}
+			'\\' if idx + 1 < chars.len() => {
+				re.push_str(&regex::escape(
+					&chars[idx + 1].to_string(),
+				));
+				idx += 2;
+				continue;
+			}
+			c => re.push_str(&regex::escape(&c.to_string())),
+		}
+		idx += 1;
+	}
+
+	re.push('$');
+	re
+}
+
 fn file_ends_with_newline(file: &Path) -> Result<bool> {
 	let mut file = File::open(file)?;
 	let size = file.metadata()?.len();
@@ -156,4 +437,112 @@ mod tests {
 		let lines = read_lines(root.join(ignore_file_path)).unwrap();
 		assert_eq!(lines.count(), 1);
 	}
+
+	#[test]
+	fn test_add_to_gitignore_scopes() -> Result<()> {
+		let (td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		repo_write_file(&repo, "foo.txt", "test")?;
+		repo_write_file(&repo, "bar.txt", "test")?;
+		repo_write_file(&repo, "baz.txt", "test")?;
+		File::create(root.join(".gitignore"))?.write_all(b"x")?;
+
+		let global_file = td.path().join("global_ignore");
+		repo.config()?.set_str(
+			"core.excludesFile",
+			global_file.to_str().unwrap(),
+		)?;
+
+		add_to_gitignore(repo_path, "foo.txt", GitignoreScope::Repo)?;
+		add_to_gitignore(repo_path, "foo.txt", GitignoreScope::Repo)?;
+		add_to_gitignore(
+			repo_path,
+			"bar.txt",
+			GitignoreScope::Local,
+		)?;
+		add_to_gitignore(
+			repo_path,
+			"baz.txt",
+			GitignoreScope::Global,
+		)?;
+
+		assert_eq!(
+			fs::read_to_string(root.join(".gitignore"))?,
+			"x\nfoo.txt\n"
+		);
+		assert!(fs::read_to_string(
+			repo.path().join("info/exclude")
+		)?
+		.ends_with("bar.txt\n"));
+		assert_eq!(fs::read_to_string(global_file)?, "baz.txt\n");
+
+		let repo = Repository::open(root)?;
+		assert!(repo.is_path_ignored("foo.txt")?);
+		assert!(repo.is_path_ignored("bar.txt")?);
+		assert!(repo.is_path_ignored("baz.txt")?);
+		assert!(!repo.is_path_ignored("other.txt")?);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_check_ignore() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		fs::create_dir_all(root.join("build/out"))?;
+		fs::create_dir_all(root.join("sub"))?;
+		repo_write_file(
+			&repo,
+			".gitignore",
+			"# c\n*.lo[g]\nbuild/\n",
+		)?;
+		repo_write_file(&repo, "sub/.gitignore", "!keep.log\n")?;
+		repo_write_file(&repo, "foo.log", "test")?;
+		repo_write_file(&repo, "sub/keep.log", "test")?;
+		repo_write_file(&repo, "sub/bar.txt", "test")?;
+		repo_write_file(&repo, "build/out/a.o", "test")?;
+
+		assert_eq!(
+			check_ignore(repo_path, "foo.log")?,
+			Some(IgnoreMatch {
+				source: PathBuf::from(".gitignore"),
+				line: 2,
+				pattern: String::from("*.lo[g]"),
+			})
+		);
+		assert_eq!(
+			check_ignore(repo_path, "build/out/a.o")?
+				.map(|m| m.pattern),
+			Some(String::from("build/"))
+		);
+		assert_eq!(check_ignore(repo_path, "sub/bar.txt")?, None);
+		assert_eq!(check_ignore(repo_path, "sub/keep.log")?, None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_glob_to_regex() {
+		let is_match = |glob: &str, path: &str| {
+			regex::Regex::new(&glob_to_regex(glob))
+				.unwrap()
+				.is_match(path)
+		};
+
+		assert!(is_match("*.txt", "a.txt"));
+		assert!(!is_match("*.txt", "dir/a.txt"));
+		assert!(is_match("**/a.txt", "dir/sub/a.txt"));
+		assert!(is_match("**/a.txt", "a.txt"));
+		assert!(is_match("dir/**", "dir/sub/a.txt"));
+		assert!(is_match("a/**/b", "a/x/y/b"));
+		assert!(is_match("[!a]b", "cb"));
+		assert!(!is_match("[!a]b", "ab"));
+		assert!(is_match("\\#x", "#x"));
+	}
 }
@@ -1,10 +1,13 @@
 use super::CommitId;
 use crate::{error::Result, sync::commit_files::get_commit_diff};
-use git2::{Commit, Oid, Repository};
+use git2::{
+	Commit, Delta, DiffFindOptions, DiffOptions, Oid, Repository,
+};
 use std::{
 	cmp::Ordering,
-	collections::{BinaryHeap, HashSet},
-	sync::Arc,
+	collections::{BinaryHeap, HashMap, HashSet},
+	path::Path,
+	sync::{Arc, Mutex},
 };
 
 struct TimeOrderedCommit<'a>(Commit<'a>);
@@ -34,8 +37,17 @@ pub type LogWalkerFilter = Arc<
 	Box<dyn Fn(&Repository, &CommitId) -> Result<bool> + Send + Sync>,
 >;
 
-///
-pub fn diff_contains_file(file_path: String) -> LogWalkerFilter {
+/// matches commits touching `file_path`, with `follow_renames` the
+/// history continues with the old path of the file once a commit
+/// renamed it
+pub fn diff_contains_file(
+	file_path: String,
+	follow_renames: bool,
+) -> LogWalkerFilter {
+	if follow_renames {
+		return follow_file(file_path);
+	}
+
 	Arc::new(Box::new(
 		move |repo: &Repository,
 		      commit_id: &CommitId|
@@ -55,6 +67,152 @@ pub fn diff_contains_file(file_path: String) -> LogWalkerFilter {
 	))
 }
 
+fn follow_file(file_path: String) -> LogWalkerFilter {
+	// path of the file as of each commit queued but not yet visited,
+	// every commit after the first one of a walk was queued by a
+	// child visited before it, so a commit not in here starts a new
+	// walk and drops whatever an unfinished walk left behind
+	let paths: Mutex<HashMap<Oid, String>> = Mutex::default();
+
+	Arc::new(Box::new(
+		move |repo: &Repository,
+		      commit_id: &CommitId|
+		      -> Result<bool> {
+			let mut paths = paths.lock()?;
+			let commit_oid: Oid = (*commit_id).into();
+			if !paths.contains_key(&commit_oid) {
+				paths.clear();
+			}
+			let path = paths
+				.remove(&commit_oid)
+				.unwrap_or_else(|| file_path.clone());
+
+			let mut diff =
+				get_commit_diff(repo, *commit_id, None, None, None)?;
+			diff.find_similar(Some(
+				DiffFindOptions::new().renames(true),
+			))?;
+
+			let delta = diff.deltas().find(|delta| {
+				delta.new_file().path() == Some(Path::new(&path))
+			});
+
+			let parent_path = match &delta {
+				Some(delta) if delta.status() == Delta::Renamed => {
+					delta.old_file().path().map_or_else(
+						|| path.clone(),
+						|p| p.to_string_lossy().to_string(),
+					)
+				}
+				_ => path.clone(),
+			};
+
+			let commit = repo.find_commit((*commit_id).into())?;
+			for parent in commit.parent_ids() {
+				paths
+					.entry(parent)
+					.or_insert_with(|| parent_path.clone());
+			}
+
+			Ok(delta.is_some())
+		},
+	))
+}
+
+/// matches commits whose author name or email contains `author`,
+/// ignoring case
+pub fn author_contains(author: &str) -> LogWalkerFilter {
+	let author = author.to_lowercase();
+
+	Arc::new(Box::new(
+		move |repo: &Repository,
+		      commit_id: &CommitId|
+		      -> Result<bool> {
+			let commit = repo.find_commit((*commit_id).into())?;
+			let signature = commit.author();
+
+			let matches = [signature.name(), signature.email()]
+				.into_iter()
+				.flatten()
+				.any(|part| part.to_lowercase().contains(&author));
+
+			Ok(matches)
+		},
+	))
+}
+
+/// filters for the log walker, all of them need to match
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+	/// the commit message has to contain this
+	pub message_contains: Option<String>,
+	/// the commit has to touch at least one of these paths
+	pub paths: Vec<String>,
+	/// applies to both `message_contains` and `paths`
+	pub case_sensitive: bool,
+}
+
+impl LogFilter {
+	fn message_matches(&self, commit: &Commit) -> bool {
+		self.message_contains.as_ref().map_or(true, |needle| {
+			let message = commit.message().unwrap_or_default();
+
+			if self.case_sensitive {
+				message.contains(needle.as_str())
+			} else {
+				message
+					.to_lowercase()
+					.contains(&needle.to_lowercase())
+			}
+		})
+	}
+
+	fn paths_match(
+		&self,
+		repo: &Repository,
+		commit: &Commit,
+	) -> Result<bool> {
+		if self.paths.is_empty() {
+			return Ok(true);
+		}
+
+		let parent = if commit.parent_count() > 0 {
+			Some(commit.parent(0)?.tree()?)
+		} else {
+			None
+		};
+
+		let mut opts = DiffOptions::new();
+		opts.ignore_case(!self.case_sensitive);
+		for path in &self.paths {
+			opts.pathspec(path);
+		}
+
+		let diff = repo.diff_tree_to_tree(
+			parent.as_ref(),
+			Some(&commit.tree()?),
+			Some(&mut opts),
+		)?;
+
+		Ok(diff.deltas().len() > 0)
+	}
+}
+
+impl From<LogFilter> for LogWalkerFilter {
+	fn from(filter: LogFilter) -> Self {
+		Arc::new(Box::new(
+			move |repo: &Repository,
+			      commit_id: &CommitId|
+			      -> Result<bool> {
+				let commit = repo.find_commit((*commit_id).into())?;
+
+				Ok(filter.message_matches(&commit)
+					&& filter.paths_match(repo, &commit)?)
+			},
+		))
+	}
+}
+
 ///
 pub struct LogWalker<'a> {
 	commits: BinaryHeap<TimeOrderedCommit<'a>>,
@@ -62,6 +220,7 @@ pub struct LogWalker<'a> {
 	limit: usize,
 	repo: &'a Repository,
 	filter: Option<LogWalkerFilter>,
+	first_parent: bool,
 }
 
 impl<'a> LogWalker<'a> {
@@ -78,6 +237,7 @@ impl<'a> LogWalker<'a> {
 			visited: HashSet::with_capacity(1000),
 			repo,
 			filter: None,
+			first_parent: false,
 		})
 	}
 
@@ -87,12 +247,24 @@ impl<'a> LogWalker<'a> {
 		Self { filter, ..self }
 	}
 
+	/// only follow the first parent of merge commits, like
+	/// `git log --first-parent`
+	#[must_use]
+	pub fn first_parent(self, first_parent: bool) -> Self {
+		Self {
+			first_parent,
+			..self
+		}
+	}
+
 	///
 	pub fn read(&mut self, out: &mut Vec<CommitId>) -> Result<usize> {
 		let mut count = 0_usize;
 
 		while let Some(c) = self.commits.pop() {
-			for p in c.0.parents() {
+			let parents =
+				if self.first_parent { 1 } else { usize::MAX };
+			for p in c.0.parents().take(parents) {
 				self.visit(p);
 			}
 
@@ -136,7 +308,7 @@ mod tests {
 		tests::repo_init_empty,
 	};
 	use pretty_assertions::assert_eq;
-	use std::{fs::File, io::Write, path::Path};
+	use std::{fs::File, io::Write};
 
 	#[test]
 	fn test_limit() -> Result<()> {
@@ -148,10 +320,10 @@ mod tests {
 
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		commit(repo_path, "commit1").unwrap();
+		commit(repo_path, "commit1", false).unwrap();
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		let oid2 = commit(repo_path, "commit2").unwrap();
+		let oid2 = commit(repo_path, "commit2", false).unwrap();
 
 		let mut items = Vec::new();
 		let mut walk = LogWalker::new(&repo, 1)?;
@@ -173,10 +345,10 @@ mod tests {
 
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		commit(repo_path, "commit1").unwrap();
+		commit(repo_path, "commit1", false).unwrap();
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(repo_path, file_path).unwrap();
-		let oid2 = commit(repo_path, "commit2").unwrap();
+		let oid2 = commit(repo_path, "commit2", false).unwrap();
 
 		let mut items = Vec::new();
 		let mut walk = LogWalker::new(&repo, 100)?;
@@ -208,19 +380,23 @@ mod tests {
 		File::create(root.join(file_path))?.write_all(b"a")?;
 		stage_add_file(&repo_path, file_path).unwrap();
 
-		let _first_commit_id = commit(&repo_path, "commit1").unwrap();
+		let _first_commit_id =
+			commit(&repo_path, "commit1", false).unwrap();
 
 		File::create(root.join(second_file_path))?.write_all(b"a")?;
 		stage_add_file(&repo_path, second_file_path).unwrap();
 
-		let second_commit_id = commit(&repo_path, "commit2").unwrap();
+		let second_commit_id =
+			commit(&repo_path, "commit2", false).unwrap();
 
 		File::create(root.join(file_path))?.write_all(b"b")?;
 		stage_add_file(&repo_path, file_path).unwrap();
 
-		let _third_commit_id = commit(&repo_path, "commit3").unwrap();
+		let _third_commit_id =
+			commit(&repo_path, "commit3", false).unwrap();
 
-		let diff_contains_baz = diff_contains_file("baz".into());
+		let diff_contains_baz =
+			diff_contains_file("baz".into(), false);
 
 		let mut items = Vec::new();
 		let mut walker = LogWalker::new(&repo, 100)?
@@ -235,7 +411,8 @@ mod tests {
 
 		assert_eq!(items.len(), 0);
 
-		let diff_contains_bar = diff_contains_file("bar".into());
+		let diff_contains_bar =
+			diff_contains_file("bar".into(), false);
 
 		let mut items = Vec::new();
 		let mut walker = LogWalker::new(&repo, 100)?
@@ -246,4 +423,275 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_logwalker_author_filter() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+
+		let tree_id = repo.index()?.write_tree()?;
+		let tree = repo.find_tree(tree_id)?;
+
+		let mut parent: Option<git2::Commit> = None;
+		let mut ids = Vec::new();
+		for (name, email) in [
+			("Alice", "alice@example.com"),
+			("Bob", "bob@example.com"),
+			("alice", "ALICE@work.org"),
+		] {
+			let sig = git2::Signature::now(name, email)?;
+			let parents: Vec<&git2::Commit> = parent.iter().collect();
+			let id = repo.commit(
+				Some("HEAD"),
+				&sig,
+				&sig,
+				name,
+				&tree,
+				&parents,
+			)?;
+			ids.push(CommitId::from(id));
+			parent = Some(repo.find_commit(id)?);
+		}
+
+		let mut items = Vec::new();
+		let mut walker = LogWalker::new(&repo, 100)?
+			.filter(Some(author_contains("ALICE")));
+		walker.read(&mut items)?;
+
+		assert_eq!(items, vec![ids[2], ids[0]]);
+
+		let mut items = Vec::new();
+		let mut walker = LogWalker::new(&repo, 100)?
+			.filter(Some(author_contains("bob@")));
+		walker.read(&mut items)?;
+
+		assert_eq!(items, vec![ids[1]]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_logwalker_log_filter() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let commit_file = |path: &str, msg: &str| -> Result<_> {
+			File::create(root.join(path))?
+				.write_all(msg.as_bytes())?;
+			stage_add_file(repo_path, Path::new(path))?;
+			commit(repo_path, msg, false)
+		};
+
+		let fix_foo = commit_file("foo", "Fix foo")?;
+		let feat_bar = commit_file("bar", "feature bar")?;
+		let fix_bar = commit_file("bar", "fix bar")?;
+
+		let walk = |filter: LogFilter| -> Result<Vec<CommitId>> {
+			let mut items = Vec::new();
+			let mut walker = LogWalker::new(&repo, 100)?
+				.filter(Some(filter.into()));
+			walker.read(&mut items)?;
+			Ok(items)
+		};
+
+		let message_only = LogFilter {
+			message_contains: Some("fix".into()),
+			..LogFilter::default()
+		};
+		assert_eq!(
+			walk(message_only.clone())?,
+			vec![fix_bar, fix_foo]
+		);
+		assert_eq!(
+			walk(LogFilter {
+				case_sensitive: true,
+				..message_only
+			})?,
+			vec![fix_bar]
+		);
+
+		assert_eq!(
+			walk(LogFilter {
+				paths: vec!["bar".into()],
+				..LogFilter::default()
+			})?,
+			vec![fix_bar, feat_bar]
+		);
+
+		assert_eq!(
+			walk(LogFilter {
+				message_contains: Some("fix".into()),
+				paths: vec!["bar".into()],
+				case_sensitive: false,
+			})?,
+			vec![fix_bar]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_logwalker_follow_renames() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content =
+			"some content\nthat stays the same\nacross the rename\n";
+
+		File::create(root.join("old.rs"))?
+			.write_all(content.as_bytes())?;
+		stage_add_file(repo_path, Path::new("old.rs"))?;
+		let created = commit(repo_path, "create", false)?;
+
+		File::create(root.join("other"))?.write_all(b"a")?;
+		stage_add_file(repo_path, Path::new("other"))?;
+		commit(repo_path, "unrelated", false)?;
+
+		std::fs::rename(root.join("old.rs"), root.join("new.rs"))?;
+		stage_add_file(repo_path, Path::new("new.rs"))?;
+		crate::sync::stage_addremoved(
+			repo_path,
+			Path::new("old.rs"),
+		)?;
+		let renamed = commit(repo_path, "rename", false)?;
+
+		let mut items = Vec::new();
+		let mut walker = LogWalker::new(&repo, 100)?
+			.filter(Some(diff_contains_file("new.rs".into(), false)));
+		walker.read(&mut items)?;
+
+		assert_eq!(items, vec![renamed]);
+
+		let filter = diff_contains_file("new.rs".into(), true);
+
+		for _ in 0..2 {
+			let mut items = Vec::new();
+			let mut walker = LogWalker::new(&repo, 100)?
+				.filter(Some(filter.clone()));
+			walker.read(&mut items)?;
+
+			assert_eq!(items, vec![renamed, created]);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_logwalker_follow_renames_paged() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content =
+			"some content\nthat stays the same\nacross the rename\n";
+
+		File::create(root.join("old.rs"))?
+			.write_all(content.as_bytes())?;
+		stage_add_file(repo_path, Path::new("old.rs"))?;
+		let created = commit(repo_path, "create", false)?;
+
+		File::create(root.join("other"))?.write_all(b"a")?;
+		stage_add_file(repo_path, Path::new("other"))?;
+		commit(repo_path, "unrelated", false)?;
+
+		std::fs::rename(root.join("old.rs"), root.join("new.rs"))?;
+		stage_add_file(repo_path, Path::new("new.rs"))?;
+		crate::sync::stage_addremoved(
+			repo_path,
+			Path::new("old.rs"),
+		)?;
+		let renamed = commit(repo_path, "rename", false)?;
+
+		File::create(root.join("new.rs"))?
+			.write_all(format!("{content}more\n").as_bytes())?;
+		stage_add_file(repo_path, Path::new("new.rs"))?;
+		let modified = commit(repo_path, "modify", false)?;
+
+		let filter = diff_contains_file("new.rs".into(), true);
+
+		let mut items = Vec::new();
+		let mut walker =
+			LogWalker::new(&repo, 2)?.filter(Some(filter.clone()));
+		walker.read(&mut items)?;
+		assert_eq!(items, vec![modified, renamed]);
+
+		walker.read(&mut items)?;
+		assert_eq!(items, vec![modified, renamed, created]);
+
+		// a new walk after an unfinished one starts over
+		let mut items = Vec::new();
+		LogWalker::new(&repo, 1)?
+			.filter(Some(filter.clone()))
+			.read(&mut items)?;
+		assert_eq!(items, vec![modified]);
+
+		let mut items = Vec::new();
+		LogWalker::new(&repo, 100)?
+			.filter(Some(filter))
+			.read(&mut items)?;
+		assert_eq!(items, vec![modified, renamed, created]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_logwalker_first_parent() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+
+		let tree_id = repo.index()?.write_tree()?;
+		let tree = repo.find_tree(tree_id)?;
+		let commit_at = |parents: &[Oid], msg: &str, time: i64| {
+			let sig = git2::Signature::new(
+				"name",
+				"email",
+				&git2::Time::new(time, 0),
+			)?;
+			let parents = parents
+				.iter()
+				.map(|id| repo.find_commit(*id))
+				.collect::<std::result::Result<Vec<_>, _>>()?;
+			let parents = parents.iter().collect::<Vec<_>>();
+
+			repo.commit(None, &sig, &sig, msg, &tree, &parents)
+				.map(CommitId::new)
+		};
+
+		let c1 = commit_at(&[], "c1", 1)?;
+		let f1 = commit_at(&[c1.into()], "feature", 2)?;
+		let c2 = commit_at(&[c1.into()], "c2", 3)?;
+		let merge = commit_at(&[c2.into(), f1.into()], "merge", 4)?;
+		repo.reference("refs/heads/master", merge.into(), true, "")?;
+
+		let mut items = Vec::new();
+		LogWalker::new(&repo, 100)?.read(&mut items)?;
+
+		assert_eq!(items, vec![merge, c2, f1, c1]);
+
+		let mut items = Vec::new();
+		LogWalker::new(&repo, 100)?
+			.first_parent(true)
+			.read(&mut items)?;
+
+		assert_eq!(items, vec![merge, c2, c1]);
+
+		let mut items = Vec::new();
+		LogWalker::new(&repo, 100)?
+			.first_parent(true)
+			.filter(Some(
+				LogFilter {
+					message_contains: Some(String::from("c")),
+					..LogFilter::default()
+				}
+				.into(),
+			))
+			.read(&mut items)?;
+
+		assert_eq!(items, vec![c2, c1]);
+
+		Ok(())
+	}
 }
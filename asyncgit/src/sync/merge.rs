@@ -2,15 +2,22 @@ use crate::{
 	error::{Error, Result},
 	sync::{
 		branch::merge_commit::commit_merge_with_head,
+		diff::{diff_trees, FileDiff},
 		rebase::{
 			abort_rebase, continue_rebase, get_rebase_progress,
 		},
 		repository::repo,
-		reset_stage, reset_workdir, CommitId,
+		reset_stage, reset_workdir,
+		utils::INDEX_ENTRY_STAGE_MASK,
+		CommitId,
 	},
 };
-use git2::{BranchType, Commit, MergeOptions, Repository};
+use git2::{
+	BranchType, Commit, Index, MergeOptions, Repository,
+	RepositoryState,
+};
 use scopetime::scope_time;
+use std::path::Path;
 
 use super::{
 	rebase::{RebaseProgress, RebaseState},
@@ -49,6 +56,48 @@ pub fn abort_pending_state(repo_path: &RepoPath) -> Result<()> {
 	Ok(())
 }
 
+/// aborts a pending merge restoring the state before it started
+pub fn abort_merge(repo_path: &RepoPath) -> Result<()> {
+	scope_time!("abort_merge");
+
+	let repo = repo(repo_path)?;
+
+	if repo.state() != RepositoryState::Merge {
+		return Err(Error::Generic("no merge in progress".into()));
+	}
+
+	abort_pending_state(repo_path)
+}
+
+/// creates the merge commit of a pending merge with `msg`
+///
+/// Fails with `Error::UnresolvedConflicts` while the index still has
+/// conflicts.
+pub fn conclude_merge(
+	repo_path: &RepoPath,
+	msg: &str,
+) -> Result<CommitId> {
+	scope_time!("conclude_merge");
+
+	let repo = repo(repo_path)?;
+
+	if repo.state() != RepositoryState::Merge {
+		return Err(Error::Generic("no merge in progress".into()));
+	}
+
+	if repo.index()?.has_conflicts() {
+		return Err(Error::UnresolvedConflicts);
+	}
+
+	let ids = mergehead_ids(repo_path)?;
+	let mut commits = Vec::with_capacity(ids.len());
+	for id in ids {
+		commits.push(repo.find_commit(id.into())?);
+	}
+
+	commit_merge_with_head(&repo, &commits, msg)
+}
+
 ///
 pub fn merge_branch(
 	repo_path: &RepoPath,
@@ -120,6 +169,93 @@ pub fn merge_branch_repo(
 	Ok(())
 }
 
+/// changes merging `branch` into `HEAD` would bring, the merge is done
+/// in memory leaving index and workdir alone
+///
+/// `branch` is any revision naming a commit like `feature` or
+/// `origin/main`. Files that would conflict are shown as in `HEAD`,
+/// [`merge_preview_conflicts`] reports which those are.
+pub fn merge_preview(
+	repo_path: &RepoPath,
+	branch: &str,
+) -> Result<FileDiff> {
+	scope_time!("merge_preview");
+
+	let repo = repo(repo_path)?;
+	let head = repo.head()?.peel_to_commit()?;
+	let mut index = merge_preview_index(&repo, &head, branch)?;
+
+	let conflicts = index
+		.conflicts()?
+		.map(|conflict| conflict.map(|c| (c.our, c.their)))
+		.collect::<std::result::Result<Vec<_>, _>>()?;
+
+	// keep our side of conflicting files
+	for (ours, theirs) in conflicts {
+		let Some(path) = ours
+			.as_ref()
+			.or(theirs.as_ref())
+			.map(|entry| entry.path.clone())
+		else {
+			continue;
+		};
+
+		index.remove_path(Path::new(
+			String::from_utf8_lossy(&path).as_ref(),
+		))?;
+
+		if let Some(mut ours) = ours {
+			ours.flags &= !INDEX_ENTRY_STAGE_MASK;
+			index.add(&ours)?;
+		}
+	}
+
+	let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+	let head_tree = head.tree()?;
+
+	diff_trees(&repo, &head_tree, &tree, None)
+}
+
+/// files that would conflict merging `branch` into `HEAD`, see
+/// [`merge_preview`]
+pub fn merge_preview_conflicts(
+	repo_path: &RepoPath,
+	branch: &str,
+) -> Result<Vec<String>> {
+	scope_time!("merge_preview_conflicts");
+
+	let repo = repo(repo_path)?;
+	let head = repo.head()?.peel_to_commit()?;
+	let index = merge_preview_index(&repo, &head, branch)?;
+
+	let mut paths = Vec::new();
+	for conflict in index.conflicts()? {
+		let conflict = conflict?;
+		if let Some(entry) =
+			conflict.our.or(conflict.their).or(conflict.ancestor)
+		{
+			paths.push(String::from_utf8_lossy(&entry.path).into());
+		}
+	}
+
+	Ok(paths)
+}
+
+fn merge_preview_index(
+	repo: &Repository,
+	head: &Commit,
+	branch: &str,
+) -> Result<Index> {
+	let theirs = repo
+		.revparse_single(branch)
+		.and_then(|object| object.peel_to_commit())
+		.map_err(|_| {
+			Error::Generic(format!("unknown branch '{branch}'"))
+		})?;
+
+	Ok(repo.merge_commits(head, &theirs, None)?)
+}
+
 ///
 pub fn merge_msg(repo_path: &RepoPath) -> Result<String> {
 	scope_time!("merge_msg");
@@ -155,10 +291,12 @@ pub fn merge_commit(
 mod tests {
 	use super::*;
 	use crate::sync::{
-		create_branch,
+		checkout_branch, create_branch, repo_state, stage_add_file,
 		tests::{repo_init, write_commit_file},
-		RepoPath,
+		utils::{repo_read_file, repo_write_file},
+		RepoPath, RepoState,
 	};
+	use crate::DiffLineType;
 	use pretty_assertions::assert_eq;
 
 	#[test]
@@ -185,4 +323,149 @@ mod tests {
 
 		assert_eq!(mergeheads[0], c1);
 	}
+
+	#[test]
+	fn test_abort_merge() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "base", "c1");
+		create_branch(repo_path, "foo").unwrap();
+		write_commit_file(&repo, "test.txt", "foo", "c2");
+		write_commit_file(&repo, "new.txt", "new", "c3");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		write_commit_file(&repo, "test.txt", "master", "c4");
+
+		assert!(abort_merge(repo_path).is_err());
+
+		merge_branch(repo_path, "foo", BranchType::Local).unwrap();
+
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Merge);
+		let mut index = repo.index().unwrap();
+		index.read(true).unwrap();
+		assert!(index.has_conflicts());
+
+		abort_merge(repo_path).unwrap();
+
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+		assert!(!repo.path().join("MERGE_HEAD").exists());
+		assert_eq!(
+			repo_read_file(&repo, "test.txt").unwrap(),
+			"master"
+		);
+		assert!(!root.join("new.txt").exists());
+	}
+
+	#[test]
+	fn test_conclude_merge() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "base", "c1");
+		create_branch(repo_path, "foo").unwrap();
+		let theirs =
+			write_commit_file(&repo, "test.txt", "foo", "c2");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		let ours =
+			write_commit_file(&repo, "test.txt", "master", "c3");
+
+		merge_branch(repo_path, "foo", BranchType::Local).unwrap();
+
+		assert!(matches!(
+			conclude_merge(repo_path, "merge"),
+			Err(Error::UnresolvedConflicts)
+		));
+
+		repo_write_file(&repo, "test.txt", "resolved").unwrap();
+		stage_add_file(repo_path, Path::new("test.txt")).unwrap();
+
+		let id = conclude_merge(repo_path, "merge").unwrap();
+
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+
+		let commit = repo.find_commit(id.into()).unwrap();
+		assert_eq!(commit.message(), Some("merge"));
+		assert_eq!(
+			commit.parent_ids().collect::<Vec<_>>(),
+			vec![ours.into(), theirs.into()]
+		);
+		assert_eq!(repo.head().unwrap().target(), Some(id.into()));
+		assert_eq!(
+			repo_read_file(&repo, "test.txt").unwrap(),
+			"resolved"
+		);
+	}
+
+	fn added_lines(diff: &FileDiff) -> Vec<String> {
+		diff.hunks
+			.iter()
+			.flat_map(|hunk| hunk.lines.iter())
+			.filter(|line| line.line_type != DiffLineType::None)
+			.filter(|line| line.line_type != DiffLineType::Header)
+			.map(|line| {
+				assert_eq!(line.line_type, DiffLineType::Add);
+				line.content.trim_end().to_string()
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_merge_preview_fast_forward() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "a\n", "c1");
+		create_branch(repo_path, "foo").unwrap();
+		write_commit_file(&repo, "test.txt", "a\nb\n", "c2");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+
+		let diff = merge_preview(repo_path, "foo").unwrap();
+
+		assert_eq!(added_lines(&diff), vec!["b"]);
+		assert!(merge_preview_conflicts(repo_path, "foo")
+			.unwrap()
+			.is_empty());
+		assert_eq!(repo_read_file(&repo, "test.txt").unwrap(), "a\n");
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+	}
+
+	#[test]
+	fn test_merge_preview_three_way() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a\n", "c1");
+		write_commit_file(&repo, "b.txt", "b\n", "c2");
+		write_commit_file(&repo, "c.txt", "c\n", "c3");
+		create_branch(repo_path, "foo").unwrap();
+		write_commit_file(&repo, "b.txt", "b\nfoo\n", "c4");
+		write_commit_file(&repo, "c.txt", "foo\n", "c5");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		write_commit_file(&repo, "a.txt", "a\nmaster\n", "c6");
+		write_commit_file(&repo, "c.txt", "master\n", "c7");
+
+		let diff = merge_preview(repo_path, "foo").unwrap();
+
+		// only the change to `b.txt` merges cleanly
+		assert_eq!(added_lines(&diff), vec!["foo"]);
+		assert_eq!(
+			merge_preview_conflicts(repo_path, "foo").unwrap(),
+			vec![String::from("c.txt")]
+		);
+
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+		let mut index = repo.index().unwrap();
+		index.read(true).unwrap();
+		assert!(!index.has_conflicts());
+
+		assert!(merge_preview(repo_path, "nope").is_err());
+	}
 }
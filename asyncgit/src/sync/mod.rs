@@ -3,16 +3,23 @@
 //TODO: remove once we have this activated on the toplevel
 #![deny(clippy::expect_used)]
 
+mod bisect;
 pub mod blame;
 pub mod branch;
+mod cherry_pick;
+mod clean;
 mod commit;
 mod commit_details;
 pub mod commit_files;
-mod commit_revert;
 mod commits_info;
 mod config;
+pub mod conflict;
 pub mod cred;
 pub mod diff;
+pub mod difftool;
+pub mod find;
+pub mod graph;
+pub mod grep;
 mod hooks;
 mod hunks;
 mod ignore;
@@ -20,9 +27,12 @@ mod logwalker;
 mod merge;
 mod patches;
 mod rebase;
+pub mod reflog;
 pub mod remotes;
 mod repository;
 mod reset;
+pub mod revert;
+mod revision;
 mod reword;
 mod staging;
 mod stash;
@@ -32,76 +42,146 @@ mod submodules;
 mod tags;
 mod tree;
 pub mod utils;
+mod word_diff;
+pub mod worktree;
 
-pub use blame::{blame_file, BlameHunk, FileBlame};
+pub use bisect::{
+	bisect_mark, bisect_reset, bisect_start, bisect_state,
+	BisectState, BisectStatus,
+};
+pub use blame::{
+	blame_at_commit, blame_file, blame_file_range,
+	blame_file_with_progress, get_commit_for_blame_line, BlameHunk,
+	BlameProgress, FileBlame,
+};
 pub use branch::{
-	branch_compare_upstream, checkout_branch, checkout_commit,
+	branch_ahead_behind, branch_compare_upstream,
+	branch_tracking_info, checkout_branch, checkout_commit,
 	config_is_pull_rebase, create_branch, delete_branch,
-	get_branch_remote, get_branches_info,
+	get_branch_remote, get_branches_info, is_head_detached,
 	merge_commit::merge_upstream_commit,
 	merge_ff::branch_merge_upstream_fastforward,
 	merge_rebase::merge_upstream_rebase, rename::rename_branch,
 	validate_branch_name, BranchCompare, BranchDetails, BranchInfo,
+	TrackingInfo,
+};
+pub use cherry_pick::{
+	abort_cherry_pick, cherry_pick_range, CherryPickResult,
+};
+pub use clean::clean_untracked;
+pub use commit::{
+	add_trailer, amend, amend_commit_author, commit,
+	expand_commit_template, load_commit_template, parse_trailers,
+	squash_last, tag_commit, verify_commit_signature,
+	SignatureStatus, Trailer,
 };
-pub use commit::{amend, commit, tag_commit};
 pub use commit_details::{
 	get_commit_details, CommitDetails, CommitMessage, CommitSignature,
 };
-pub use commit_files::get_commit_files;
-pub use commit_revert::{commit_revert, revert_commit, revert_head};
+pub use commit_files::{
+	get_commit_files, get_commit_files_under, get_commit_stats,
+	CommitStats,
+};
 pub use commits_info::{
 	get_commit_info, get_commits_info, CommitId, CommitInfo,
 };
 pub use config::{
-	get_config_string, untracked_files_config,
-	ShowUntrackedFilesConfig,
+	get_config_string, set_config_string, unset_config,
+	untracked_files_config, ShowUntrackedFilesConfig,
+};
+pub use conflict::{
+	get_conflicts, resolve_conflict, ConflictEntry, ConflictKind,
+	Resolution,
 };
 pub use diff::get_diff_commit;
-pub use git2::BranchType;
+pub use difftool::{launch_mergetool, open_difftool};
+pub use find::{find_files, FileMatch};
+pub use git2::{BranchType, ConfigLevel, Time};
+pub use graph::{get_commit_graph, GraphRow};
+pub use grep::{grep, GrepMatch, GrepOptions};
 pub use hooks::{
-	hooks_commit_msg, hooks_post_commit, hooks_pre_commit, HookResult,
+	hooks_commit_msg, hooks_post_commit, hooks_pre_commit,
+	hooks_pre_push, HookResult, PrePushRef,
 };
 pub use hunks::{reset_hunk, stage_hunk, unstage_hunk};
-pub use ignore::add_to_ignore;
-pub use logwalker::{diff_contains_file, LogWalker, LogWalkerFilter};
+pub use ignore::{
+	add_to_gitignore, add_to_ignore, check_ignore, GitignoreScope,
+	IgnoreMatch,
+};
+pub use logwalker::{
+	author_contains, diff_contains_file, LogFilter, LogWalker,
+	LogWalkerFilter,
+};
 pub use merge::{
-	abort_pending_rebase, abort_pending_state,
-	continue_pending_rebase, merge_branch, merge_commit, merge_msg,
+	abort_merge, abort_pending_rebase, abort_pending_state,
+	conclude_merge, continue_pending_rebase, merge_branch,
+	merge_commit, merge_msg, merge_preview, merge_preview_conflicts,
 	mergehead_ids, rebase_progress,
 };
-pub use rebase::rebase_branch;
+pub use rebase::{
+	get_rebase_todo, rebase_branch, set_rebase_todo,
+	RebaseTodoAction, RebaseTodoLine, RebaseTodoStep,
+};
+pub use reflog::{read_reflog, ReflogEntry};
 pub use remotes::{
-	get_default_remote, get_remotes, push::AsyncProgress,
-	tags::PushTagsProgress,
+	add_remote, clone_repo, get_default_remote, get_remotes,
+	list_remotes,
+	pull::{pull, PullResult, PullStrategy},
+	push::{pre_push_ref, push_force_with_lease, AsyncProgress},
+	remove_remote, rename_remote, set_remote_url,
+	tags::{delete_remote_tag, push_tag, PushTagsProgress},
+	RemoteDetail,
 };
 pub(crate) use repository::repo;
 pub use repository::{RepoPath, RepoPathRef};
-pub use reset::{reset_repo, reset_stage, reset_workdir};
+#[allow(deprecated)]
+pub use reset::reset_path_to_head;
+pub use reset::{
+	reset_hard, reset_mixed, reset_repo, reset_soft, reset_stage,
+	reset_workdir, restore_path_from_head,
+};
+pub use revert::{
+	commit_revert, revert_commit, revert_head, RevertResult,
+};
+pub use revision::{resolve_commit_prefix, resolve_revision};
 pub use reword::reword;
-pub use staging::{discard_lines, stage_lines};
+pub use staging::{
+	discard_lines, file_stage_state, lines_in_range, stage_hunks,
+	stage_hunks_from, stage_lines, toggle_stage_file, FileStageState,
+};
 pub use stash::{
-	get_stashes, stash_apply, stash_drop, stash_pop, stash_save,
+	get_stashes, rename_stash, stash_apply, stash_drop,
+	stash_list_detailed, stash_pop, stash_save, stash_save_pathspec,
+	StashApplyResult, StashDetail, StashPopResult,
 };
 pub use state::{repo_state, RepoState};
 pub use status::is_workdir_clean;
 pub use submodules::{
 	get_submodules, submodule_parent_info, update_submodule,
-	SubmoduleInfo, SubmoduleParentInfo, SubmoduleStatus,
+	SubmoduleInfo, SubmoduleParentInfo, SubmoduleState,
+	SubmoduleStatus,
 };
 pub use tags::{
-	delete_tag, get_tags, get_tags_with_metadata, CommitTags, Tag,
-	TagWithMetadata, Tags,
+	delete_tag, get_tags, get_tags_detailed, get_tags_with_metadata,
+	CommitTags, Tag, TagDetail, TagWithMetadata, Tags,
+};
+pub use tree::{
+	content_is_binary, get_file_at_commit, tree_file_content,
+	tree_files, TreeFile,
 };
-pub use tree::{tree_file_content, tree_files, TreeFile};
 pub use utils::{
 	get_head, get_head_tuple, repo_dir, repo_open_error,
-	stage_add_all, stage_add_file, stage_addremoved, Head,
+	stage_add_all, stage_add_file, stage_addremoved, stage_all,
+	unstage_all, Head,
+};
+pub use worktree::{
+	add_worktree, list_worktrees, remove_worktree, WorktreeInfo,
 };
 
 pub use git2::ResetType;
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
 	use super::{
 		commit,
 		repository::repo,
@@ -155,6 +235,7 @@ mod tests {
 		commit(
 			&repo.workdir().unwrap().to_str().unwrap().into(),
 			commit_name,
+			false,
 		)
 		.unwrap()
 	}
@@ -1,13 +1,21 @@
-use super::diff::{get_diff_raw, DiffOptions, HunkHeader};
+use super::diff::{
+	get_diff_raw, get_diff_raw_untracked_content, DiffOptions,
+	HunkHeader,
+};
 use crate::error::{Error, Result};
 use git2::{Diff, DiffLine, Patch, Repository};
 
 #[allow(clippy::redundant_pub_crate)]
 pub(crate) struct HunkLines<'a> {
+	/// index of the hunk inside the file diff
+	pub index: usize,
 	pub hunk: HunkHeader,
 	pub lines: Vec<DiffLine<'a>>,
 }
 
+/// uses one line of context whatever `DiffOptions::context` the diff
+/// was shown with, applying line selections relies on it and they only
+/// refer to line numbers anyway
 #[allow(clippy::redundant_pub_crate)]
 pub(crate) fn get_file_diff_patch_and_hunklines<'a>(
 	repo: &'a Repository,
@@ -15,16 +23,15 @@ pub(crate) fn get_file_diff_patch_and_hunklines<'a>(
 	is_staged: bool,
 	reverse: bool,
 ) -> Result<(Patch<'a>, Vec<HunkLines<'a>>)> {
-	let diff = get_diff_raw(
-		repo,
-		file,
-		is_staged,
-		reverse,
-		Some(DiffOptions {
-			context: 1,
-			..DiffOptions::default()
-		}),
-	)?;
+	let options = Some(DiffOptions {
+		context: 1,
+		..DiffOptions::default()
+	});
+	let diff = if is_staged {
+		get_diff_raw(repo, file, is_staged, reverse, options)?
+	} else {
+		get_diff_raw_untracked_content(repo, file, reverse, options)?
+	};
 	let patches = get_patches(&diff)?;
 	if patches.len() > 1 {
 		return Err(Error::Generic(String::from("patch error")));
@@ -51,6 +58,7 @@ fn patch_get_hunklines<'a>(
 		let count_lines = patch.num_lines_in_hunk(hunk_idx)?;
 
 		let mut hunk = HunkLines {
+			index: hunk_idx,
 			hunk: HunkHeader::from(hunk),
 			lines: Vec::with_capacity(count_lines),
 		};
@@ -1,5 +1,6 @@
 use git2::{BranchType, Repository};
 use scopetime::scope_time;
+use std::{fs, path::PathBuf};
 
 use crate::{
 	error::{Error, Result},
@@ -106,7 +107,11 @@ pub fn rebase(
 	Ok(RebaseState::Finished)
 }
 
-/// continue pending rebase
+/// continue pending rebase, committing the resolution of the current
+/// step
+///
+/// Fails with `Error::UnresolvedConflicts` while the index still has
+/// conflicts.
 pub fn continue_rebase(
 	repo: &git2::Repository,
 ) -> Result<RebaseState> {
@@ -115,7 +120,7 @@ pub fn continue_rebase(
 		crate::sync::commit::signature_allow_undefined_name(repo)?;
 
 	if repo.index()?.has_conflicts() {
-		return Ok(RebaseState::Conflicted);
+		return Err(Error::UnresolvedConflicts);
 	}
 
 	// try commit current rebase step
@@ -183,6 +188,159 @@ pub fn abort_rebase(repo: &git2::Repository) -> Result<()> {
 	Ok(())
 }
 
+/// action of a single step of an interactive rebase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseTodoAction {
+	///
+	Pick,
+	///
+	Reword,
+	///
+	Edit,
+	///
+	Squash,
+	///
+	Fixup,
+	///
+	Drop,
+}
+
+impl RebaseTodoAction {
+	fn parse(s: &str) -> Option<Self> {
+		match s {
+			"pick" | "p" => Some(Self::Pick),
+			"reword" | "r" => Some(Self::Reword),
+			"edit" | "e" => Some(Self::Edit),
+			"squash" | "s" => Some(Self::Squash),
+			"fixup" | "f" => Some(Self::Fixup),
+			"drop" | "d" => Some(Self::Drop),
+			_ => None,
+		}
+	}
+
+	const fn as_str(self) -> &'static str {
+		match self {
+			Self::Pick => "pick",
+			Self::Reword => "reword",
+			Self::Edit => "edit",
+			Self::Squash => "squash",
+			Self::Fixup => "fixup",
+			Self::Drop => "drop",
+		}
+	}
+}
+
+/// single step of the todo list of an interactive rebase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebaseTodoStep {
+	///
+	pub action: RebaseTodoAction,
+	///
+	pub id: CommitId,
+	///
+	pub summary: String,
+}
+
+/// line of the todo list of an interactive rebase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebaseTodoLine {
+	///
+	Step(RebaseTodoStep),
+	/// commands we do not model like `exec`, `break`, `label` or
+	/// `fixup -C`, kept as they are
+	Other(String),
+}
+
+fn rebase_todo_path(repo: &Repository) -> Result<PathBuf> {
+	let path =
+		repo.path().join("rebase-merge").join("git-rebase-todo");
+
+	if path.exists() {
+		Ok(path)
+	} else {
+		Err(Error::NoInteractiveRebase)
+	}
+}
+
+/// reads the remaining steps of a pending interactive rebase
+/// (`git-rebase-todo`)
+pub fn get_rebase_todo(
+	repo_path: &RepoPath,
+) -> Result<Vec<RebaseTodoLine>> {
+	scope_time!("get_rebase_todo");
+
+	let repo = repo(repo_path)?;
+	let content = fs::read_to_string(rebase_todo_path(&repo)?)?;
+
+	content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(|line| {
+			let mut parts = line.splitn(3, ' ');
+			let command = parts.next().unwrap_or_default();
+
+			let Some(action) = RebaseTodoAction::parse(command)
+			else {
+				return Ok(RebaseTodoLine::Other(line.to_string()));
+			};
+
+			let id = parts.next().ok_or_else(|| {
+				Error::Generic(format!("invalid rebase todo: {line}"))
+			})?;
+
+			// options like in `fixup -C <id>`
+			if id.starts_with('-') {
+				return Ok(RebaseTodoLine::Other(line.to_string()));
+			}
+
+			let id = repo.revparse_single(id)?.peel_to_commit()?.id();
+
+			Ok(RebaseTodoLine::Step(RebaseTodoStep {
+				action,
+				id: id.into(),
+				summary: parts.next().unwrap_or_default().to_string(),
+			}))
+		})
+		.collect()
+}
+
+/// replaces the remaining steps of a pending interactive rebase
+pub fn set_rebase_todo(
+	repo_path: &RepoPath,
+	steps: &[RebaseTodoLine],
+) -> Result<()> {
+	scope_time!("set_rebase_todo");
+
+	let repo = repo(repo_path)?;
+	let path = rebase_todo_path(&repo)?;
+
+	let content = steps
+		.iter()
+		.map(|line| match line {
+			RebaseTodoLine::Step(step) if step.summary.is_empty() => {
+				format!(
+					"{} {}",
+					step.action.as_str(),
+					step.id.to_string()
+				)
+			}
+			RebaseTodoLine::Step(step) => format!(
+				"{} {} {}",
+				step.action.as_str(),
+				step.id.to_string(),
+				step.summary
+			),
+			RebaseTodoLine::Other(line) => line.clone(),
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	fs::write(path, content + "\n")?;
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod test_conflict_free_rebase {
 	use crate::sync::{
@@ -284,17 +442,22 @@ mod test_conflict_free_rebase {
 
 #[cfg(test)]
 mod test_rebase {
-	use crate::sync::{
-		checkout_branch, create_branch,
-		rebase::{
-			abort_rebase, get_rebase_progress, RebaseProgress,
-			RebaseState,
+	use crate::{
+		error::Error,
+		sync::{
+			checkout_branch, continue_pending_rebase, create_branch,
+			rebase::{
+				abort_rebase, get_rebase_progress, RebaseProgress,
+				RebaseState,
+			},
+			rebase_branch, repo_state, stage_add_file,
+			tests::{repo_init, write_commit_file},
+			utils::{repo_read_file, repo_write_file},
+			CommitId, RepoPath, RepoState,
 		},
-		rebase_branch, repo_state,
-		tests::{repo_init, write_commit_file},
-		RepoPath, RepoState,
 	};
 	use git2::BranchType;
+	use std::path::Path;
 
 	#[test]
 	fn test_conflicted_abort() {
@@ -339,5 +502,189 @@ mod test_rebase {
 		abort_rebase(&repo).unwrap();
 
 		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+		assert_eq!(
+			repo_read_file(&repo, "test.txt").unwrap(),
+			"test2"
+		);
+		assert_eq!(
+			CommitId::from(repo.head().unwrap().target().unwrap()),
+			c
+		);
+	}
+
+	#[test]
+	fn test_conflicted_continue() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "test1", "commit1");
+		create_branch(repo_path, "foo").unwrap();
+		write_commit_file(&repo, "test.txt", "test2", "commit2");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		let onto =
+			write_commit_file(&repo, "test.txt", "test3", "commit3");
+		checkout_branch(repo_path, "refs/heads/foo").unwrap();
+
+		let r = rebase_branch(repo_path, "master", BranchType::Local)
+			.unwrap();
+		assert_eq!(r, RebaseState::Conflicted);
+
+		assert!(matches!(
+			continue_pending_rebase(repo_path),
+			Err(Error::UnresolvedConflicts)
+		));
+
+		repo_write_file(&repo, "test.txt", "resolved").unwrap();
+		stage_add_file(repo_path, Path::new("test.txt")).unwrap();
+
+		assert_eq!(
+			continue_pending_rebase(repo_path).unwrap(),
+			RebaseState::Finished
+		);
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+
+		let head = repo.head().unwrap().peel_to_commit().unwrap();
+		assert_eq!(head.message(), Some("commit2"));
+		assert_eq!(
+			head.parent_ids().collect::<Vec<_>>(),
+			vec![onto.into()]
+		);
+		assert_eq!(repo.head().unwrap().shorthand(), Some("foo"));
+		assert_eq!(
+			repo_read_file(&repo, "test.txt").unwrap(),
+			"resolved"
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_rebase_todo {
+	use crate::{
+		error::Error,
+		sync::{
+			rebase::{
+				get_rebase_todo, set_rebase_todo, RebaseTodoAction,
+				RebaseTodoLine, RebaseTodoStep,
+			},
+			tests::{repo_init, write_commit_file},
+			RepoPath,
+		},
+	};
+	use std::{fs, process::Command};
+
+	#[test]
+	fn test_no_rebase() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert!(matches!(
+			get_rebase_todo(repo_path),
+			Err(Error::NoInteractiveRebase)
+		));
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "1", "commit1");
+		write_commit_file(&repo, "test.txt", "2", "commit2");
+		let c3 = write_commit_file(&repo, "test.txt", "3", "commit3");
+
+		// the failing `exec` after the first pick stops the rebase
+		let status = Command::new("git")
+			.args(["rebase", "-i", "--exec", "false", "HEAD~2"])
+			.env("GIT_SEQUENCE_EDITOR", "true")
+			.current_dir(root)
+			.output()
+			.unwrap()
+			.status;
+		assert!(!status.success());
+
+		let mut lines = get_rebase_todo(repo_path).unwrap();
+
+		assert_eq!(
+			lines,
+			vec![
+				RebaseTodoLine::Step(RebaseTodoStep {
+					action: RebaseTodoAction::Pick,
+					id: c3,
+					summary: String::from("commit3"),
+				}),
+				RebaseTodoLine::Other(String::from("exec false")),
+			]
+		);
+
+		if let RebaseTodoLine::Step(step) = &mut lines[0] {
+			step.action = RebaseTodoAction::Reword;
+		}
+		set_rebase_todo(repo_path, &lines).unwrap();
+
+		assert_eq!(get_rebase_todo(repo_path).unwrap(), lines);
+	}
+
+	#[test]
+	fn test_todo_passthrough() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = write_commit_file(&repo, "test.txt", "1", "commit1");
+		let c2 = write_commit_file(&repo, "test.txt", "2", "commit2");
+
+		let todo = repo.path().join("rebase-merge/git-rebase-todo");
+		fs::create_dir(todo.parent().unwrap()).unwrap();
+		fs::write(
+			&todo,
+			[
+				format!("edit {} commit1", c1.get_short_string()),
+				String::from("exec make test"),
+				String::new(),
+				String::from("# comment"),
+				String::from("break"),
+				format!("fixup -C {} commit2", c2.get_short_string()),
+				format!("p {}", c2.get_short_string()),
+			]
+			.join("\n"),
+		)
+		.unwrap();
+
+		let lines = get_rebase_todo(repo_path).unwrap();
+
+		assert_eq!(
+			lines,
+			vec![
+				RebaseTodoLine::Step(RebaseTodoStep {
+					action: RebaseTodoAction::Edit,
+					id: c1,
+					summary: String::from("commit1"),
+				}),
+				RebaseTodoLine::Other(String::from("exec make test")),
+				RebaseTodoLine::Other(String::from("break")),
+				RebaseTodoLine::Other(format!(
+					"fixup -C {} commit2",
+					c2.get_short_string()
+				)),
+				RebaseTodoLine::Step(RebaseTodoStep {
+					action: RebaseTodoAction::Pick,
+					id: c2,
+					summary: String::new(),
+				}),
+			]
+		);
+
+		set_rebase_todo(repo_path, &lines).unwrap();
+
+		assert!(fs::read_to_string(&todo)
+			.unwrap()
+			.ends_with(&format!("\npick {}\n", c2.to_string())));
 	}
 }
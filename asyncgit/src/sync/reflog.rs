@@ -0,0 +1,94 @@
+//! sync git api for reading reflogs
+
+use super::{repository::repo, CommitId, CommitSignature, RepoPath};
+use crate::error::Result;
+use scopetime::scope_time;
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflogEntry {
+	/// commit the reference pointed to before
+	pub old_id: CommitId,
+	/// commit the reference pointed to afterwards
+	pub new_id: CommitId,
+	///
+	pub committer: CommitSignature,
+	///
+	pub message: String,
+}
+
+/// reads the reflog of `reference` (e.g. `HEAD`), newest entry first
+pub fn read_reflog(
+	repo_path: &RepoPath,
+	reference: &str,
+) -> Result<Vec<ReflogEntry>> {
+	scope_time!("read_reflog");
+
+	let repo = repo(repo_path)?;
+	let reflog = repo.reflog(reference)?;
+
+	Ok(reflog
+		.iter()
+		.map(|entry| ReflogEntry {
+			old_id: entry.id_old().into(),
+			new_id: entry.id_new().into(),
+			committer: CommitSignature::from(&entry.committer()),
+			message: entry.message().unwrap_or_default().to_string(),
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		reset_repo,
+		tests::{repo_init, write_commit_file},
+		utils::get_head_repo,
+	};
+	use git2::ResetType;
+
+	#[test]
+	fn test_reflog_after_reset() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let initial = get_head_repo(&repo)?;
+		let c1 = write_commit_file(&repo, "foo.txt", "a", "c1");
+		let c2 = write_commit_file(&repo, "foo.txt", "b", "c2");
+
+		reset_repo(repo_path, c1, ResetType::Hard)?;
+
+		let reflog = read_reflog(repo_path, "HEAD")?;
+
+		assert!(reflog.len() >= 3);
+
+		assert_eq!(reflog[0].old_id, c2);
+		assert_eq!(reflog[0].new_id, c1);
+		assert!(reflog[0].message.starts_with("reset"));
+
+		assert_eq!(reflog[1].old_id, c1);
+		assert_eq!(reflog[1].new_id, c2);
+		assert_eq!(reflog[1].message, "commit: c2");
+
+		assert_eq!(reflog[2].old_id, initial);
+		assert_eq!(reflog[2].new_id, c1);
+		assert_eq!(reflog[2].committer.name, "name");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_reflog_missing_reference() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert!(read_reflog(repo_path, "refs/heads/nope")?.is_empty());
+
+		Ok(())
+	}
+}
@@ -1,16 +1,52 @@
 use super::push::ProgressNotification;
 use crate::{error::Result, sync::cred::BasicAuthCredential};
 use crossbeam_channel::Sender;
-use git2::{Cred, Error as GitError, RemoteCallbacks};
-use std::sync::{
-	atomic::{AtomicBool, Ordering},
-	Arc, Mutex,
+use git2::{
+	Config, Cred, CredentialType, Error as GitError, RemoteCallbacks,
+	Repository,
+};
+use std::{
+	path::PathBuf,
+	sync::{Arc, Mutex},
 };
 
 ///
 #[derive(Default, Clone)]
 pub struct CallbackStats {
-	pub push_rejected_msg: Option<(String, String)>,
+	/// every reference the remote reported on with its rejection
+	/// message, if any
+	pub push_updates: Vec<(String, Option<String>)>,
+}
+
+/// ways to authenticate, in the order they are tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CredentialMethod {
+	SshAgent,
+	CredentialHelper,
+	UserPass,
+	Username,
+	Default,
+}
+
+impl CredentialMethod {
+	const ORDER: [Self; 5] = [
+		Self::SshAgent,
+		Self::CredentialHelper,
+		Self::UserPass,
+		Self::Username,
+		Self::Default,
+	];
+}
+
+#[derive(Debug, Default)]
+struct CredentialState {
+	/// methods that were rejected or could not provide a credential
+	tried: Vec<CredentialMethod>,
+	/// method handed out last, not known to work yet
+	pending: Option<CredentialMethod>,
+	/// method the remote accepted before, reused by later connections
+	/// of the same operation
+	accepted: Option<CredentialMethod>,
 }
 
 ///
@@ -19,7 +55,8 @@ pub struct Callbacks {
 	sender: Option<Sender<ProgressNotification>>,
 	basic_credential: Option<BasicAuthCredential>,
 	stats: Arc<Mutex<CallbackStats>>,
-	first_call_to_credentials: Arc<AtomicBool>,
+	credentials: Arc<Mutex<CredentialState>>,
+	git_dir: Option<PathBuf>,
 }
 
 impl Callbacks {
@@ -34,9 +71,18 @@ impl Callbacks {
 			sender,
 			basic_credential,
 			stats,
-			first_call_to_credentials: Arc::new(AtomicBool::new(
-				true,
-			)),
+			credentials: Arc::default(),
+			git_dir: None,
+		}
+	}
+
+	/// look up `credential.helper` in the config of `repo` instead
+	/// of the global config only
+	#[must_use]
+	pub fn repo(self, repo: &Repository) -> Self {
+		Self {
+			git_dir: Some(repo.path().to_path_buf()),
+			..self
 		}
 	}
 
@@ -91,7 +137,9 @@ impl Callbacks {
 			},
 		);
 
+		let this = self.clone();
 		callbacks.sideband_progress(move |data| {
+			this.credentials_accepted();
 			log::debug!(
 				"sideband transfer: '{}'",
 				String::from_utf8_lossy(data).trim()
@@ -102,11 +150,21 @@ impl Callbacks {
 		callbacks
 	}
 
+	/// the remote only sends data once authentication succeeded
+	fn credentials_accepted(&self) {
+		if let Ok(mut state) = self.credentials.lock() {
+			if let Some(method) = state.pending.take() {
+				state.accepted = Some(method);
+			}
+		}
+	}
+
 	fn push_update_reference(
 		&self,
 		reference: &str,
 		msg: Option<&str>,
 	) {
+		self.credentials_accepted();
 		log::debug!(
 			"push_update_reference: '{}' {:?}",
 			reference,
@@ -114,8 +172,9 @@ impl Callbacks {
 		);
 
 		if let Ok(mut stats) = self.stats.lock() {
-			stats.push_rejected_msg = msg
-				.map(|msg| (reference.to_string(), msg.to_string()));
+			stats
+				.push_updates
+				.push((reference.to_string(), msg.map(String::from)));
 		}
 	}
 
@@ -125,6 +184,7 @@ impl Callbacks {
 		total: usize,
 		current: usize,
 	) {
+		self.credentials_accepted();
 		log::debug!("packing: {:?} - {}/{}", stage, current, total);
 		self.sender.clone().map(|sender| {
 			sender.send(ProgressNotification::Packing {
@@ -136,20 +196,24 @@ impl Callbacks {
 	}
 
 	fn transfer_progress(&self, p: &git2::Progress) {
+		self.credentials_accepted();
 		log::debug!(
-			"transfer: {}/{}",
+			"transfer: {}/{} ({} B)",
 			p.received_objects(),
-			p.total_objects()
+			p.total_objects(),
+			p.received_bytes()
 		);
 		self.sender.clone().map(|sender| {
 			sender.send(ProgressNotification::Transfer {
 				objects: p.received_objects(),
 				total_objects: p.total_objects(),
+				received_bytes: p.received_bytes(),
 			})
 		});
 	}
 
 	fn update_tips(&self, name: &str, a: git2::Oid, b: git2::Oid) {
+		self.credentials_accepted();
 		log::debug!("update tips: '{}' [{}] [{}]", name, a, b);
 		self.sender.clone().map(|sender| {
 			sender.send(ProgressNotification::UpdateTips {
@@ -166,6 +230,7 @@ impl Callbacks {
 		total: usize,
 		bytes: usize,
 	) {
+		self.credentials_accepted();
 		log::debug!("progress: {}/{} ({} B)", current, total, bytes,);
 		self.sender.clone().map(|sender| {
 			sender.send(ProgressNotification::PushTransfer {
@@ -184,7 +249,7 @@ impl Callbacks {
 		&self,
 		url: &str,
 		username_from_url: Option<&str>,
-		allowed_types: git2::CredentialType,
+		allowed_types: CredentialType,
 	) -> std::result::Result<Cred, GitError> {
 		log::debug!(
 			"creds: '{}' {:?} ({:?})",
@@ -193,36 +258,180 @@ impl Callbacks {
 			allowed_types
 		);
 
-		// This boolean is used to avoid multiple calls to credentials callback.
-		if self.first_call_to_credentials.load(Ordering::Relaxed) {
-			self.first_call_to_credentials
-				.store(false, Ordering::Relaxed);
+		let mut state = self.credentials.lock().map_err(|_| {
+			GitError::from_str("credentials state poisoned")
+		})?;
+
+		// libgit2 calls us again as long as the remote rejects what
+		// we hand out, so every method is only tried once
+		if let Some(method) = state.accepted.take() {
+			if let Some(Ok(cred)) = self.credential(
+				method,
+				url,
+				username_from_url,
+				allowed_types,
+			) {
+				state.pending = Some(method);
+				return Ok(cred);
+			}
+		}
+
+		if let Some(rejected) = state.pending.take() {
+			state.tried.push(rejected);
+		}
+
+		for method in CredentialMethod::ORDER {
+			if state.tried.contains(&method) {
+				continue;
+			}
+
+			match self.credential(
+				method,
+				url,
+				username_from_url,
+				allowed_types,
+			) {
+				Some(Ok(cred)) => {
+					state.pending = Some(method);
+					return Ok(cred);
+				}
+				Some(Err(e)) => {
+					log::debug!("creds: {:?} failed: {}", method, e);
+					state.tried.push(method);
+				}
+				None => (),
+			}
+		}
+
+		if state.tried.is_empty() {
+			Err(GitError::from_str("Couldn't find credentials"))
 		} else {
-			return Err(GitError::from_str("Bad credentials."));
+			Err(GitError::from_str("Bad credentials."))
 		}
+	}
 
-		match &self.basic_credential {
-			_ if allowed_types.is_ssh_key() => username_from_url
-				.map_or_else(
+	/// `None` if `method` does not apply to this request
+	fn credential(
+		&self,
+		method: CredentialMethod,
+		url: &str,
+		username_from_url: Option<&str>,
+		allowed_types: CredentialType,
+	) -> Option<std::result::Result<Cred, GitError>> {
+		let basic = self.basic_credential.as_ref();
+		let username = basic.and_then(|c| c.username.as_deref());
+
+		match method {
+			CredentialMethod::SshAgent
+				if allowed_types.is_ssh_key() =>
+			{
+				Some(username_from_url.or(username).map_or_else(
 					|| {
 						Err(GitError::from_str(
 							" Couldn't extract username from url.",
 						))
 					},
 					Cred::ssh_key_from_agent,
-				),
-			Some(BasicAuthCredential {
-				username: Some(user),
-				password: Some(pwd),
-			}) if allowed_types.is_user_pass_plaintext() => {
-				Cred::userpass_plaintext(user, pwd)
+				))
+			}
+			CredentialMethod::CredentialHelper
+				if allowed_types.is_user_pass_plaintext() =>
+			{
+				Some(self.config().and_then(|config| {
+					Cred::credential_helper(
+						&config,
+						url,
+						username_from_url,
+					)
+				}))
+			}
+			CredentialMethod::UserPass
+				if allowed_types.is_user_pass_plaintext() =>
+			{
+				let password =
+					basic.and_then(|c| c.password.as_deref());
+				username.zip(password).map(|(user, pwd)| {
+					Cred::userpass_plaintext(user, pwd)
+				})
+			}
+			CredentialMethod::Username
+				if allowed_types.is_username() =>
+			{
+				username.map(Cred::username)
 			}
-			Some(BasicAuthCredential {
-				username: Some(user),
-				password: _,
-			}) if allowed_types.is_username() => Cred::username(user),
-			_ if allowed_types.is_default() => Cred::default(),
-			_ => Err(GitError::from_str("Couldn't find credentials")),
+			CredentialMethod::Default
+				if allowed_types.is_default() =>
+			{
+				Some(Cred::default())
+			}
+			_ => None,
 		}
 	}
+
+	fn config(&self) -> std::result::Result<Config, GitError> {
+		self.git_dir
+			.as_ref()
+			.map_or_else(Config::open_default, |path| {
+				Repository::open(path)?.config()
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ssh_request(
+		callbacks: &Callbacks,
+	) -> std::result::Result<Cred, GitError> {
+		callbacks.credentials(
+			"ssh://git@example.com/repo.git",
+			Some("git"),
+			CredentialType::SSH_KEY | CredentialType::USERNAME,
+		)
+	}
+
+	fn pending(callbacks: &Callbacks) -> Option<CredentialMethod> {
+		callbacks.credentials.lock().unwrap().pending
+	}
+
+	#[test]
+	fn test_ssh_agent_first() {
+		let callbacks = Callbacks::new(
+			None,
+			Some(BasicAuthCredential::new(Some("user".into()), None)),
+		);
+
+		assert!(ssh_request(&callbacks).is_ok());
+		assert_eq!(
+			pending(&callbacks),
+			Some(CredentialMethod::SshAgent)
+		);
+
+		// agent rejected, fall back to what the user entered
+		assert!(ssh_request(&callbacks).is_ok());
+		assert_eq!(
+			pending(&callbacks),
+			Some(CredentialMethod::Username)
+		);
+
+		assert!(ssh_request(&callbacks).is_err());
+	}
+
+	#[test]
+	fn test_accepted_credential_is_reused() {
+		let callbacks = Callbacks::new(None, None);
+
+		assert!(ssh_request(&callbacks).is_ok());
+		callbacks.credentials_accepted();
+
+		// a later connection of the same operation
+		assert!(ssh_request(&callbacks).is_ok());
+		assert_eq!(
+			pending(&callbacks),
+			Some(CredentialMethod::SshAgent)
+		);
+
+		assert!(ssh_request(&callbacks).is_err());
+	}
 }
@@ -1,6 +1,7 @@
 //!
 
 mod callbacks;
+pub(crate) mod pull;
 pub(crate) mod push;
 pub(crate) mod tags;
 
@@ -13,8 +14,12 @@ use crate::{
 	ProgressPercent,
 };
 use crossbeam_channel::Sender;
-use git2::{BranchType, FetchOptions, ProxyOptions, Repository};
+use git2::{
+	build::RepoBuilder, BranchType, Direction, FetchOptions,
+	ProxyOptions, Repository,
+};
 use scopetime::scope_time;
+use std::{collections::HashSet, path::Path};
 use utils::bytes2string;
 
 pub use callbacks::Callbacks;
@@ -44,6 +49,100 @@ pub fn get_remotes(repo_path: &RepoPath) -> Result<Vec<String>> {
 	Ok(remotes)
 }
 
+/// name and urls of a remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteDetail {
+	///
+	pub name: String,
+	///
+	pub url: Option<String>,
+	/// `pushurl` if one is configured, otherwise pushes use `url`
+	pub push_url: Option<String>,
+}
+
+/// lists all remotes with their urls
+pub fn list_remotes(
+	repo_path: &RepoPath,
+) -> Result<Vec<RemoteDetail>> {
+	scope_time!("list_remotes");
+
+	let repo = repo(repo_path)?;
+
+	let mut list = Vec::new();
+	for name in repo.remotes()?.iter().flatten() {
+		let remote = repo.find_remote(name)?;
+
+		list.push(RemoteDetail {
+			name: name.to_string(),
+			url: remote.url().map(String::from),
+			push_url: remote.pushurl().map(String::from),
+		});
+	}
+
+	Ok(list)
+}
+
+///
+pub fn add_remote(
+	repo_path: &RepoPath,
+	name: &str,
+	url: &str,
+) -> Result<()> {
+	scope_time!("add_remote");
+
+	let repo = repo(repo_path)?;
+	repo.remote(name, url)?;
+
+	Ok(())
+}
+
+/// renames remote `name` including its remote tracking branches
+pub fn rename_remote(
+	repo_path: &RepoPath,
+	name: &str,
+	new_name: &str,
+) -> Result<()> {
+	scope_time!("rename_remote");
+
+	let repo = repo(repo_path)?;
+	repo.remote_rename(name, new_name)?;
+
+	Ok(())
+}
+
+/// removes remote `name` including its remote tracking branches
+pub fn remove_remote(repo_path: &RepoPath, name: &str) -> Result<()> {
+	scope_time!("remove_remote");
+
+	let repo = repo(repo_path)?;
+	repo.remote_delete(name)?;
+
+	Ok(())
+}
+
+/// sets the fetch `url` of `remote` or its push url if `push` is set
+pub fn set_remote_url(
+	repo_path: &RepoPath,
+	remote: &str,
+	url: &str,
+	push: bool,
+) -> Result<()> {
+	scope_time!("set_remote_url");
+
+	let repo = repo(repo_path)?;
+
+	// `remote_set_url` would create the remote config section
+	repo.find_remote(remote)?;
+
+	if push {
+		repo.remote_set_pushurl(remote, Some(url))?;
+	} else {
+		repo.remote_set_url(remote, url)?;
+	}
+
+	Ok(())
+}
+
 /// tries to find origin or the only remote that is defined if any
 /// in case of multiple remotes and none named *origin* we fail
 pub fn get_default_remote(repo_path: &RepoPath) -> Result<String> {
@@ -97,7 +196,8 @@ fn fetch_from_remote(
 	let mut remote = repo.find_remote(remote)?;
 
 	let mut options = FetchOptions::new();
-	let callbacks = Callbacks::new(progress_sender, basic_credential);
+	let callbacks =
+		Callbacks::new(progress_sender, basic_credential).repo(&repo);
 	options.prune(git2::FetchPrune::On);
 	options.proxy_options(proxy_auto());
 	options.download_tags(git2::AutotagOption::All);
@@ -147,12 +247,14 @@ pub fn fetch_all(
 	Ok(())
 }
 
-/// fetches from upstream/remote for local `branch`
+/// fetches from upstream/remote for local `branch`, with `prune`
+/// remote-tracking refs deleted upstream are removed
 pub(crate) fn fetch(
 	repo_path: &RepoPath,
 	branch: &str,
 	basic_credential: Option<BasicAuthCredential>,
 	progress_sender: Option<Sender<ProgressNotification>>,
+	prune: bool,
 ) -> Result<usize> {
 	scope_time!("fetch");
 
@@ -167,21 +269,149 @@ pub(crate) fn fetch(
 
 	let mut options = FetchOptions::new();
 	options.download_tags(git2::AutotagOption::All);
-	let callbacks = Callbacks::new(progress_sender, basic_credential);
+	let callbacks =
+		Callbacks::new(progress_sender, basic_credential.clone())
+			.repo(&repo);
 	options.remote_callbacks(callbacks.callbacks());
 	options.proxy_options(proxy_auto());
 
 	remote.fetch(&[branch], Some(&mut options), None)?;
 
-	Ok(remote.stats().received_bytes())
+	let bytes = remote.stats().received_bytes();
+
+	if prune {
+		// `FetchPrune::On` only considers the refspecs fetched, which
+		// is just `branch` here
+		prune_remote(&repo, &remote_name, basic_credential)?;
+	}
+
+	Ok(bytes)
+}
+
+/// deletes remote-tracking refs of `remote_name` whose branch does
+/// not exist upstream anymore
+fn prune_remote(
+	repo: &Repository,
+	remote_name: &str,
+	basic_credential: Option<BasicAuthCredential>,
+) -> Result<()> {
+	let mut remote = repo.find_remote(remote_name)?;
+
+	let callbacks = Callbacks::new(None, basic_credential).repo(repo);
+	let connection = remote.connect_auth(
+		Direction::Fetch,
+		Some(callbacks.callbacks()),
+		Some(proxy_auto()),
+	)?;
+	let heads = connection
+		.list()?
+		.iter()
+		.map(|head| head.name().to_string())
+		.collect::<HashSet<_>>();
+	drop(connection);
+
+	let refspecs = remote.refspecs().collect::<Vec<_>>();
+
+	for reference in repo
+		.references_glob(&format!("refs/remotes/{remote_name}/*"))?
+	{
+		let mut reference = reference?;
+
+		if reference.symbolic_target().is_some() {
+			continue;
+		}
+
+		let Some(name) = reference.name().map(String::from) else {
+			continue;
+		};
+
+		let stale = refspecs
+			.iter()
+			.filter(|spec| {
+				spec.direction() == Direction::Fetch
+					&& spec.dst_matches(&name)
+			})
+			.filter_map(|spec| spec.rtransform(&name).ok())
+			.filter_map(|src| src.as_str().map(String::from))
+			.any(|src| !heads.contains(&src));
+
+		if stale {
+			reference.delete()?;
+		}
+	}
+
+	Ok(())
+}
+
+/// clones `url` into `path`, `depth` limits the history to that many
+/// commits
+///
+/// The linked libgit2 cannot fetch shallow, asking for a `depth`
+/// fails with [`Error::ShallowCloneUnsupported`] before anything is
+/// downloaded.
+pub fn clone_repo(
+	url: &str,
+	path: &Path,
+	depth: Option<u32>,
+	basic_credential: Option<BasicAuthCredential>,
+) -> Result<()> {
+	scope_time!("clone_repo");
+
+	if depth.is_some() {
+		return Err(Error::ShallowCloneUnsupported);
+	}
+
+	let mut options = FetchOptions::new();
+	let callbacks = Callbacks::new(None, basic_credential);
+	options.proxy_options(proxy_auto());
+	options.remote_callbacks(callbacks.callbacks());
+
+	RepoBuilder::new().fetch_options(options).clone(url, path)?;
+
+	Ok(())
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::sync::tests::{
-		debug_cmd_print, repo_clone, repo_init,
+		debug_cmd_print, repo_clone, repo_init, write_commit_file,
 	};
+	use tempfile::TempDir;
+
+	#[test]
+	fn test_clone_repo() {
+		let (remote_dir, remote) = repo_init().unwrap();
+		let remote_path = remote_dir.path().to_str().unwrap();
+		let id = write_commit_file(&remote, "foo.txt", "foo", "c1");
+
+		let td = TempDir::new().unwrap();
+		let path = td.path().join("clone");
+
+		clone_repo(remote_path, &path, None, None).unwrap();
+
+		let cloned = Repository::open(&path).unwrap();
+		assert_eq!(
+			cloned.head().unwrap().peel_to_commit().unwrap().id(),
+			id.into()
+		);
+		assert!(path.join("foo.txt").exists());
+	}
+
+	#[test]
+	fn test_clone_repo_shallow_unsupported() {
+		let (remote_dir, _remote) = repo_init().unwrap();
+		let remote_path = remote_dir.path().to_str().unwrap();
+
+		let td = TempDir::new().unwrap();
+		let path = td.path().join("clone");
+
+		assert!(matches!(
+			clone_repo(remote_path, &path, Some(1), None),
+			Err(Error::ShallowCloneUnsupported)
+		));
+		assert!(!path.exists());
+	}
 
 	#[test]
 	fn test_smoke() {
@@ -199,7 +429,79 @@ mod tests {
 
 		assert_eq!(remotes, vec![String::from("origin")]);
 
-		fetch(repo_path, "master", None, None).unwrap();
+		fetch(repo_path, "master", None, None, false).unwrap();
+	}
+
+	#[test]
+	fn test_fetch_prune() {
+		let (remote_dir, remote) = repo_init().unwrap();
+		let remote_path = remote_dir.path().to_str().unwrap();
+
+		let head = remote.head().unwrap().peel_to_commit().unwrap();
+		remote.branch("feature", &head, false).unwrap();
+
+		let (repo_dir, repo) = repo_clone(remote_path).unwrap();
+		let repo_path: &RepoPath =
+			&repo_dir.path().as_os_str().to_str().unwrap().into();
+
+		assert!(repo
+			.find_reference("refs/remotes/origin/feature")
+			.is_ok());
+
+		remote
+			.find_branch("feature", BranchType::Local)
+			.unwrap()
+			.delete()
+			.unwrap();
+
+		fetch(repo_path, "master", None, None, false).unwrap();
+		assert!(repo
+			.find_reference("refs/remotes/origin/feature")
+			.is_ok());
+
+		fetch(repo_path, "master", None, None, true).unwrap();
+		assert!(repo
+			.find_reference("refs/remotes/origin/feature")
+			.is_err());
+	}
+
+	#[test]
+	fn test_fetch_progress() {
+		let (remote_dir, remote) = repo_init().unwrap();
+		let remote_path = remote_dir.path().to_str().unwrap();
+		let (repo_dir, _repo) = repo_clone(remote_path).unwrap();
+		let repo_path: &RepoPath =
+			&repo_dir.path().as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&remote, "foo.txt", "foo", "c1");
+
+		let (sender, receiver) = crossbeam_channel::unbounded();
+
+		fetch(repo_path, "master", None, Some(sender), false)
+			.unwrap();
+
+		let transfers = receiver
+			.try_iter()
+			.filter_map(|notification| match notification {
+				ProgressNotification::Transfer {
+					objects,
+					total_objects,
+					received_bytes,
+				} => Some((objects, total_objects, received_bytes)),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+
+		assert!(!transfers.is_empty());
+
+		let (objects, total_objects, received_bytes) =
+			*transfers.last().unwrap();
+		assert!(total_objects > 0);
+		assert_eq!(objects, total_objects);
+		assert!(received_bytes > 0);
+		assert!(transfers
+			.iter()
+			.all(|(objects, total, _)| objects <= total));
 	}
 
 	#[test]
@@ -304,4 +606,78 @@ mod tests {
 		assert_eq!(res.is_err(), true);
 		assert!(matches!(res, Err(Error::NoDefaultRemoteFound)));
 	}
+
+	#[test]
+	fn test_manage_remotes() -> Result<()> {
+		let (remote_dir, _remote) = repo_init()?;
+		let remote_path = remote_dir.path().to_str().unwrap();
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert!(list_remotes(repo_path)?.is_empty());
+
+		add_remote(repo_path, "upstream", remote_path)?;
+		rename_remote(repo_path, "upstream", "fork")?;
+
+		assert_eq!(
+			list_remotes(repo_path)?,
+			vec![RemoteDetail {
+				name: String::from("fork"),
+				url: Some(String::from(remote_path)),
+				push_url: None,
+			}]
+		);
+
+		remove_remote(repo_path, "fork")?;
+
+		assert!(list_remotes(repo_path)?.is_empty());
+		assert!(remove_remote(repo_path, "fork").is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_remote_url() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		add_remote(repo_path, "origin", "https://old.host/repo")?;
+
+		set_remote_url(
+			repo_path,
+			"origin",
+			"https://new.host/repo",
+			false,
+		)?;
+		set_remote_url(
+			repo_path,
+			"origin",
+			"git@new.host:repo",
+			true,
+		)?;
+
+		assert_eq!(
+			list_remotes(repo_path)?,
+			vec![RemoteDetail {
+				name: String::from("origin"),
+				url: Some(String::from("https://new.host/repo")),
+				push_url: Some(String::from("git@new.host:repo")),
+			}]
+		);
+
+		assert!(set_remote_url(
+			repo_path,
+			"missing",
+			"https://new.host/repo",
+			false
+		)
+		.is_err());
+		assert_eq!(list_remotes(repo_path)?.len(), 1);
+
+		Ok(())
+	}
 }
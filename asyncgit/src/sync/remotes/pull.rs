@@ -0,0 +1,314 @@
+//! fetch and integrate in one go
+
+use super::fetch_from_remote;
+use crate::{
+	error::{Error, Result},
+	sync::{
+		branch::{
+			config_is_pull_rebase, get_branch_name_repo,
+			merge_commit::merge_upstream_commit,
+			merge_ff::branch_merge_upstream_fastforward,
+		},
+		cred::BasicAuthCredential,
+		rebase::{rebase, RebaseState},
+		repository::repo,
+		utils::{conflicted_paths, get_head_repo},
+		CommitId, RepoPath,
+	},
+};
+use git2::BranchType;
+use scopetime::scope_time;
+
+/// how `pull` integrates the fetched branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullStrategy {
+	///
+	Merge,
+	///
+	Rebase,
+}
+
+/// outcome of a `pull`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullResult {
+	/// nothing new upstream
+	UpToDate,
+	/// head was moved to the upstream commit
+	FastForward(CommitId),
+	/// upstream was merged creating this commit
+	MergeCommit(CommitId),
+	/// local commits were rebased onto upstream, this is the new head
+	Rebased(CommitId),
+	/// the merge or rebase stopped on these conflicted paths and is
+	/// left in progress to be resolved
+	Conflicted(Vec<String>),
+}
+
+/// fetches `remote` and integrates its `branch` into the checked out
+/// branch, without a `strategy` `pull.rebase` decides like in git
+///
+/// `remote`/`branch` has to be the upstream of the checked out branch,
+/// the integration itself is done by
+/// [`branch_merge_upstream_fastforward`], [`merge_upstream_commit`]
+/// and the rebase behind [`crate::sync::rebase_branch`]. Unlike
+/// [`crate::sync::merge_upstream_rebase`], which aborts on the first
+/// conflict, that leaves a conflicted rebase in progress just like a
+/// conflicted merge.
+pub fn pull(
+	repo_path: &RepoPath,
+	remote: &str,
+	branch: &str,
+	strategy: Option<PullStrategy>,
+	basic_credential: Option<BasicAuthCredential>,
+) -> Result<PullResult> {
+	scope_time!("pull");
+
+	let strategy = match strategy {
+		Some(strategy) => strategy,
+		None if config_is_pull_rebase(repo_path)? => {
+			PullStrategy::Rebase
+		}
+		None => PullStrategy::Merge,
+	};
+
+	let repo = repo(repo_path)?;
+
+	// fails on a detached head
+	let local = get_branch_name_repo(&repo)?;
+
+	fetch_from_remote(repo_path, remote, basic_credential, None)?;
+
+	let upstream = repo
+		.find_branch(&local, BranchType::Local)?
+		.upstream()?
+		.into_reference();
+
+	if upstream.name()
+		!= Some(&format!("refs/remotes/{remote}/{branch}"))
+	{
+		return Err(Error::Generic(format!(
+			"{remote}/{branch} is not the upstream of {local}"
+		)));
+	}
+
+	let annotated_upstream =
+		repo.reference_to_annotated_commit(&upstream)?;
+
+	let (analysis, pref) =
+		repo.merge_analysis(&[&annotated_upstream])?;
+
+	if analysis.is_up_to_date() {
+		return Ok(PullResult::UpToDate);
+	}
+
+	if analysis.is_fast_forward() && !pref.is_no_fast_forward() {
+		branch_merge_upstream_fastforward(repo_path, &local)?;
+
+		return Ok(PullResult::FastForward(
+			annotated_upstream.id().into(),
+		));
+	}
+
+	if pref.is_fastforward_only() {
+		return Err(Error::Generic(
+			"fast forward merge not possible".into(),
+		));
+	}
+
+	match strategy {
+		PullStrategy::Merge => {
+			Ok(match merge_upstream_commit(repo_path, &local)? {
+				Some(id) => PullResult::MergeCommit(id),
+				None => {
+					PullResult::Conflicted(conflicted_paths(&repo)?)
+				}
+			})
+		}
+		PullStrategy::Rebase => {
+			Ok(match rebase(&repo, &annotated_upstream)? {
+				RebaseState::Finished => {
+					PullResult::Rebased(get_head_repo(&repo)?)
+				}
+				RebaseState::Conflicted => {
+					PullResult::Conflicted(conflicted_paths(&repo)?)
+				}
+			})
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		remotes::push::push_branch,
+		repo_state,
+		tests::{
+			get_commit_ids, repo_clone, repo_init_bare,
+			write_commit_file,
+		},
+		RepoState,
+	};
+
+	#[test]
+	fn test_pull_fast_forward() -> Result<()> {
+		let (r1_dir, _repo) = repo_init_bare()?;
+		let (clone1_dir, clone1) =
+			repo_clone(r1_dir.path().to_str().unwrap())?;
+		let clone1_path: &RepoPath =
+			&clone1_dir.path().to_str().unwrap().into();
+
+		let commit0 =
+			write_commit_file(&clone1, "base.txt", "base", "c0");
+		push_branch(
+			clone1_path,
+			"origin",
+			"master",
+			false,
+			false,
+			None,
+			None,
+		)?;
+
+		let (clone2_dir, clone2) =
+			repo_clone(r1_dir.path().to_str().unwrap())?;
+		let clone2_path: &RepoPath =
+			&clone2_dir.path().to_str().unwrap().into();
+
+		let commit1 =
+			write_commit_file(&clone1, "test.txt", "test", "c1");
+		push_branch(
+			clone1_path,
+			"origin",
+			"master",
+			false,
+			false,
+			None,
+			None,
+		)?;
+
+		let res = pull(clone2_path, "origin", "master", None, None)?;
+
+		assert_eq!(res, PullResult::FastForward(commit1));
+		assert_eq!(
+			get_commit_ids(&clone2, 10),
+			vec![commit1, commit0]
+		);
+		assert!(clone2_dir.path().join("test.txt").exists());
+
+		assert_eq!(
+			pull(clone2_path, "origin", "master", None, None)?,
+			PullResult::UpToDate
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_pull_merge_commit() -> Result<()> {
+		let (r1_dir, _repo) = repo_init_bare()?;
+		let (clone1_dir, clone1) =
+			repo_clone(r1_dir.path().to_str().unwrap())?;
+		let (clone2_dir, clone2) =
+			repo_clone(r1_dir.path().to_str().unwrap())?;
+		let clone1_path: &RepoPath =
+			&clone1_dir.path().to_str().unwrap().into();
+		let clone2_path: &RepoPath =
+			&clone2_dir.path().to_str().unwrap().into();
+
+		let commit1 =
+			write_commit_file(&clone1, "test.txt", "test", "c1");
+		push_branch(
+			clone1_path,
+			"origin",
+			"master",
+			false,
+			false,
+			None,
+			None,
+		)?;
+
+		let commit2 =
+			write_commit_file(&clone2, "test2.txt", "test", "c2");
+
+		let res = pull(
+			clone2_path,
+			"origin",
+			"master",
+			Some(PullStrategy::Merge),
+			None,
+		)?;
+
+		let merge_commit = get_commit_ids(&clone2, 1)[0];
+		assert_eq!(res, PullResult::MergeCommit(merge_commit));
+
+		assert_eq!(repo_state(clone2_path)?, RepoState::Clean);
+
+		let merge = clone2.find_commit(merge_commit.into())?;
+		assert_eq!(
+			merge
+				.parent_ids()
+				.map(CommitId::from)
+				.collect::<Vec<_>>(),
+			vec![commit2, commit1]
+		);
+		assert!(clone2_dir.path().join("test.txt").exists());
+		assert!(clone2_dir.path().join("test2.txt").exists());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_pull_rebase_conflict() -> Result<()> {
+		let (r1_dir, _repo) = repo_init_bare()?;
+		let (clone1_dir, clone1) =
+			repo_clone(r1_dir.path().to_str().unwrap())?;
+		let clone1_path: &RepoPath =
+			&clone1_dir.path().to_str().unwrap().into();
+
+		write_commit_file(&clone1, "test.txt", "base", "c0");
+		push_branch(
+			clone1_path,
+			"origin",
+			"master",
+			false,
+			false,
+			None,
+			None,
+		)?;
+
+		let (clone2_dir, clone2) =
+			repo_clone(r1_dir.path().to_str().unwrap())?;
+		let clone2_path: &RepoPath =
+			&clone2_dir.path().to_str().unwrap().into();
+
+		write_commit_file(&clone1, "test.txt", "upstream", "c1");
+		push_branch(
+			clone1_path,
+			"origin",
+			"master",
+			false,
+			false,
+			None,
+			None,
+		)?;
+
+		write_commit_file(&clone2, "test.txt", "local", "c2");
+
+		let res = pull(
+			clone2_path,
+			"origin",
+			"master",
+			Some(PullStrategy::Rebase),
+			None,
+		)?;
+
+		assert_eq!(
+			res,
+			PullResult::Conflicted(vec![String::from("test.txt")])
+		);
+		assert_eq!(repo_state(clone2_path)?, RepoState::Rebase);
+
+		Ok(())
+	}
+}
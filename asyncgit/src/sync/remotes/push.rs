@@ -6,12 +6,13 @@ use crate::{
 		cred::BasicAuthCredential,
 		remotes::{proxy_auto, Callbacks},
 		repository::repo,
-		CommitId, RepoPath,
+		CommitId, PrePushRef, RepoPath,
 	},
 };
 use crossbeam_channel::Sender;
-use git2::{PackBuilderStage, PushOptions};
+use git2::{ErrorCode, Oid, PackBuilderStage, PushOptions};
 use scopetime::scope_time;
+use std::cell::Cell;
 
 ///
 pub trait AsyncProgress: Clone + Send + Sync {
@@ -39,6 +40,8 @@ pub enum ProgressNotification {
 		objects: usize,
 		///
 		total_objects: usize,
+		///
+		received_bytes: usize,
 	},
 	///
 	PushTransfer {
@@ -91,6 +94,86 @@ impl AsyncProgress for ProgressNotification {
 	}
 }
 
+/// outcome of pushing a single reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushRefStatus {
+	///
+	Ok,
+	/// the remote has commits the push would discard
+	NonFastForward(String),
+	/// a hook on the remote declined the update, protected branches
+	/// usually end up here
+	HookDeclined(String),
+	/// any other rejection, carries the remote's message
+	Rejected(String),
+}
+
+impl PushRefStatus {
+	fn from_msg(msg: Option<String>) -> Self {
+		match msg {
+			None => Self::Ok,
+			Some(msg)
+				if msg.contains("non-fast-forward")
+					|| msg.contains("fetch first") =>
+			{
+				Self::NonFastForward(msg)
+			}
+			Some(msg) if msg.contains("declined") => {
+				Self::HookDeclined(msg)
+			}
+			Some(msg) => Self::Rejected(msg),
+		}
+	}
+}
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushRefUpdate {
+	///
+	pub reference: String,
+	///
+	pub status: PushRefStatus,
+}
+
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PushResult {
+	///
+	pub updates: Vec<PushRefUpdate>,
+}
+
+impl PushResult {
+	/// first update the remote did not accept
+	pub fn rejected(&self) -> Option<&PushRefUpdate> {
+		self.updates
+			.iter()
+			.find(|update| update.status != PushRefStatus::Ok)
+	}
+
+	/// turns a rejection into an error describing its reason
+	pub fn check(&self) -> Result<()> {
+		let Some(update) = self.rejected() else {
+			return Ok(());
+		};
+
+		let reason = match &update.status {
+			PushRefStatus::Ok => String::new(),
+			PushRefStatus::NonFastForward(msg) => {
+				format!("non-fast-forward ({msg})")
+			}
+			PushRefStatus::HookDeclined(msg) => {
+				format!("hook declined ({msg})")
+			}
+			PushRefStatus::Rejected(msg) => msg.clone(),
+		};
+
+		Err(Error::Generic(format!(
+			"push to '{}' rejected: {reason}",
+			update.reference
+		)))
+	}
+}
+
 ///
 #[derive(Copy, Clone, Debug)]
 pub enum PushType {
@@ -106,6 +189,45 @@ impl Default for PushType {
 	}
 }
 
+/// the ref update `push_raw` with these arguments asks the remote for,
+/// to be passed to `hooks_pre_push`
+pub fn pre_push_ref(
+	repo_path: &RepoPath,
+	remote: &str,
+	branch: &str,
+	ref_type: PushType,
+	delete: bool,
+) -> Result<PrePushRef> {
+	let repo = repo(repo_path)?;
+
+	let reference = match ref_type {
+		PushType::Branch => format!("refs/heads/{branch}"),
+		PushType::Tag => format!("refs/tags/{branch}"),
+	};
+
+	let (local_ref, local_id) = if delete {
+		(String::from("(delete)"), None)
+	} else {
+		let id = repo.refname_to_id(&reference)?;
+		(reference.clone(), Some(id.into()))
+	};
+
+	let remote_id = match ref_type {
+		PushType::Branch => repo
+			.refname_to_id(&format!("refs/remotes/{remote}/{branch}"))
+			.ok()
+			.map(CommitId::from),
+		PushType::Tag => None,
+	};
+
+	Ok(PrePushRef {
+		local_ref,
+		local_id,
+		remote_ref: reference,
+		remote_id,
+	})
+}
+
 #[cfg(test)]
 pub fn push_branch(
 	repo_path: &RepoPath,
@@ -125,7 +247,8 @@ pub fn push_branch(
 		delete,
 		basic_credential,
 		progress_sender,
-	)
+	)?
+	.check()
 }
 
 //TODO: clenaup
@@ -139,18 +262,41 @@ pub fn push_raw(
 	delete: bool,
 	basic_credential: Option<BasicAuthCredential>,
 	progress_sender: Option<Sender<ProgressNotification>>,
-) -> Result<()> {
+) -> Result<PushResult> {
 	scope_time!("push");
 
-	let repo = repo(repo_path)?;
-	let mut remote = repo.find_remote(remote)?;
+	push_with_lease(
+		repo_path,
+		remote,
+		branch,
+		ref_type,
+		force,
+		delete,
+		None,
+		basic_credential,
+		progress_sender,
+	)
+}
 
-	let mut options = PushOptions::new();
-	options.proxy_options(proxy_auto());
+/// `lease` is where the remote reference has to point to (zero if it
+/// must not exist) for the push to go ahead, checked while negotiating
+/// the push so nobody can sneak in between the check and the update
+#[allow(clippy::too_many_arguments)]
+fn push_with_lease(
+	repo_path: &RepoPath,
+	remote: &str,
+	branch: &str,
+	ref_type: PushType,
+	force: bool,
+	delete: bool,
+	lease: Option<Oid>,
+	basic_credential: Option<BasicAuthCredential>,
+	progress_sender: Option<Sender<ProgressNotification>>,
+) -> Result<PushResult> {
+	let stale = Cell::new(false);
 
-	let callbacks = Callbacks::new(progress_sender, basic_credential);
-	options.remote_callbacks(callbacks.callbacks());
-	options.packbuilder_parallelism(0);
+	let repo = repo(repo_path)?;
+	let mut remote = repo.find_remote(remote)?;
 
 	let branch_modifier = match (force, delete) {
 		(true, true) => "+:",
@@ -158,28 +304,122 @@ pub fn push_raw(
 		(true, false) => "+",
 		(false, false) => "",
 	};
-	let ref_type = match ref_type {
+	let ref_namespace = match ref_type {
 		PushType::Branch => "heads",
 		PushType::Tag => "tags",
 	};
 
-	let branch_name =
-		format!("{branch_modifier}refs/{ref_type}/{branch}");
-	remote.push(&[branch_name.as_str()], Some(&mut options))?;
+	let reference = format!("refs/{ref_namespace}/{branch}");
+	let branch_name = format!("{branch_modifier}{reference}");
+
+	let callbacks =
+		Callbacks::new(progress_sender, basic_credential).repo(&repo);
+	let mut remote_callbacks = callbacks.callbacks();
+	if let Some(expected) = lease {
+		let leased = reference.clone();
+		let stale = &stale;
+		remote_callbacks.push_negotiation(move |updates| {
+			let moved = updates.iter().any(|update| {
+				update.dst_refname() == Some(leased.as_str())
+					&& update.src() != expected
+			});
+
+			if moved {
+				stale.set(true);
+				return Err(git2::Error::from_str("stale info"));
+			}
 
-	if let Some((reference, msg)) =
-		callbacks.get_stats()?.push_rejected_msg
-	{
-		return Err(Error::Generic(format!(
-			"push to '{reference}' rejected: {msg}"
-		)));
+			Ok(())
+		});
 	}
 
-	if !delete {
+	let mut options = PushOptions::new();
+	options.proxy_options(proxy_auto());
+	options.remote_callbacks(remote_callbacks);
+	options.packbuilder_parallelism(0);
+
+	let result = match remote
+		.push(&[branch_name.as_str()], Some(&mut options))
+	{
+		Err(_) if stale.get() => PushResult {
+			updates: vec![PushRefUpdate {
+				reference,
+				status: PushRefStatus::Rejected(String::from(
+					"stale info",
+				)),
+			}],
+		},
+		// libgit2 refuses non-fast-forward pushes itself before the
+		// remote gets to report anything
+		Err(e) if e.code() == ErrorCode::NotFastForward => {
+			PushResult {
+				updates: vec![PushRefUpdate {
+					reference,
+					status: PushRefStatus::NonFastForward(
+						e.message().to_string(),
+					),
+				}],
+			}
+		}
+		Err(e) => return Err(e.into()),
+		Ok(()) => PushResult {
+			updates: callbacks
+				.get_stats()?
+				.push_updates
+				.into_iter()
+				.map(|(reference, msg)| PushRefUpdate {
+					reference,
+					status: PushRefStatus::from_msg(msg),
+				})
+				.collect(),
+		},
+	};
+
+	if matches!(ref_type, PushType::Branch)
+		&& !delete
+		&& result.rejected().is_none()
+	{
 		branch_set_upstream(&repo, branch)?;
 	}
 
-	Ok(())
+	Ok(result)
+}
+
+/// force pushes `branch` only if the remote branch still points to
+/// `expected`, like `git push --force-with-lease`
+///
+/// Without `expected` the remote-tracking branch we fetched last is
+/// used. If the remote moved on the push is refused with a
+/// `stale info` rejection.
+pub fn push_force_with_lease(
+	repo_path: &RepoPath,
+	remote: &str,
+	branch: &str,
+	expected: Option<CommitId>,
+	basic_credential: Option<BasicAuthCredential>,
+	progress_sender: Option<Sender<ProgressNotification>>,
+) -> Result<PushResult> {
+	scope_time!("push_force_with_lease");
+
+	let expected = if let Some(expected) = expected {
+		Some(expected.into())
+	} else {
+		let repo = repo(repo_path)?;
+		repo.refname_to_id(&format!("refs/remotes/{remote}/{branch}"))
+			.ok()
+	};
+
+	push_with_lease(
+		repo_path,
+		remote,
+		branch,
+		PushType::Branch,
+		true,
+		false,
+		Some(expected.unwrap_or_else(Oid::zero)),
+		basic_credential,
+		progress_sender,
+	)
 }
 
 #[cfg(test)]
@@ -226,6 +466,7 @@ mod tests {
 		sync::commit(
 			&tmp_repo_dir.path().to_str().unwrap().into(),
 			"repo_1_commit",
+			false,
 		)
 		.unwrap();
 
@@ -249,6 +490,7 @@ mod tests {
 		sync::commit(
 			&tmp_other_repo_dir.path().to_str().unwrap().into(),
 			"repo_2_commit",
+			false,
 		)
 		.unwrap();
 
@@ -323,6 +565,7 @@ mod tests {
 		let repo_1_commit = sync::commit(
 			&tmp_repo_dir.path().to_str().unwrap().into(),
 			"repo_1_commit",
+			false,
 		)
 		.unwrap();
 
@@ -367,6 +610,7 @@ mod tests {
 		let repo_2_commit = sync::commit(
 			&tmp_other_repo_dir.path().to_str().unwrap().into(),
 			"repo_2_commit",
+			false,
 		)
 		.unwrap();
 
@@ -519,4 +763,162 @@ mod tests {
 			false
 		);
 	}
+
+	#[test]
+	fn test_push_rejected_non_fast_forward() {
+		let (tmp_repo_dir, repo) = repo_init().unwrap();
+		let (tmp_other_repo_dir, other_repo) = repo_init().unwrap();
+		let (tmp_upstream_dir, _) = repo_init_bare().unwrap();
+
+		let upstream = tmp_upstream_dir.path().to_str().unwrap();
+		repo.remote("origin", upstream).unwrap();
+		other_repo.remote("origin", upstream).unwrap();
+
+		write_commit_file(&repo, "foo.txt", "a", "repo_1_commit");
+		push_branch(
+			&tmp_repo_dir.path().to_str().unwrap().into(),
+			"origin",
+			"master",
+			false,
+			false,
+			None,
+			None,
+		)
+		.unwrap();
+
+		write_commit_file(
+			&other_repo,
+			"foo.txt",
+			"b",
+			"repo_2_commit",
+		);
+
+		let result = push_raw(
+			&tmp_other_repo_dir.path().to_str().unwrap().into(),
+			"origin",
+			"master",
+			PushType::Branch,
+			false,
+			false,
+			None,
+			None,
+		)
+		.unwrap();
+
+		let rejected = result.rejected().unwrap();
+		assert_eq!(rejected.reference, "refs/heads/master");
+		assert!(matches!(
+			rejected.status,
+			PushRefStatus::NonFastForward(_)
+		));
+		assert!(matches!(
+			result.check(),
+			Err(Error::Generic(msg)) if msg.contains("rejected: non-fast-forward")
+		));
+		assert!(other_repo
+			.branch_upstream_name("refs/heads/master")
+			.is_err());
+	}
+
+	#[test]
+	fn test_push_ref_status_from_msg() {
+		assert_eq!(PushRefStatus::from_msg(None), PushRefStatus::Ok);
+		assert!(matches!(
+			PushRefStatus::from_msg(Some("non-fast-forward".into())),
+			PushRefStatus::NonFastForward(_)
+		));
+		assert!(matches!(
+			PushRefStatus::from_msg(Some(
+				"pre-receive hook declined".into()
+			)),
+			PushRefStatus::HookDeclined(_)
+		));
+		assert!(matches!(
+			PushRefStatus::from_msg(Some(
+				"deny updating a hidden ref".into()
+			)),
+			PushRefStatus::Rejected(_)
+		));
+	}
+
+	#[test]
+	fn test_push_force_with_lease() {
+		let (tmp_repo_dir, repo) = repo_init().unwrap();
+		let (tmp_upstream_dir, upstream) = repo_init_bare().unwrap();
+		let repo_path: &RepoPath =
+			&tmp_repo_dir.path().to_str().unwrap().into();
+
+		repo.remote(
+			"origin",
+			tmp_upstream_dir.path().to_str().unwrap(),
+		)
+		.unwrap();
+
+		write_commit_file(&repo, "foo.txt", "a", "c1");
+		push_branch(
+			repo_path, "origin", "master", false, false, None, None,
+		)
+		.unwrap();
+
+		let (tmp_other_dir, other_repo) =
+			repo_clone(tmp_upstream_dir.path().to_str().unwrap())
+				.unwrap();
+		let other_path: &RepoPath =
+			&tmp_other_dir.path().to_str().unwrap().into();
+
+		// someone else pushes while our rewrite is in progress
+		write_commit_file(&repo, "foo.txt", "b", "c2");
+		push_branch(
+			repo_path, "origin", "master", false, false, None, None,
+		)
+		.unwrap();
+		let c2 = repo.head().unwrap().target().unwrap();
+
+		sync::amend(
+			other_path,
+			get_commit_ids(&other_repo, 1)[0],
+			"rewritten",
+			false,
+		)
+		.unwrap();
+
+		let result = push_force_with_lease(
+			other_path, "origin", "master", None, None, None,
+		)
+		.unwrap();
+
+		assert_eq!(
+			result.rejected().map(|update| &update.status),
+			Some(&PushRefStatus::Rejected(String::from(
+				"stale info"
+			)))
+		);
+		assert_eq!(
+			upstream
+				.find_reference("refs/heads/master")
+				.unwrap()
+				.target(),
+			Some(c2)
+		);
+
+		// once we know about c2 the lease holds
+		let result = push_force_with_lease(
+			other_path,
+			"origin",
+			"master",
+			Some(c2.into()),
+			None,
+			None,
+		)
+		.unwrap();
+
+		assert_eq!(result.rejected(), None);
+		assert_eq!(
+			upstream
+				.find_reference("refs/heads/master")
+				.unwrap()
+				.target(),
+			other_repo.head().unwrap().target()
+		);
+	}
 }
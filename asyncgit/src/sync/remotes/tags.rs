@@ -1,6 +1,9 @@
 //!
 
-use super::push::AsyncProgress;
+use super::push::{
+	push_raw, AsyncProgress, ProgressNotification, PushResult,
+	PushType,
+};
 use crate::{
 	error::Result,
 	progress::ProgressPercent,
@@ -57,7 +60,8 @@ fn remote_tag_refs(
 
 	let repo = repo(repo_path)?;
 	let mut remote = repo.find_remote(remote)?;
-	let callbacks = Callbacks::new(None, basic_credential);
+	let callbacks =
+		Callbacks::new(None, basic_credential).repo(&repo);
 	let conn = remote.connect_auth(
 		Direction::Fetch,
 		Some(callbacks.callbacks()),
@@ -132,7 +136,8 @@ pub fn push_tags(
 	for (idx, tag) in tags_missing.into_iter().enumerate() {
 		let mut options = PushOptions::new();
 		let callbacks =
-			Callbacks::new(None, basic_credential.clone());
+			Callbacks::new(None, basic_credential.clone())
+				.repo(&repo);
 		options.remote_callbacks(callbacks.callbacks());
 		options.packbuilder_parallelism(0);
 		options.proxy_options(proxy_auto());
@@ -153,16 +158,60 @@ pub fn push_tags(
 	Ok(())
 }
 
+/// pushes the single tag `refs/tags/<tag>` to `remote`
+///
+/// `force` overwrites a different tag of the same name on the remote.
+pub fn push_tag(
+	repo_path: &RepoPath,
+	remote: &str,
+	tag: &str,
+	force: bool,
+	basic_credential: Option<BasicAuthCredential>,
+	progress_sender: Option<Sender<ProgressNotification>>,
+) -> Result<PushResult> {
+	scope_time!("push_tag");
+
+	push_raw(
+		repo_path,
+		remote,
+		tag,
+		PushType::Tag,
+		force,
+		false,
+		basic_credential,
+		progress_sender,
+	)
+}
+
+/// deletes `refs/tags/<tag>` on `remote`, the local tag is kept
+pub fn delete_remote_tag(
+	repo_path: &RepoPath,
+	remote: &str,
+	tag: &str,
+	basic_credential: Option<BasicAuthCredential>,
+	progress_sender: Option<Sender<ProgressNotification>>,
+) -> Result<PushResult> {
+	scope_time!("delete_remote_tag");
+
+	push_raw(
+		repo_path,
+		remote,
+		tag,
+		PushType::Tag,
+		false,
+		true,
+		basic_credential,
+		progress_sender,
+	)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::{
 		sync::{
 			self, delete_tag,
-			remotes::{
-				fetch, fetch_all,
-				push::{push_branch, push_raw},
-			},
+			remotes::{fetch, fetch_all, push::push_branch},
 			tests::{repo_clone, repo_init_bare},
 		},
 		PushType,
@@ -190,7 +239,8 @@ mod tests {
 		let commit1 =
 			write_commit_file(&clone1, "test.txt", "test", "commit1");
 
-		sync::tag_commit(clone1_dir, &commit1, "tag1", None).unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
 
 		push_branch(
 			clone1_dir, "origin", "master", false, false, None, None,
@@ -210,7 +260,8 @@ mod tests {
 		assert_eq!(sync::get_tags(clone2_dir).unwrap().len(), 0);
 
 		//lets fetch from origin
-		let bytes = fetch(clone2_dir, "master", None, None).unwrap();
+		let bytes =
+			fetch(clone2_dir, "master", None, None, false).unwrap();
 		assert!(bytes > 0);
 
 		sync::merge_upstream_commit(clone2_dir, "master").unwrap();
@@ -238,7 +289,8 @@ mod tests {
 		let commit1 =
 			write_commit_file(&clone1, "test.txt", "test", "commit1");
 
-		sync::tag_commit(clone1_dir, &commit1, "tag1", None).unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
 
 		push_branch(
 			clone1_dir, "origin", "master", false, false, None, None,
@@ -272,7 +324,8 @@ mod tests {
 		let commit1 =
 			write_commit_file(&clone1, "test.txt", "test", "commit1");
 
-		sync::tag_commit(clone1_dir, &commit1, "tag1", None).unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
 
 		push_branch(
 			clone1_dir, "origin", "master", false, false, None, None,
@@ -314,7 +367,8 @@ mod tests {
 
 		// clone1 - creates tag
 
-		sync::tag_commit(clone1_dir, &commit1, "tag1", None).unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
 
 		let tags1 = sync::get_tags(clone1_dir).unwrap();
 
@@ -325,7 +379,7 @@ mod tests {
 
 		// clone 2 - pull
 
-		fetch(clone2_dir, "master", None, None).unwrap();
+		fetch(clone2_dir, "master", None, None, false).unwrap();
 
 		let tags2 = sync::get_tags(clone2_dir).unwrap();
 
@@ -354,7 +408,8 @@ mod tests {
 
 		// clone1 - creates tag
 
-		sync::tag_commit(clone1_dir, &commit1, "tag1", None).unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
 
 		let tags1 = sync::get_tags(clone1_dir).unwrap();
 
@@ -394,7 +449,8 @@ mod tests {
 
 		// clone1 - creates tag
 
-		sync::tag_commit(clone1_dir, &commit1, "tag1", None).unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
 		push_tags(clone1_dir, "origin", None, None).unwrap();
 
 		// clone 2 - pull
@@ -416,6 +472,8 @@ mod tests {
 			None,
 			None,
 		)
+		.unwrap()
+		.check()
 		.unwrap();
 
 		push_tags(clone1_dir, "origin", None, None).unwrap();
@@ -425,4 +483,49 @@ mod tests {
 		fetch_all(clone2_dir, &None, &None).unwrap();
 		assert_eq!(sync::get_tags(clone2_dir).unwrap().len(), 0);
 	}
+
+	#[test]
+	fn test_push_and_delete_remote_tag() {
+		let (r1_dir, _repo) = repo_init_bare().unwrap();
+		let r1_dir = r1_dir.path().to_str().unwrap();
+
+		let (clone1_dir, clone1) = repo_clone(r1_dir).unwrap();
+
+		let clone1_dir: &RepoPath =
+			&clone1_dir.path().to_str().unwrap().into();
+
+		let commit1 =
+			write_commit_file(&clone1, "test.txt", "test", "commit1");
+		push_branch(
+			clone1_dir, "origin", "master", false, false, None, None,
+		)
+		.unwrap();
+
+		sync::tag_commit(clone1_dir, &commit1, "tag1", None, false)
+			.unwrap();
+		sync::tag_commit(clone1_dir, &commit1, "tag2", None, false)
+			.unwrap();
+
+		let result =
+			push_tag(clone1_dir, "origin", "tag1", false, None, None)
+				.unwrap();
+
+		assert!(result.rejected().is_none());
+		assert_eq!(result.updates.len(), 1);
+		assert_eq!(result.updates[0].reference, "refs/tags/tag1");
+		assert_eq!(
+			remote_tag_refs(clone1_dir, "origin", None).unwrap(),
+			vec![String::from("refs/tags/tag1")]
+		);
+
+		delete_remote_tag(clone1_dir, "origin", "tag1", None, None)
+			.unwrap()
+			.check()
+			.unwrap();
+
+		assert!(remote_tag_refs(clone1_dir, "origin", None)
+			.unwrap()
+			.is_empty());
+		assert_eq!(sync::get_tags(clone1_dir).unwrap().len(), 1);
+	}
 }
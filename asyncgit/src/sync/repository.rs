@@ -13,9 +13,11 @@ pub type RepoPathRef = RefCell<RepoPath>;
 ///
 #[derive(Clone, Debug)]
 pub enum RepoPath {
-	///
+	/// workdir (of the main checkout or a linked worktree) or git dir,
+	/// the repository is searched for like git does
 	Path(PathBuf),
-	///
+	/// git dir used with a separate workdir, like
+	/// `git --git-dir=.. --work-tree=..` (e.g. for bare repositories)
 	Workdir {
 		///
 		gitdir: PathBuf,
@@ -61,3 +63,89 @@ pub fn repo(repo_path: &RepoPath) -> Result<Repository> {
 
 	Ok(repo)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		commit, get_head, stage_add_file,
+		status::{get_status, StatusType},
+		tests::{repo_init, repo_init_bare, write_commit_file},
+		utils::repo_write_file,
+		worktree::add_worktree,
+	};
+	use tempfile::TempDir;
+
+	fn workdir_status(repo_path: &RepoPath) -> Vec<String> {
+		get_status(repo_path, StatusType::WorkingDir, None)
+			.unwrap()
+			.into_iter()
+			.map(|item| item.path)
+			.collect()
+	}
+
+	#[test]
+	fn test_linked_worktree_status() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "foo", "c1");
+
+		let wt_dir = TempDir::new()?;
+		let wt_path = wt_dir.path().join("wt");
+		add_worktree(repo_path, "wt", &wt_path, None)?;
+
+		repo_write_file(&repo, "main.txt", "main")?;
+		std::fs::write(wt_path.join("foo.txt"), "changed")?;
+
+		assert_eq!(workdir_status(repo_path), vec!["main.txt"]);
+
+		let by_workdir: &RepoPath =
+			&wt_path.as_os_str().to_str().unwrap().into();
+		assert_eq!(workdir_status(by_workdir), vec!["foo.txt"]);
+
+		let by_gitdir = &RepoPath::Workdir {
+			gitdir: repo.path().join("worktrees").join("wt"),
+			workdir: wt_path,
+		};
+		assert_eq!(workdir_status(by_gitdir), vec!["foo.txt"]);
+		assert_eq!(
+			super::repo(by_gitdir)?.head()?.shorthand(),
+			Some("wt")
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_bare_with_workdir() -> Result<()> {
+		let (bare_dir, _bare) = repo_init_bare()?;
+		let workdir = TempDir::new()?;
+		let repo_path = &RepoPath::Workdir {
+			gitdir: bare_dir.path().to_path_buf(),
+			workdir: workdir.path().to_path_buf(),
+		};
+
+		{
+			let repo = super::repo(repo_path)?;
+			let mut config = repo.config()?;
+			config.set_str("user.name", "name")?;
+			config.set_str("user.email", "email")?;
+		}
+
+		std::fs::write(workdir.path().join("foo.txt"), "foo")?;
+
+		assert_eq!(workdir_status(repo_path), vec!["foo.txt"]);
+
+		stage_add_file(repo_path, Path::new("foo.txt"))?;
+		let id = commit(repo_path, "c1", false)?;
+
+		assert_eq!(get_head(repo_path)?, id);
+		assert!(workdir_status(repo_path).is_empty());
+		assert!(Repository::open_bare(bare_dir.path())?.is_bare());
+
+		Ok(())
+	}
+}
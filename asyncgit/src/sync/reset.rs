@@ -1,9 +1,18 @@
 use super::{utils::get_head_repo, CommitId, RepoPath};
-use crate::{error::Result, sync::repository::repo};
-use git2::{build::CheckoutBuilder, ObjectType, ResetType};
+use crate::{
+	error::{Error, Result},
+	sync::repository::repo,
+};
+use git2::{
+	build::CheckoutBuilder, ObjectType, ResetType, StatusOptions,
+};
 use scopetime::scope_time;
+use std::path::Path;
 
+/// unstages `path` leaving all other index entries alone, like
+/// `git restore --staged`
 ///
+/// `path` is a pathspec, so `*` unstages everything.
 pub fn reset_stage(repo_path: &RepoPath, path: &str) -> Result<()> {
 	scope_time!("reset_stage");
 
@@ -55,23 +64,132 @@ pub fn reset_repo(
 	Ok(())
 }
 
+///
+#[deprecated(note = "use `reset_stage`")]
+pub fn reset_path_to_head(
+	repo_path: &RepoPath,
+	path: &str,
+) -> Result<()> {
+	reset_stage(repo_path, path)
+}
+
+/// discards the working tree changes of `path` by checking out its
+/// version in `HEAD`, the index is left alone like with
+/// `git restore --source=HEAD`
+pub fn restore_path_from_head(
+	repo_path: &RepoPath,
+	path: &str,
+) -> Result<()> {
+	scope_time!("restore_path_from_head");
+
+	let repo = repo(repo_path)?;
+	let head_tree = repo.head()?.peel_to_tree()?;
+
+	if head_tree.get_path(Path::new(path)).is_err() {
+		return Err(Error::Generic(format!(
+			"'{path}' does not exist in HEAD"
+		)));
+	}
+
+	let mut checkout_opts = CheckoutBuilder::new();
+	// git2 does not expose `GIT_CHECKOUT_DISABLE_PATHSPEC_MATCH`, so
+	// escape the glob characters to match `path` literally
+	checkout_opts
+		.update_index(false)
+		.force()
+		.path(escape_pathspec(path));
+
+	repo.checkout_tree(
+		head_tree.as_object(),
+		Some(&mut checkout_opts),
+	)?;
+
+	Ok(())
+}
+
+fn escape_pathspec(path: &str) -> String {
+	let mut escaped = String::with_capacity(path.len());
+
+	for c in path.chars() {
+		if matches!(c, '*' | '?' | '[' | '\\') {
+			escaped.push('\\');
+		}
+		escaped.push(c);
+	}
+
+	escaped
+}
+
+/// moves `HEAD` to `commit` keeping index and working tree, like
+/// `git reset --soft`
+pub fn reset_soft(
+	repo_path: &RepoPath,
+	commit: CommitId,
+) -> Result<()> {
+	scope_time!("reset_soft");
+
+	reset_repo(repo_path, commit, ResetType::Soft)
+}
+
+/// moves `HEAD` to `commit` and resets the index keeping the working
+/// tree, like `git reset --mixed`
+pub fn reset_mixed(
+	repo_path: &RepoPath,
+	commit: CommitId,
+) -> Result<()> {
+	scope_time!("reset_mixed");
+
+	reset_repo(repo_path, commit, ResetType::Mixed)
+}
+
+/// like `git reset --hard` but unless `force` is set this fails with
+/// [`Error::UncommittedChangesIn`] if changes would be lost
+pub fn reset_hard(
+	repo_path: &RepoPath,
+	commit: CommitId,
+	force: bool,
+) -> Result<()> {
+	scope_time!("reset_hard");
+
+	if !force {
+		let repo = repo(repo_path)?;
+
+		// untracked files survive a hard reset
+		let mut options = StatusOptions::new();
+		options.include_untracked(false).include_ignored(false);
+
+		let dirty = repo
+			.statuses(Some(&mut options))?
+			.iter()
+			.filter_map(|entry| entry.path().map(String::from))
+			.collect::<Vec<_>>();
+
+		if !dirty.is_empty() {
+			return Err(Error::UncommittedChangesIn(dirty));
+		}
+	}
+
+	reset_repo(repo_path, commit, ResetType::Hard)
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{reset_stage, reset_workdir};
-	use crate::error::Result;
+	use super::*;
 	use crate::sync::{
 		commit,
 		status::{get_status, StatusType},
 		tests::{
-			debug_cmd_print, get_statuses, repo_init, repo_init_empty,
+			debug_cmd_print, get_statuses, repo_init,
+			repo_init_empty, write_commit_file,
+		},
+		utils::{
+			repo_read_file, repo_write_file, stage_add_all,
+			stage_add_file,
 		},
-		utils::{stage_add_all, stage_add_file},
-		RepoPath,
 	};
 	use std::{
 		fs::{self, File},
 		io::Write,
-		path::Path,
 	};
 
 	static HUNK_A: &str = r"
@@ -189,7 +307,7 @@ mod tests {
 		}
 
 		stage_add_all(repo_path, "*", None).unwrap();
-		commit(repo_path, "msg").unwrap();
+		commit(repo_path, "msg", false).unwrap();
 
 		{
 			File::create(root.join("foo/file1.txt"))?
@@ -336,4 +454,170 @@ mod tests {
 
 		assert_eq!(get_statuses(repo_path), (0, 0));
 	}
+
+	#[test]
+	fn test_reset_soft() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let first = write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "a.txt", "b", "c2");
+
+		reset_soft(repo_path, first)?;
+
+		assert_eq!(get_head_repo(&repo)?, first);
+		assert_eq!(get_statuses(repo_path), (0, 1));
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "b");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_reset_mixed() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let first = write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "a.txt", "b", "c2");
+
+		reset_mixed(repo_path, first)?;
+
+		assert_eq!(get_head_repo(&repo)?, first);
+		assert_eq!(get_statuses(repo_path), (1, 0));
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "b");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_reset_hard() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let first = write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "a.txt", "b", "c2");
+		repo_write_file(&repo, "untracked.txt", "u")?;
+
+		reset_hard(repo_path, first, false)?;
+
+		assert_eq!(get_head_repo(&repo)?, first);
+		assert_eq!(get_statuses(repo_path), (1, 0));
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_reset_hard_dirty() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let first = write_commit_file(&repo, "a.txt", "a", "c1");
+		let second = write_commit_file(&repo, "b.txt", "b", "c2");
+		repo_write_file(&repo, "b.txt", "changed")?;
+
+		let res = reset_hard(repo_path, first, false);
+
+		assert!(matches!(
+			&res,
+			Err(Error::UncommittedChangesIn(paths))
+				if paths == &vec![String::from("b.txt")]
+		));
+		assert_eq!(
+			res.map_err(|e| e.to_string()),
+			Err(String::from("git: uncommitted changes in: b.txt"))
+		);
+		assert_eq!(get_head_repo(&repo)?, second);
+		assert_eq!(repo_read_file(&repo, "b.txt")?, "changed");
+
+		reset_hard(repo_path, first, true)?;
+
+		assert_eq!(get_head_repo(&repo)?, first);
+		assert!(!root.join("b.txt").exists());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_reset_stage_single_path() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "b.txt", "b", "c2");
+
+		repo_write_file(&repo, "a.txt", "a2")?;
+		repo_write_file(&repo, "b.txt", "b2")?;
+		stage_add_file(repo_path, Path::new("a.txt"))?;
+		stage_add_file(repo_path, Path::new("b.txt"))?;
+
+		reset_stage(repo_path, "a.txt")?;
+
+		let staged = get_status(repo_path, StatusType::Stage, None)?;
+		assert_eq!(staged.len(), 1);
+		assert_eq!(staged[0].path, "b.txt");
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a2");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_restore_path_from_head() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "b.txt", "b", "c2");
+
+		repo_write_file(&repo, "a.txt", "a2")?;
+		repo_write_file(&repo, "b.txt", "b2")?;
+		stage_add_file(repo_path, Path::new("b.txt"))?;
+		repo_write_file(&repo, "b.txt", "b3")?;
+
+		restore_path_from_head(repo_path, "a.txt")?;
+
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a");
+		assert_eq!(repo_read_file(&repo, "b.txt")?, "b3");
+
+		let staged = get_status(repo_path, StatusType::Stage, None)?;
+		assert_eq!(staged.len(), 1);
+		assert_eq!(staged[0].path, "b.txt");
+
+		assert!(restore_path_from_head(repo_path, "c.txt").is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_restore_path_from_head_is_no_pathspec() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "[ab].txt", "ab", "c2");
+
+		repo_write_file(&repo, "a.txt", "a2")?;
+		repo_write_file(&repo, "[ab].txt", "ab2")?;
+
+		restore_path_from_head(repo_path, "[ab].txt")?;
+
+		assert_eq!(repo_read_file(&repo, "[ab].txt")?, "ab");
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a2");
+
+		Ok(())
+	}
 }
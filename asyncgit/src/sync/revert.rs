@@ -0,0 +1,166 @@
+//! sync git api for reverting commits
+
+use super::{CommitId, RepoPath};
+use crate::{
+	error::Result,
+	sync::{
+		repository::repo,
+		utils::{conflicted_paths, read_file},
+	},
+};
+use scopetime::scope_time;
+
+const GIT_REVERT_HEAD_FILE: &str = "REVERT_HEAD";
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertResult {
+	/// the inverse change is staged, the repo is in `Revert` state
+	Staged,
+	/// the inverse change was committed
+	Committed(CommitId),
+	/// reverting conflicted, the index is left with the conflicts
+	Conflict {
+		///
+		conflicted_paths: Vec<String>,
+	},
+}
+
+/// reverts `commit` staging the inverse change, with `auto_commit`
+/// it is committed right away using the default revert message
+pub fn revert_commit(
+	repo_path: &RepoPath,
+	commit: CommitId,
+	auto_commit: bool,
+) -> Result<RevertResult> {
+	scope_time!("revert");
+
+	let repo = repo(repo_path)?;
+
+	let commit = repo.find_commit(commit.into())?;
+
+	repo.revert(&commit, None)?;
+
+	if repo.index()?.has_conflicts() {
+		return Ok(RevertResult::Conflict {
+			conflicted_paths: conflicted_paths(&repo)?,
+		});
+	}
+
+	if !auto_commit {
+		return Ok(RevertResult::Staged);
+	}
+
+	let msg = format!(
+		"Revert \"{}\"\n\nThis reverts commit {}.\n",
+		commit.summary().unwrap_or_default(),
+		commit.id()
+	);
+
+	Ok(RevertResult::Committed(commit_revert(repo_path, &msg)?))
+}
+
+///
+pub fn revert_head(repo_path: &RepoPath) -> Result<CommitId> {
+	scope_time!("revert_head");
+
+	let path = repo(repo_path)?.path().join(GIT_REVERT_HEAD_FILE);
+
+	let file_content = read_file(&path)?;
+
+	let id = git2::Oid::from_str(file_content.trim())?;
+
+	Ok(id.into())
+}
+
+///
+pub fn commit_revert(
+	repo_path: &RepoPath,
+	msg: &str,
+) -> Result<CommitId> {
+	scope_time!("commit_revert");
+
+	let id = crate::sync::commit(repo_path, msg, false)?;
+
+	repo(repo_path)?.cleanup_state()?;
+
+	Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		get_commits_info, repo_state,
+		tests::{repo_init, write_commit_file},
+		utils::{get_head_repo, repo_read_file},
+		RepoState,
+	};
+
+	#[test]
+	fn test_revert_staged() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "before", "c1");
+		let c2 = write_commit_file(&repo, "foo.txt", "after", "c2");
+
+		let res = revert_commit(repo_path, c2, false)?;
+
+		assert_eq!(res, RevertResult::Staged);
+		assert_eq!(repo_read_file(&repo, "foo.txt")?, "before");
+		assert_eq!(get_head_repo(&repo)?, c2);
+		assert_eq!(repo_state(repo_path)?, RepoState::Revert);
+		assert_eq!(revert_head(repo_path)?, c2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_revert_committed() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "before", "c1");
+		let c2 = write_commit_file(&repo, "foo.txt", "after", "c2");
+
+		let res = revert_commit(repo_path, c2, true)?;
+
+		let head = get_head_repo(&repo)?;
+		assert_eq!(res, RevertResult::Committed(head));
+		assert_eq!(repo_read_file(&repo, "foo.txt")?, "before");
+		assert_eq!(repo_state(repo_path)?, RepoState::Clean);
+
+		let info = get_commits_info(repo_path, &[head], 100)?;
+		assert_eq!(info[0].message, "Revert \"c2\"");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_revert_conflict() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "before", "c1");
+		let c2 = write_commit_file(&repo, "foo.txt", "after", "c2");
+		write_commit_file(&repo, "foo.txt", "again", "c3");
+
+		let res = revert_commit(repo_path, c2, true)?;
+
+		assert_eq!(
+			res,
+			RevertResult::Conflict {
+				conflicted_paths: vec![String::from("foo.txt")]
+			}
+		);
+
+		Ok(())
+	}
+}
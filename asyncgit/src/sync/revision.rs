@@ -0,0 +1,205 @@
+//! resolving user supplied commit ids
+
+use super::{repository::repo, CommitId, RepoPath};
+use crate::error::{Error, Result};
+use git2::{ErrorCode, ObjectType, Oid};
+use scopetime::scope_time;
+
+/// expands an abbreviated commit id like `git rev-parse` does
+///
+/// Fails if no commit or more than one commit starts with `prefix`,
+/// the latter listing the candidates.
+pub fn resolve_commit_prefix(
+	repo_path: &RepoPath,
+	prefix: &str,
+) -> Result<CommitId> {
+	scope_time!("resolve_commit_prefix");
+
+	let prefix = prefix.trim().to_lowercase();
+
+	if prefix.is_empty()
+		|| prefix.len() > 40
+		|| !prefix.chars().all(|c| c.is_ascii_hexdigit())
+	{
+		return Err(Error::Generic(format!(
+			"invalid commit id: '{prefix}'"
+		)));
+	}
+
+	let repo = repo(repo_path)?;
+	let odb = repo.odb()?;
+
+	match odb.exists_prefix(Oid::from_str(&prefix)?, prefix.len()) {
+		Ok(id) => {
+			if odb.read_header(id)?.1 == ObjectType::Commit {
+				return Ok(id.into());
+			}
+		}
+		Err(e) if e.code() == ErrorCode::NotFound => {}
+		// ambiguous or too short, only commits count
+		Err(_) => {
+			let mut candidates = Vec::new();
+			odb.foreach(|id| {
+				if id.to_string().starts_with(&prefix)
+					&& matches!(
+						odb.read_header(*id),
+						Ok((_, ObjectType::Commit))
+					) {
+					candidates.push(*id);
+				}
+				true
+			})?;
+
+			match candidates.as_slice() {
+				[] => (),
+				[id] => return Ok((*id).into()),
+				_ => {
+					candidates.sort();
+
+					return Err(Error::Generic(format!(
+						"commit id '{prefix}' is ambiguous: {}",
+						candidates
+							.iter()
+							.map(ToString::to_string)
+							.collect::<Vec<_>>()
+							.join(", ")
+					)));
+				}
+			}
+		}
+	}
+
+	Err(Error::Generic(format!("unknown commit id: '{prefix}'")))
+}
+
+/// resolves a revision expression like `HEAD~3`, `main@{yesterday}`
+/// or `v1.0^{commit}` to the commit it names
+///
+/// Tags are peeled to their commit, specs naming a tree or blob are
+/// an error.
+pub fn resolve_revision(
+	repo_path: &RepoPath,
+	spec: &str,
+) -> Result<CommitId> {
+	scope_time!("resolve_revision");
+
+	let repo = repo(repo_path)?;
+
+	let object = repo.revparse_single(spec.trim()).map_err(|e| {
+		Error::Generic(format!(
+			"invalid revision '{spec}': {}",
+			e.message()
+		))
+	})?;
+
+	let commit = object.peel_to_commit().map_err(|_| {
+		Error::Generic(format!(
+			"revision '{spec}' does not point to a commit"
+		))
+	})?;
+
+	Ok(commit.id().into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::tests::{repo_init, write_commit_file};
+
+	#[test]
+	fn test_resolve_unique_prefix() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let id = write_commit_file(&repo, "a.txt", "a", "c1");
+		let full = id.to_string();
+
+		assert_eq!(
+			resolve_commit_prefix(repo_path, &full[..10])?,
+			id
+		);
+		assert_eq!(
+			resolve_commit_prefix(repo_path, &full.to_uppercase())?,
+			id
+		);
+		assert!(resolve_commit_prefix(repo_path, "xyz").is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_resolve_ambiguous_prefix() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		// with 17 commits at least two share their first digit
+		let mut ids = (0..17)
+			.map(|i| {
+				write_commit_file(&repo, "a.txt", &i.to_string(), "c")
+			})
+			.collect::<Vec<_>>();
+		ids.sort_by_key(ToString::to_string);
+
+		let first_digit =
+			|id: &CommitId| id.to_string()[..1].to_string();
+		let prefix = ids
+			.windows(2)
+			.find(|pair| {
+				first_digit(&pair[0]) == first_digit(&pair[1])
+			})
+			.map(|pair| first_digit(&pair[0]))
+			.unwrap();
+
+		let msg = resolve_commit_prefix(repo_path, &prefix)
+			.map_err(|e| e.to_string())
+			.err()
+			.unwrap();
+
+		assert!(msg.contains("is ambiguous"));
+		for id in ids
+			.iter()
+			.filter(|id| id.to_string().starts_with(prefix.as_str()))
+		{
+			assert!(msg.contains(&id.to_string()));
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_resolve_revision() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = write_commit_file(&repo, "a.txt", "a", "c1");
+		let c2 = write_commit_file(&repo, "a.txt", "b", "c2");
+
+		let sig = repo.signature()?;
+		repo.tag(
+			"v1.0",
+			&repo.find_object(c1.into(), None)?,
+			&sig,
+			"release",
+			false,
+		)?;
+
+		assert_eq!(resolve_revision(repo_path, "HEAD")?, c2);
+		assert_eq!(resolve_revision(repo_path, "HEAD~1")?, c1);
+		assert_eq!(resolve_revision(repo_path, "v1.0")?, c1);
+		assert_eq!(resolve_revision(repo_path, "v1.0^{commit}")?, c1);
+
+		assert!(resolve_revision(repo_path, "HEAD^{tree}").is_err());
+		assert!(
+			resolve_revision(repo_path, "no such thing~~").is_err()
+		);
+		assert!(resolve_revision(repo_path, "HEAD~5").is_err());
+
+		Ok(())
+	}
+}
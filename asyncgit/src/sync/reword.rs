@@ -9,6 +9,10 @@ use super::{
 use crate::error::{Error, Result};
 
 /// This is the same as reword, but will abort and fix the repo if something goes wrong
+///
+/// Unlike `amend` this works for any commit of the current branch, its
+/// descendants are replayed on top of the reworded commit and get new
+/// ids. Returns the id of the reworded commit.
 pub fn reword(
 	repo_path: &RepoPath,
 	commit: CommitId,
@@ -167,4 +171,57 @@ mod tests {
 			get_commit_info(repo_path, &reworded).unwrap().message
 		);
 	}
+
+	#[test]
+	fn test_reword_middle_commit() {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let oid1 = write_commit_file(&repo, "foo", "a", "commit1");
+		let oid2 = write_commit_file(&repo, "foo", "ab", "commit2");
+		let oid3 = write_commit_file(&repo, "bar", "c", "commit3");
+
+		let tree_of =
+			|id: Oid| repo.find_commit(id).unwrap().tree_id();
+		let old_trees = [tree_of(oid2.into()), tree_of(oid3.into())];
+
+		let reworded = reword(repo_path, oid2, "NewMessage").unwrap();
+
+		let head = repo.head().unwrap().peel_to_commit().unwrap();
+		assert!(repo.head().unwrap().is_branch());
+		assert_ne!(CommitId::new(head.id()), oid3);
+		assert_eq!(head.message(), Some("commit3"));
+		assert_eq!(head.parent_id(0).unwrap(), reworded.into());
+
+		let middle = head.parent(0).unwrap();
+		assert_ne!(CommitId::new(middle.id()), oid2);
+		assert_eq!(middle.message(), Some("NewMessage"));
+		assert_eq!(middle.parent_id(0).unwrap(), oid1.into());
+
+		assert_eq!([middle.tree_id(), head.tree_id()], old_trees);
+	}
+
+	#[test]
+	fn test_reword_root_commit_fails_cleanly() {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let oid1 = write_commit_file(&repo, "foo", "a", "commit1");
+		let oid2 = write_commit_file(&repo, "foo", "ab", "commit2");
+
+		assert!(matches!(
+			reword(repo_path, oid1, "NewMessage"),
+			Err(Error::NoParent)
+		));
+
+		assert!(repo.head().unwrap().is_branch());
+		assert_eq!(repo.head().unwrap().target(), Some(oid2.into()));
+		assert!(repo.open_rebase(None).is_err());
+	}
 }
@@ -1,4 +1,4 @@
-use super::{apply_selection, load_file};
+use super::{apply_selection, load_file, EolConversion, NEWLINE};
 use crate::{
 	error::Result,
 	sync::{
@@ -10,6 +10,7 @@ use crate::{
 use scopetime::scope_time;
 
 /// discards specific lines in an unstaged hunk of a diff
+/// (lines of untracked files are diffed against empty content)
 pub fn discard_lines(
 	repo_path: &RepoPath,
 	file_path: &str,
@@ -24,17 +25,19 @@ pub fn discard_lines(
 	let repo = repo(repo_path)?;
 	repo.index()?.read(true)?;
 
-	//TODO: check that file is not new (status modified)
-
 	let new_content = {
 		let (_patch, hunks) = get_file_diff_patch_and_hunklines(
 			&repo, file_path, false, false,
 		)?;
 
 		let working_content = load_file(&repo, file_path)?;
-		let old_lines = working_content.lines().collect::<Vec<_>>();
+		let old_lines = working_content
+			.split_inclusive(NEWLINE)
+			.collect::<Vec<_>>();
 
-		apply_selection(lines, &hunks, &old_lines, false, true)?
+		EolConversion::new(&repo, file_path)?.to_workdir(
+			apply_selection(lines, &hunks, &old_lines, false, true)?,
+		)
 	};
 
 	repo_write_file(&repo, file_path, new_content.as_str())?;
@@ -343,4 +346,143 @@ end
 
 		assert_eq!(result_file.as_str(), FILE_3);
 	}
+
+	#[test]
+	fn test_discard_subset_of_added_lines() {
+		static FILE_1: &str = r"start
+end
+";
+
+		static FILE_2: &str = r"start
+1
+2
+3
+end
+";
+
+		static FILE_3: &str = r"start
+2
+end
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		discard_lines(
+			path,
+			"test.txt",
+			&[
+				DiffLinePosition {
+					old_lineno: None,
+					new_lineno: Some(2),
+				},
+				DiffLinePosition {
+					old_lineno: None,
+					new_lineno: Some(4),
+				},
+			],
+		)
+		.unwrap();
+
+		let result_file = load_file(&repo, "test.txt").unwrap();
+
+		assert_eq!(result_file.as_str(), FILE_3);
+	}
+
+	#[test]
+	fn test_discard_no_newline_at_end() {
+		static FILE_1: &str = r"start
+end";
+
+		static FILE_2: &str = r"start
+1
+end";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		discard_lines(
+			path,
+			"test.txt",
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(2),
+			}],
+		)
+		.unwrap();
+
+		let result_file = load_file(&repo, "test.txt").unwrap();
+
+		assert_eq!(result_file.as_str(), FILE_1);
+	}
+
+	#[test]
+	fn test_discard_untracked() {
+		static FILE_1: &str = r"1
+2
+3
+";
+
+		static FILE_2: &str = r"1
+3
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		repo_write_file(&repo, "test.txt", FILE_1).unwrap();
+
+		discard_lines(
+			path,
+			"test.txt",
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(2),
+			}],
+		)
+		.unwrap();
+
+		let result_file = load_file(&repo, "test.txt").unwrap();
+
+		assert_eq!(result_file.as_str(), FILE_2);
+	}
+
+	#[test]
+	fn test_discard_eol_crlf_attribute() {
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(
+			&repo,
+			".gitattributes",
+			"*.txt eol=crlf\n",
+			"c1",
+		);
+		write_commit_file(&repo, "test.txt", "0\r\n1\r\n2\r\n", "c2");
+
+		repo_write_file(&repo, "test.txt", "0\r\n1\r\n2\r\n3\r\n")
+			.unwrap();
+
+		discard_lines(
+			path,
+			"test.txt",
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(4),
+			}],
+		)
+		.unwrap();
+
+		let result_file = load_file(&repo, "test.txt").unwrap();
+
+		assert_eq!(result_file.as_str(), "0\r\n1\r\n2\r\n");
+	}
 }
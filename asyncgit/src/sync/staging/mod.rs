@@ -1,37 +1,72 @@
 mod discard_tracked;
 mod stage_tracked;
+mod toggle;
 
 pub use discard_tracked::discard_lines;
-pub use stage_tracked::stage_lines;
+pub use stage_tracked::{stage_hunks, stage_hunks_from, stage_lines};
+pub use toggle::{
+	file_stage_state, toggle_stage_file, FileStageState,
+};
 
 use super::{
-	diff::DiffLinePosition, patches::HunkLines, utils::work_dir,
+	config::get_config_string_repo,
+	diff::{self, DiffLinePosition, Hunk},
+	patches::HunkLines,
+	utils::work_dir,
+};
+use crate::error::{Error, Result};
+use git2::{
+	AttrCheckFlags, AttrValue, DiffLine, DiffLineType, Patch,
+	Repository,
 };
-use crate::error::Result;
-use git2::{DiffLine, DiffLineType, Repository};
 use std::{
-	collections::HashSet, convert::TryFrom, fs::File, io::Read,
+	collections::{HashMap, HashSet},
+	convert::TryFrom,
+	fs::File,
+	io::Read,
+	path::Path,
 };
 
 const NEWLINE: char = '\n';
+const CRLF: &str = "\r\n";
 
-#[derive(Default)]
 struct NewFromOldContent {
 	lines: Vec<String>,
 	old_index: usize,
+	/// terminator to use when a line needs one added
+	line_ending: &'static str,
 }
 
 impl NewFromOldContent {
+	fn new(old_lines: &[&str]) -> Self {
+		let terminated = old_lines
+			.iter()
+			.filter(|line| line.ends_with(NEWLINE))
+			.count();
+		let crlf_lines = old_lines
+			.iter()
+			.filter(|line| line.ends_with(CRLF))
+			.count();
+
+		// honor the dominant line ending of the old content
+		let line_ending =
+			if crlf_lines > 0 && crlf_lines * 2 >= terminated {
+				CRLF
+			} else {
+				"\n"
+			};
+
+		Self {
+			lines: Vec::new(),
+			old_index: 0,
+			line_ending,
+		}
+	}
+
 	fn add_from_hunk(&mut self, line: &DiffLine) -> Result<()> {
 		let line = String::from_utf8(line.content().into())?;
 
-		let line = if line.ends_with(NEWLINE) {
-			line[0..line.len() - 1].to_string()
-		} else {
-			line
-		};
-
-		self.lines.push(line);
+		self.push_line(line);
 
 		Ok(())
 	}
@@ -40,33 +75,50 @@ impl NewFromOldContent {
 		self.old_index += 1;
 	}
 
-	fn add_old_line(&mut self, old_lines: &[&str]) {
-		self.lines.push(old_lines[self.old_index].to_string());
+	fn add_old_line(&mut self, old_lines: &[&str]) -> Result<()> {
+		let line =
+			old_lines.get(self.old_index).ok_or_else(|| {
+				Error::Generic(String::from(
+					"diff does not match file content",
+				))
+			})?;
+
+		self.push_line((*line).to_string());
 		self.old_index += 1;
+
+		Ok(())
+	}
+
+	/// lines keep their own terminator, only the last line of a file
+	/// can lack one and needs it as soon as another line follows
+	fn push_line(&mut self, line: String) {
+		if let Some(last) = self.lines.last_mut() {
+			if !last.ends_with(NEWLINE) {
+				last.push_str(self.line_ending);
+			}
+		}
+
+		self.lines.push(line);
 	}
 
 	fn catchup_to_hunkstart(
 		&mut self,
 		hunk_start: usize,
 		old_lines: &[&str],
-	) {
+	) -> Result<()> {
 		while hunk_start > self.old_index + 1 {
-			self.add_old_line(old_lines);
+			self.add_old_line(old_lines)?;
 		}
+
+		Ok(())
 	}
 
 	fn finish(mut self, old_lines: &[&str]) -> String {
 		for line in old_lines.iter().skip(self.old_index) {
-			self.lines.push((*line).to_string());
-		}
-		let lines = self.lines.join("\n");
-		if lines.ends_with(NEWLINE) {
-			lines
-		} else {
-			let mut lines = lines;
-			lines.push(NEWLINE);
-			lines
+			self.push_line((*line).to_string());
 		}
+
+		self.lines.concat()
 	}
 }
 
@@ -79,7 +131,7 @@ pub(crate) fn apply_selection(
 	is_staged: bool,
 	reverse: bool,
 ) -> Result<String> {
-	let mut new_content = NewFromOldContent::default();
+	let mut new_content = NewFromOldContent::new(old_lines);
 	let lines = lines.iter().collect::<HashSet<_>>();
 
 	let added = if reverse {
@@ -112,7 +164,8 @@ pub(crate) fn apply_selection(
 		}
 
 		if first_hunk_encountered {
-			new_content.catchup_to_hunkstart(hunk_start, old_lines);
+			new_content
+				.catchup_to_hunkstart(hunk_start, old_lines)?;
 
 			for hunk_line in &hunk.lines {
 				let hunk_line_pos: DiffLinePosition =
@@ -130,12 +183,15 @@ pub(crate) fn apply_selection(
 						.trim()
 				);
 
-				if hunk_line.origin_value()
-					== DiffLineType::DeleteEOFNL
-					|| hunk_line.origin_value()
-						== DiffLineType::AddEOFNL
-				{
-					break;
+				// the missing newline is already reflected in the
+				// content of the line preceding the marker
+				if matches!(
+					hunk_line.origin_value(),
+					DiffLineType::ContextEOFNL
+						| DiffLineType::AddEOFNL
+						| DiffLineType::DeleteEOFNL
+				) {
+					continue;
 				}
 
 				if (is_staged && !selected_line)
@@ -151,7 +207,7 @@ pub(crate) fn apply_selection(
 							new_content.skip_old_line();
 						}
 					} else {
-						new_content.add_old_line(old_lines);
+						new_content.add_old_line(old_lines)?;
 					}
 				} else {
 					if hunk_line.origin_value() != added {
@@ -170,7 +226,210 @@ pub(crate) fn apply_selection(
 		}
 	}
 
-	Ok(new_content.finish(old_lines))
+	let new_content = new_content.finish(old_lines);
+
+	check_selection_effect(
+		&lines,
+		hunks,
+		&old_lines.concat(),
+		&new_content,
+		is_staged || reverse,
+	)?;
+
+	Ok(new_content)
+}
+
+/// re-diffs the result of `apply_selection` making sure it adds and
+/// removes exactly the selected lines (the other way around if
+/// `inverted`), a wrong selection or hunks not matching `old_content`
+/// would otherwise silently produce a corrupt blob
+fn check_selection_effect(
+	lines: &HashSet<&DiffLinePosition>,
+	hunks: &[HunkLines],
+	old_content: &str,
+	new_content: &str,
+	inverted: bool,
+) -> Result<()> {
+	fn count(
+		counts: &mut HashMap<String, i64>,
+		line: &DiffLine,
+		inverted: bool,
+	) {
+		let sign = match line.origin_value() {
+			DiffLineType::Addition => 1,
+			DiffLineType::Deletion => -1,
+			_ => return,
+		};
+		let sign = if inverted { -sign } else { sign };
+
+		// line endings may differ between workdir and index
+		let content = String::from_utf8_lossy(line.content())
+			.trim_end_matches(['\n', '\r'])
+			.to_string();
+
+		*counts.entry(content).or_default() += sign;
+	}
+
+	let mut expected = HashMap::new();
+	for line in hunks.iter().flat_map(|hunk| hunk.lines.iter()) {
+		if lines.contains(&DiffLinePosition::from(line)) {
+			count(&mut expected, line, inverted);
+		}
+	}
+
+	let mut opt = git2::DiffOptions::new();
+	opt.context_lines(0);
+	let patch = Patch::from_buffers(
+		old_content.as_bytes(),
+		None,
+		new_content.as_bytes(),
+		None,
+		Some(&mut opt),
+	)?;
+
+	let mut actual = HashMap::new();
+	for hunk_idx in 0..patch.num_hunks() {
+		for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+			count(
+				&mut actual,
+				&patch.line_in_hunk(hunk_idx, line_idx)?,
+				false,
+			);
+		}
+	}
+
+	expected.retain(|_, n| *n != 0);
+	actual.retain(|_, n| *n != 0);
+
+	if expected == actual {
+		Ok(())
+	} else {
+		Err(Error::PatchApplyMismatch)
+	}
+}
+
+/// returns the positions of all added/deleted lines between `start` and
+/// `end` (inclusive, in any order) across `hunks`, ready to be passed to
+/// `stage_lines`. context and header lines are skipped.
+pub fn lines_in_range(
+	hunks: &[Hunk],
+	start: DiffLinePosition,
+	end: DiffLinePosition,
+) -> Vec<DiffLinePosition> {
+	let lines = hunks
+		.iter()
+		.flat_map(|hunk| hunk.lines.iter())
+		.collect::<Vec<_>>();
+
+	let find = |pos: DiffLinePosition| {
+		lines.iter().position(|line| line.position == pos)
+	};
+
+	let (Some(start), Some(end)) = (find(start), find(end)) else {
+		return Vec::new();
+	};
+
+	let (first, last) = if start <= end {
+		(start, end)
+	} else {
+		(end, start)
+	};
+
+	let mut res = lines[first..=last]
+		.iter()
+		.filter(|line| {
+			matches!(
+				line.line_type,
+				diff::DiffLineType::Add | diff::DiffLineType::Delete
+			)
+		})
+		.map(|line| line.position)
+		.collect::<Vec<_>>();
+
+	// "no newline at end of file" markers share the position of the
+	// line they follow
+	res.dedup();
+
+	res
+}
+
+/// line ending conversion git applies to a file between the object
+/// database and the working directory, based on its `text` and `eol`
+/// attributes and the `core.autocrlf`/`core.eol` settings
+pub struct EolConversion {
+	/// content is normalized to `LF` in the object database
+	text: bool,
+	/// content uses `CRLF` in the working directory
+	crlf_in_workdir: bool,
+}
+
+impl EolConversion {
+	///
+	pub fn new(repo: &Repository, file_path: &str) -> Result<Self> {
+		let path = Path::new(file_path);
+		let flags = AttrCheckFlags::FILE_THEN_INDEX;
+
+		let eol = match AttrValue::from_string(
+			repo.get_attr(path, "eol", flags)?,
+		) {
+			AttrValue::String(eol) => Some(eol.to_lowercase()),
+			_ => None,
+		};
+
+		let autocrlf = get_config_string_repo(repo, "core.autocrlf")?
+			.map(|value| value.to_lowercase());
+
+		let text = match AttrValue::from_string(
+			repo.get_attr(path, "text", flags)?,
+		) {
+			AttrValue::True | AttrValue::String(_) => true,
+			AttrValue::False => false,
+			_ => {
+				eol.is_some()
+					|| matches!(
+						autocrlf.as_deref(),
+						Some("true" | "input")
+					)
+			}
+		};
+
+		let crlf_in_workdir = text
+			&& match eol.as_deref() {
+				Some("crlf") => true,
+				Some(_) => false,
+				None => match autocrlf.as_deref() {
+					Some("true") => true,
+					Some("input") => false,
+					_ => get_config_string_repo(repo, "core.eol")?
+						.map_or(false, |eol| {
+							eol.eq_ignore_ascii_case("crlf")
+						}),
+				},
+			};
+
+		Ok(Self {
+			text,
+			crlf_in_workdir,
+		})
+	}
+
+	/// converts working directory content to what gets stored
+	pub fn to_odb(&self, content: String) -> String {
+		if self.text && content.contains(CRLF) {
+			content.replace(CRLF, "\n")
+		} else {
+			content
+		}
+	}
+
+	/// converts content to what gets written to the working directory
+	pub fn to_workdir(&self, content: String) -> String {
+		if self.crlf_in_workdir {
+			self.to_odb(content).replace(NEWLINE, CRLF)
+		} else {
+			content
+		}
+	}
 }
 
 pub fn load_file(
@@ -184,3 +443,132 @@ pub fn load_file(
 
 	Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::diff::HunkHeader;
+
+	fn hunk_lines<'a>(patch: &Patch<'a>) -> Vec<HunkLines<'a>> {
+		(0..patch.num_hunks())
+			.map(|hunk_idx| HunkLines {
+				index: hunk_idx,
+				hunk: HunkHeader::from(
+					patch.hunk(hunk_idx).unwrap().0,
+				),
+				lines: (0..patch
+					.num_lines_in_hunk(hunk_idx)
+					.unwrap())
+					.map(|line_idx| {
+						patch
+							.line_in_hunk(hunk_idx, line_idx)
+							.unwrap()
+					})
+					.collect(),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_apply_selection_checks_effect() {
+		let old = "a\nb\nc\n";
+		let patch = Patch::from_buffers(
+			old.as_bytes(),
+			None,
+			b"a\nx\nc\n",
+			None,
+			None,
+		)
+		.unwrap();
+		let hunks = hunk_lines(&patch);
+		let selection = [
+			DiffLinePosition {
+				old_lineno: Some(2),
+				new_lineno: None,
+			},
+			DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(2),
+			},
+		];
+
+		let old_lines =
+			old.split_inclusive(NEWLINE).collect::<Vec<_>>();
+		assert_eq!(
+			apply_selection(
+				&selection, &hunks, &old_lines, false, false
+			)
+			.unwrap(),
+			"a\nx\nc\n"
+		);
+
+		// hunks not matching the content they are applied to
+		let inconsistent = ["a\n", "q\n", "c\n"];
+		assert!(matches!(
+			apply_selection(
+				&selection,
+				&hunks,
+				&inconsistent,
+				false,
+				false
+			),
+			Err(Error::PatchApplyMismatch)
+		));
+	}
+
+	#[test]
+	fn test_check_selection_effect() {
+		let patch = Patch::from_buffers(
+			b"a\nb\n",
+			None,
+			b"a\nb\nc\n",
+			None,
+			None,
+		)
+		.unwrap();
+		let hunks = hunk_lines(&patch);
+		let added = DiffLinePosition {
+			old_lineno: None,
+			new_lineno: Some(3),
+		};
+		let selection = HashSet::from([&added]);
+
+		assert!(check_selection_effect(
+			&selection,
+			&hunks,
+			"a\nb\n",
+			"a\nb\nc\n",
+			false
+		)
+		.is_ok());
+		// CRLF in the workdir does not count as a change
+		assert!(check_selection_effect(
+			&selection,
+			&hunks,
+			"a\r\nb\r\n",
+			"a\nb\nc\n",
+			false
+		)
+		.is_ok());
+		assert!(matches!(
+			check_selection_effect(
+				&selection,
+				&hunks,
+				"a\nb\n",
+				"a\nb\nd\n",
+				false
+			),
+			Err(Error::PatchApplyMismatch)
+		));
+		assert!(matches!(
+			check_selection_effect(
+				&selection,
+				&hunks,
+				"a\nb\n",
+				"a\nb\nc\n",
+				true
+			),
+			Err(Error::PatchApplyMismatch)
+		));
+	}
+}
@@ -1,17 +1,23 @@
-use super::apply_selection;
+use super::{apply_selection, EolConversion, NEWLINE};
 use crate::{
 	error::{Error, Result},
 	sync::{
 		diff::DiffLinePosition,
-		patches::get_file_diff_patch_and_hunklines, repository::repo,
+		patches::{get_file_diff_patch_and_hunklines, HunkLines},
+		repository::repo,
+		utils::work_dir,
 		RepoPath,
 	},
 };
 use easy_cast::Conv;
+use git2::{
+	DiffLineType, FileMode, IndexEntry, IndexTime, Oid, Repository,
+};
 use scopetime::scope_time;
-use std::path::Path;
+use std::{fs, path::Path};
 
-///
+/// (un)stages the added/deleted `lines` of a file, these are logical
+/// lines of the diff however they end up wrapped when rendered
 pub fn stage_lines(
 	repo_path: &RepoPath,
 	file_path: &str,
@@ -27,25 +33,125 @@ pub fn stage_lines(
 	let repo = repo(repo_path)?;
 	// log::debug!("stage_lines: {:?}", lines);
 
+	stage_selection(&repo, file_path, is_stage, |_| lines.to_vec())
+}
+
+/// (un)stages multiple hunks of a file writing the index only once.
+/// `hunk_indices` refer to the hunks of a diff using one line of context
+/// (see `DiffOptions::context`)
+pub fn stage_hunks(
+	repo_path: &RepoPath,
+	file_path: &str,
+	is_stage: bool,
+	hunk_indices: &[usize],
+) -> Result<()> {
+	scope_time!("stage_hunks");
+
+	if hunk_indices.is_empty() {
+		return Ok(());
+	}
+
+	let repo = repo(repo_path)?;
+
+	stage_selection(&repo, file_path, is_stage, |hunks| {
+		hunks
+			.iter()
+			.filter(|hunk| hunk_indices.contains(&hunk.index))
+			.flat_map(|hunk| hunk.lines.iter())
+			.filter(|line| {
+				matches!(
+					line.origin_value(),
+					DiffLineType::Addition | DiffLineType::Deletion
+				)
+			})
+			.map(DiffLinePosition::from)
+			.collect()
+	})
+}
+
+/// stages hunk `start` and all hunks below it like `git add -p` does
+/// with `a`, writing the index only once
+///
+/// Returns the indices of the staged hunks, these refer to the hunks of
+/// a diff using one line of context (see `stage_hunks`).
+pub fn stage_hunks_from(
+	repo_path: &RepoPath,
+	file_path: &str,
+	start: usize,
+) -> Result<Vec<usize>> {
+	scope_time!("stage_hunks_from");
+
+	let repo = repo(repo_path)?;
+	let mut staged = Vec::new();
+
+	stage_selection(&repo, file_path, false, |hunks| {
+		hunks
+			.iter()
+			.filter(|hunk| hunk.index >= start)
+			.inspect(|hunk| staged.push(hunk.index))
+			.flat_map(|hunk| hunk.lines.iter())
+			.filter(|line| {
+				matches!(
+					line.origin_value(),
+					DiffLineType::Addition | DiffLineType::Deletion
+				)
+			})
+			.map(DiffLinePosition::from)
+			.collect()
+	})?;
+
+	Ok(staged)
+}
+
+fn stage_selection<F>(
+	repo: &Repository,
+	file_path: &str,
+	is_stage: bool,
+	select_lines: F,
+) -> Result<()>
+where
+	F: FnOnce(&[HunkLines]) -> Vec<DiffLinePosition>,
+{
 	let mut index = repo.index()?;
 	index.read(true)?;
-	let mut idx =
-		index.get_path(Path::new(file_path), 0).ok_or_else(|| {
-			Error::Generic(String::from(
-				"only non new files supported",
-			))
-		})?;
-	let blob = repo.find_blob(idx.id)?;
-	let indexed_content = String::from_utf8(blob.content().into())?;
+	let (mut idx, indexed_content) =
+		if let Some(idx) = index.get_path(Path::new(file_path), 0) {
+			let blob = repo.find_blob(idx.id)?;
+			if blob.is_binary() {
+				return Err(Error::BinaryFile);
+			}
+			let content = String::from_utf8(blob.content().into())?;
+			(idx, content)
+		} else if is_stage {
+			return Err(Error::Generic(String::from(
+				"file not found in index",
+			)));
+		} else {
+			// untracked file: diff is against empty content
+			(
+				index_entry_for_untracked_file(repo, file_path)?,
+				String::new(),
+			)
+		};
 
 	let new_content = {
-		let (_patch, hunks) = get_file_diff_patch_and_hunklines(
-			&repo, file_path, is_stage, false,
+		let (patch, hunks) = get_file_diff_patch_and_hunklines(
+			repo, file_path, is_stage, false,
 		)?;
 
-		let old_lines = indexed_content.lines().collect::<Vec<_>>();
+		if patch.delta().flags().is_binary() {
+			return Err(Error::BinaryFile);
+		}
+
+		let lines = select_lines(&hunks);
 
-		apply_selection(lines, &hunks, &old_lines, is_stage, false)?
+		let old_lines = indexed_content
+			.split_inclusive(NEWLINE)
+			.collect::<Vec<_>>();
+
+		EolConversion::new(repo, file_path)?.to_odb(apply_selection(
+			&lines, &hunks, &old_lines, is_stage, false,
+		)?)
 	};
 
 	let blob_id = repo.blob(new_content.as_bytes())?;
@@ -60,11 +166,42 @@ pub fn stage_lines(
 	Ok(())
 }
 
+/// index entry for a file that is not in the index yet,
+/// `id` and `file_size` are expected to be set by the caller
+fn index_entry_for_untracked_file(
+	repo: &Repository,
+	file_path: &str,
+) -> Result<IndexEntry> {
+	let meta = fs::symlink_metadata(work_dir(repo)?.join(file_path))?;
+
+	if meta.file_type().is_dir() || meta.file_type().is_symlink() {
+		return Err(Error::Generic(String::from(
+			"only regular files supported",
+		)));
+	}
+
+	Ok(IndexEntry {
+		ctime: IndexTime::new(0, 0),
+		mtime: IndexTime::new(0, 0),
+		dev: 0,
+		ino: 0,
+		mode: u32::from(FileMode::Blob),
+		uid: 0,
+		gid: 0,
+		file_size: 0,
+		id: Oid::zero(),
+		flags: 0,
+		flags_extended: 0,
+		path: file_path.as_bytes().to_vec(),
+	})
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
 	use crate::sync::{
-		diff::get_diff,
+		diff::{get_diff, DiffOptions},
+		staging::lines_in_range,
 		tests::{get_statuses, repo_init, write_commit_file},
 		utils::{repo_write_file, stage_add_file},
 	};
@@ -139,8 +276,12 @@ c = 4";
 
 		let diff = get_diff(path, "test.txt", true, None).unwrap();
 
-		assert_eq!(diff.lines, 5);
-		assert_eq!(&*diff.hunks[0].lines[0].content, "@@ -1,2 +1 @@");
+		// staging both deletions leaves an empty blob behind
+		assert_eq!(diff.lines, 4);
+		assert_eq!(
+			&*diff.hunks[0].lines[0].content,
+			"@@ -1,2 +0,0 @@"
+		);
 	}
 
 	#[test]
@@ -189,4 +330,509 @@ c = 4";
 
 		assert_eq!(diff.lines, 4);
 	}
+
+	#[test]
+	fn test_unstage_last_line() {
+		static FILE_1: &str = r"0
+";
+
+		static FILE_2: &str = r"0
+1
+2
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		stage_add_file(path, Path::new("test.txt")).unwrap();
+
+		stage_lines(
+			path,
+			"test.txt",
+			true,
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(3),
+			}],
+		)
+		.unwrap();
+
+		assert_eq!(get_statuses(path), (1, 1));
+
+		let diff = get_diff(path, "test.txt", true, None).unwrap();
+
+		assert_eq!(diff.lines, 3);
+		assert_eq!(&*diff.hunks[0].lines[0].content, "@@ -1 +1,2 @@");
+		assert_eq!(&*diff.hunks[0].lines[2].content, "1");
+	}
+
+	#[test]
+	fn test_unstage_last_line_no_newline() {
+		static FILE_1: &str = r"0
+1";
+
+		static FILE_2: &str = r"0
+1
+2";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		stage_add_file(path, Path::new("test.txt")).unwrap();
+
+		stage_lines(
+			path,
+			"test.txt",
+			true,
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(3),
+			}],
+		)
+		.unwrap();
+
+		let diff = get_diff(path, "test.txt", true, None).unwrap();
+
+		// only the newline added to line `1` remains staged
+		assert_eq!(diff.hunks.len(), 1);
+		assert_eq!(
+			&*diff.hunks[0].lines[0].content,
+			"@@ -1,2 +1,2 @@"
+		);
+		assert_eq!(diff.hunks[0].lines.len(), 5);
+	}
+
+	#[test]
+	fn test_stage_untracked_lines() {
+		static FILE_1: &str = r"1
+2
+3
+4
+5
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		repo_write_file(&repo, "test.txt", FILE_1).unwrap();
+
+		assert_eq!(get_statuses(path), (1, 0));
+
+		stage_lines(
+			path,
+			"test.txt",
+			false,
+			&[
+				DiffLinePosition {
+					old_lineno: None,
+					new_lineno: Some(2),
+				},
+				DiffLinePosition {
+					old_lineno: None,
+					new_lineno: Some(4),
+				},
+			],
+		)
+		.unwrap();
+
+		assert_eq!(get_statuses(path), (1, 1));
+
+		let diff = get_diff(path, "test.txt", true, None).unwrap();
+
+		assert_eq!(diff.hunks.len(), 1);
+		assert_eq!(diff.lines, 3);
+		assert_eq!(
+			&*diff.hunks[0].lines[0].content,
+			"@@ -0,0 +1,2 @@"
+		);
+		assert_eq!(&*diff.hunks[0].lines[1].content, "2");
+		assert_eq!(&*diff.hunks[0].lines[2].content, "4");
+	}
+
+	#[test]
+	fn test_stage_lines_missing_file() {
+		let (path, _repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		let lines = [DiffLinePosition {
+			old_lineno: None,
+			new_lineno: Some(1),
+		}];
+
+		assert!(
+			stage_lines(path, "missing.txt", false, &lines).is_err()
+		);
+		assert!(
+			stage_lines(path, "missing.txt", true, &lines).is_err()
+		);
+	}
+
+	#[test]
+	fn test_stage_hunks() {
+		static FILE_1: &str = r"1
+2
+3
+4
+5
+6
+7
+8
+9
+10
+11
+";
+
+		static FILE_2: &str = r"1
+a
+3
+4
+5
+b
+7
+8
+9
+c
+11
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		let options = DiffOptions {
+			context: 1,
+			..DiffOptions::default()
+		};
+
+		let diff =
+			get_diff(path, "test.txt", false, Some(options)).unwrap();
+
+		assert_eq!(diff.hunks.len(), 3);
+
+		stage_hunks(path, "test.txt", false, &[0, 2]).unwrap();
+
+		let diff =
+			get_diff(path, "test.txt", true, Some(options)).unwrap();
+
+		assert_eq!(diff.hunks.len(), 2);
+		assert_eq!(&*diff.hunks[0].lines[3].content, "a");
+		assert_eq!(&*diff.hunks[1].lines[3].content, "c");
+
+		let diff =
+			get_diff(path, "test.txt", false, Some(options)).unwrap();
+
+		assert_eq!(diff.hunks.len(), 1);
+		assert_eq!(&*diff.hunks[0].lines[3].content, "b");
+	}
+
+	#[test]
+	fn test_stage_long_lines_one_by_one() {
+		let long_line = |i: usize, tag: &str| {
+			format!("{i} {}{tag}\n", "wrapped text ".repeat(40))
+		};
+		let file_1 =
+			(0..4).map(|i| long_line(i, "")).collect::<String>();
+		let file_2 = (0..4)
+			.map(|i| long_line(i, " changed"))
+			.collect::<String>();
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", &file_1, "c1");
+		repo_write_file(&repo, "test.txt", &file_2).unwrap();
+
+		let indexed = || {
+			let mut index = repo.index().unwrap();
+			index.read(true).unwrap();
+			let entry =
+				index.get_path(Path::new("test.txt"), 0).unwrap();
+			String::from_utf8(
+				repo.find_blob(entry.id).unwrap().content().into(),
+			)
+			.unwrap()
+		};
+
+		// stage every logical line on its own, last one first
+		for line in (1..=4).rev() {
+			stage_lines(
+				path,
+				"test.txt",
+				false,
+				&[
+					DiffLinePosition {
+						old_lineno: Some(line),
+						new_lineno: None,
+					},
+					DiffLinePosition {
+						old_lineno: None,
+						new_lineno: Some(line),
+					},
+				],
+			)
+			.unwrap();
+
+			let expected = (0..4_usize)
+				.map(|i| {
+					if i + 1 >= usize::try_from(line).unwrap() {
+						long_line(i, " changed")
+					} else {
+						long_line(i, "")
+					}
+				})
+				.collect::<String>();
+			assert_eq!(indexed(), expected);
+		}
+
+		assert_eq!(get_statuses(path), (0, 1));
+
+		// unstaging works the same way
+		let diff = get_diff(path, "test.txt", true, None).unwrap();
+		let first = diff.hunks[0]
+			.lines
+			.iter()
+			.filter(|line| line.content.starts_with("0 "))
+			.map(|line| line.position)
+			.collect::<Vec<_>>();
+		assert_eq!(first.len(), 2);
+
+		stage_lines(path, "test.txt", true, &first).unwrap();
+
+		let expected = (0..4)
+			.map(|i| {
+				long_line(i, if i == 0 { "" } else { " changed" })
+			})
+			.collect::<String>();
+		assert_eq!(indexed(), expected);
+	}
+
+	#[test]
+	fn test_stage_hunks_from() {
+		static FILE_1: &str = r"1
+2
+3
+4
+5
+6
+7
+8
+9
+10
+11
+";
+
+		static FILE_2: &str = r"1
+a
+3
+4
+5
+b
+7
+8
+9
+c
+11
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		let options = DiffOptions {
+			context: 1,
+			..DiffOptions::default()
+		};
+
+		assert_eq!(
+			stage_hunks_from(path, "test.txt", 1).unwrap(),
+			vec![1, 2]
+		);
+
+		let diff =
+			get_diff(path, "test.txt", true, Some(options)).unwrap();
+
+		assert_eq!(diff.hunks.len(), 2);
+		assert_eq!(&*diff.hunks[0].lines[3].content, "b");
+		assert_eq!(&*diff.hunks[1].lines[3].content, "c");
+
+		let diff =
+			get_diff(path, "test.txt", false, Some(options)).unwrap();
+
+		assert_eq!(diff.hunks.len(), 1);
+		assert_eq!(&*diff.hunks[0].lines[3].content, "a");
+
+		assert!(stage_hunks_from(path, "test.txt", 5)
+			.unwrap()
+			.is_empty());
+	}
+
+	#[test]
+	fn test_stage_crlf() {
+		static FILE_1: &str = "a\r\nb";
+
+		static FILE_2: &str = "a\r\nb\r\nc\r\nd";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		stage_lines(
+			path,
+			"test.txt",
+			false,
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(3),
+			}],
+		)
+		.unwrap();
+
+		let mut index = repo.index().unwrap();
+		index.read(true).unwrap();
+		let entry = index.get_path(Path::new("test.txt"), 0).unwrap();
+		let blob = repo.find_blob(entry.id).unwrap();
+
+		assert_eq!(blob.content(), b"a\r\nb\r\nc\r\n");
+	}
+
+	#[test]
+	fn test_stage_eol_lf_attribute() {
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(
+			&repo,
+			".gitattributes",
+			"*.txt eol=lf\n",
+			"c1",
+		);
+		write_commit_file(&repo, "test.txt", "a\nb\n", "c2");
+
+		repo_write_file(&repo, "test.txt", "a\r\nb\r\nc\r\nd\r\n")
+			.unwrap();
+
+		stage_lines(
+			path,
+			"test.txt",
+			false,
+			&[DiffLinePosition {
+				old_lineno: None,
+				new_lineno: Some(3),
+			}],
+		)
+		.unwrap();
+
+		let mut index = repo.index().unwrap();
+		index.read(true).unwrap();
+		let entry = index.get_path(Path::new("test.txt"), 0).unwrap();
+		let blob = repo.find_blob(entry.id).unwrap();
+
+		assert_eq!(blob.content(), b"a\nb\nc\n");
+	}
+
+	#[test]
+	fn test_stage_lines_binary() {
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		let lines = [DiffLinePosition {
+			old_lineno: None,
+			new_lineno: Some(1),
+		}];
+
+		repo_write_file(&repo, "untracked.bin", "a\0b\n").unwrap();
+
+		assert!(matches!(
+			stage_lines(path, "untracked.bin", false, &lines),
+			Err(Error::BinaryFile)
+		));
+
+		write_commit_file(&repo, "test.bin", "a\0b\n", "c1");
+
+		repo_write_file(&repo, "test.bin", "a\0c\n").unwrap();
+
+		assert!(matches!(
+			stage_lines(path, "test.bin", false, &lines),
+			Err(Error::BinaryFile)
+		));
+	}
+
+	#[test]
+	fn test_lines_in_range_across_hunks() {
+		static FILE_1: &str = r"1
+2
+3
+4
+5
+6
+7
+8
+9
+10
+11
+";
+
+		static FILE_2: &str = r"1
+a
+3
+4
+5
+6
+7
+8
+9
+b
+11
+";
+
+		let (path, repo) = repo_init().unwrap();
+		let path: &RepoPath = &path.path().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", FILE_1, "c1");
+
+		repo_write_file(&repo, "test.txt", FILE_2).unwrap();
+
+		let diff = get_diff(path, "test.txt", false, None).unwrap();
+
+		assert_eq!(diff.hunks.len(), 2);
+
+		let start = DiffLinePosition {
+			old_lineno: None,
+			new_lineno: Some(2),
+		};
+		let end = DiffLinePosition {
+			old_lineno: Some(10),
+			new_lineno: None,
+		};
+
+		let lines = lines_in_range(&diff.hunks, start, end);
+
+		assert_eq!(lines, vec![start, end]);
+		assert_eq!(lines_in_range(&diff.hunks, end, start), lines);
+
+		stage_lines(path, "test.txt", false, &lines).unwrap();
+
+		let diff = get_diff(path, "test.txt", true, None).unwrap();
+
+		assert_eq!(diff.hunks.len(), 2);
+		assert_eq!(&*diff.hunks[0].lines[3].content, "a");
+		assert_eq!(&*diff.hunks[1].lines[4].content, "10");
+	}
 }
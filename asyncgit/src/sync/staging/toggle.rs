@@ -0,0 +1,195 @@
+use crate::{
+	error::Result,
+	sync::{
+		repository::repo,
+		reset::reset_stage,
+		utils::{stage_add_file, stage_addremoved},
+		RepoPath,
+	},
+};
+use git2::Status;
+use scopetime::scope_time;
+use std::path::Path;
+
+/// staging state of a file, see [`toggle_stage_file`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileStageState {
+	/// no changes at all
+	Unchanged,
+	/// all changes are unstaged
+	Unstaged,
+	/// some changes are staged, others are not
+	Partial,
+	/// all changes are staged
+	Staged,
+}
+
+///
+pub fn file_stage_state(
+	repo_path: &RepoPath,
+	file_path: &str,
+) -> Result<FileStageState> {
+	scope_time!("file_stage_state");
+
+	let repo = repo(repo_path)?;
+	let status = repo.status_file(Path::new(file_path))?;
+
+	let staged = status.intersects(
+		Status::INDEX_NEW
+			| Status::INDEX_MODIFIED
+			| Status::INDEX_DELETED
+			| Status::INDEX_RENAMED
+			| Status::INDEX_TYPECHANGE,
+	);
+	let unstaged = status.intersects(
+		Status::WT_NEW
+			| Status::WT_MODIFIED
+			| Status::WT_DELETED
+			| Status::WT_RENAMED
+			| Status::WT_TYPECHANGE,
+	);
+
+	Ok(match (staged, unstaged) {
+		(false, false) => FileStageState::Unchanged,
+		(false, true) => FileStageState::Unstaged,
+		(true, true) => FileStageState::Partial,
+		(true, false) => FileStageState::Staged,
+	})
+}
+
+/// toggles staging of a whole file and returns its new state
+///
+/// Any unstaged change is staged, so a partially staged file becomes
+/// fully staged rather than losing what was staged before. Only a
+/// fully staged file is unstaged again (back to `HEAD`), an unchanged
+/// file is left alone.
+pub fn toggle_stage_file(
+	repo_path: &RepoPath,
+	file_path: &str,
+) -> Result<FileStageState> {
+	scope_time!("toggle_stage_file");
+
+	let path = Path::new(file_path);
+
+	match file_stage_state(repo_path, file_path)? {
+		FileStageState::Unchanged => (),
+		FileStageState::Unstaged | FileStageState::Partial => {
+			let repo = repo(repo_path)?;
+			let status = repo.status_file(path)?;
+
+			if status.contains(Status::WT_DELETED) {
+				stage_addremoved(repo_path, path)?;
+			} else {
+				stage_add_file(repo_path, path)?;
+			}
+		}
+		FileStageState::Staged => reset_stage(repo_path, file_path)?,
+	}
+
+	file_stage_state(repo_path, file_path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		diff::get_diff,
+		stage_hunks,
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+
+	#[test]
+	fn test_toggle_stage_file_transitions() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = (0..20)
+			.map(|i| format!("line{i}"))
+			.collect::<Vec<_>>()
+			.join("\n")
+			+ "\n";
+		write_commit_file(&repo, "a.txt", &content, "c1");
+
+		assert_eq!(
+			toggle_stage_file(repo_path, "a.txt")?,
+			FileStageState::Unchanged
+		);
+
+		repo_write_file(
+			&repo,
+			"a.txt",
+			&content
+				.replace("line2\n", "x\n")
+				.replace("line15\n", "y\n"),
+		)?;
+		assert_eq!(
+			file_stage_state(repo_path, "a.txt")?,
+			FileStageState::Unstaged
+		);
+
+		// none -> all
+		assert_eq!(
+			toggle_stage_file(repo_path, "a.txt")?,
+			FileStageState::Staged
+		);
+		assert_eq!(
+			get_diff(repo_path, "a.txt", true, None)?.hunks.len(),
+			2
+		);
+
+		// all -> none
+		assert_eq!(
+			toggle_stage_file(repo_path, "a.txt")?,
+			FileStageState::Unstaged
+		);
+		assert!(get_diff(repo_path, "a.txt", true, None)?
+			.hunks
+			.is_empty());
+
+		stage_hunks(repo_path, "a.txt", false, &[0])?;
+		assert_eq!(
+			file_stage_state(repo_path, "a.txt")?,
+			FileStageState::Partial
+		);
+
+		// partial -> all
+		assert_eq!(
+			toggle_stage_file(repo_path, "a.txt")?,
+			FileStageState::Staged
+		);
+		assert_eq!(
+			get_diff(repo_path, "a.txt", true, None)?.hunks.len(),
+			2
+		);
+		assert!(get_diff(repo_path, "a.txt", false, None)?
+			.hunks
+			.is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_toggle_stage_deleted_file() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a\n", "c1");
+		std::fs::remove_file(root.join("a.txt"))?;
+
+		assert_eq!(
+			toggle_stage_file(repo_path, "a.txt")?,
+			FileStageState::Staged
+		);
+		assert_eq!(
+			toggle_stage_file(repo_path, "a.txt")?,
+			FileStageState::Unstaged
+		);
+
+		Ok(())
+	}
+}
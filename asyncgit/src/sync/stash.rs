@@ -1,13 +1,25 @@
 use super::{CommitId, RepoPath};
 use crate::{
 	error::{Error, Result},
-	sync::repository::repo,
+	sync::{
+		commit_files::{get_commit_diff, CommitStats},
+		repository::repo,
+		status::renamed_from,
+		utils::{conflicted_paths, work_dir},
+	},
+	StatusItem, StatusItemType,
 };
 use git2::{
-	build::CheckoutBuilder, Oid, Repository, StashApplyOptions,
-	StashFlags,
+	build::CheckoutBuilder, Commit, Index, IndexEntry, Oid, Pathspec,
+	PathspecFlags, Repository, StashApplyOptions, StashFlags, Tree,
 };
 use scopetime::scope_time;
+use std::{
+	collections::BTreeSet,
+	path::{Path, PathBuf},
+};
+
+const STASH_REF: &str = "refs/stash";
 
 ///
 pub fn get_stashes(repo_path: &RepoPath) -> Result<Vec<CommitId>> {
@@ -23,6 +35,80 @@ pub fn get_stashes(repo_path: &RepoPath) -> Result<Vec<CommitId>> {
 	Ok(list)
 }
 
+/// a stash and a summary of what it contains
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StashDetail {
+	/// position in the stash list, `0` being the latest stash
+	pub index: usize,
+	///
+	pub id: CommitId,
+	///
+	pub message: String,
+	/// files changed compared to the commit the stash is based on,
+	/// stashed untracked files included
+	pub files: Vec<StatusItem>,
+	/// line counts of `files`
+	pub stats: CommitStats,
+}
+
+/// like `get_stashes` but including message and changed files of
+/// every stash
+pub fn stash_list_detailed(
+	repo_path: &RepoPath,
+) -> Result<Vec<StashDetail>> {
+	scope_time!("stash_list_detailed");
+
+	let mut repo = repo(repo_path)?;
+
+	let mut list = Vec::new();
+	repo.stash_foreach(|index, msg, id| {
+		list.push((index, CommitId::new(*id), msg.to_string()));
+		true
+	})?;
+
+	let stashes = list.iter().map(|(_, id, _)| *id).collect();
+
+	list.into_iter()
+		.map(|(index, id, message)| {
+			let diff = get_commit_diff(
+				&repo,
+				id,
+				None,
+				None,
+				Some(&stashes),
+			)?;
+			let stats = diff.stats()?;
+
+			let mut files = diff
+				.deltas()
+				.map(|delta| StatusItem {
+					path: delta
+						.new_file()
+						.path()
+						.or_else(|| delta.old_file().path())
+						.map(|p| p.to_string_lossy().to_string())
+						.unwrap_or_default(),
+					old_path: renamed_from(&delta),
+					status: StatusItemType::from(delta.status()),
+				})
+				.collect::<Vec<_>>();
+			files.sort_by(|a, b| a.path.cmp(&b.path));
+
+			Ok(StashDetail {
+				index,
+				id,
+				message,
+				files,
+				stats: CommitStats {
+					files: stats.files_changed(),
+					insertions: stats.insertions(),
+					deletions: stats.deletions(),
+				},
+			})
+		})
+		.collect()
+}
+
 ///
 pub fn stash_drop(
 	repo_path: &RepoPath,
@@ -39,20 +125,45 @@ pub fn stash_drop(
 	Ok(())
 }
 
-///
+/// result of popping a stash
+#[derive(Debug, PartialEq, Eq)]
+pub enum StashPopResult {
+	/// the stash applied cleanly and was dropped
+	Popped,
+	/// applying the stash left these paths conflicted, the stash is
+	/// kept
+	Conflicted(Vec<String>),
+}
+
+/// applies the stash and drops it only if that did not leave any
+/// conflicts behind
 pub fn stash_pop(
 	repo_path: &RepoPath,
 	stash_id: CommitId,
-) -> Result<()> {
+) -> Result<StashPopResult> {
 	scope_time!("stash_pop");
 
 	let mut repo = repo(repo_path)?;
 
 	let index = get_stash_index(&mut repo, stash_id.into())?;
 
-	repo.stash_pop(index, None)?;
+	repo.stash_apply(index, None)?;
 
-	Ok(())
+	let conflicted = conflicted_paths(&repo)?;
+	if !conflicted.is_empty() {
+		return Ok(StashPopResult::Conflicted(conflicted));
+	}
+
+	repo.stash_drop(index)?;
+
+	Ok(StashPopResult::Popped)
+}
+
+/// result of applying a stash
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StashApplyResult {
+	/// paths left conflicted in the index by the apply
+	pub conflicted_paths: Vec<String>,
 }
 
 ///
@@ -60,7 +171,7 @@ pub fn stash_apply(
 	repo_path: &RepoPath,
 	stash_id: CommitId,
 	allow_conflicts: bool,
-) -> Result<()> {
+) -> Result<StashApplyResult> {
 	scope_time!("stash_apply");
 
 	let mut repo = repo(repo_path)?;
@@ -74,7 +185,9 @@ pub fn stash_apply(
 	opt.checkout_options(checkout);
 	repo.stash_apply(index, Some(&mut opt))?;
 
-	Ok(())
+	Ok(StashApplyResult {
+		conflicted_paths: conflicted_paths(&repo)?,
+	})
 }
 
 fn get_stash_index(
@@ -97,6 +210,54 @@ fn get_stash_index(
 	})
 }
 
+/// replaces the message of the stash at `index` (`0` being the latest
+/// stash), the stashed commits stay untouched
+pub fn rename_stash(
+	repo_path: &RepoPath,
+	index: usize,
+	new_message: &str,
+) -> Result<()> {
+	scope_time!("rename_stash");
+
+	let repo = repo(repo_path)?;
+
+	let mut reflog = repo.reflog(STASH_REF)?;
+
+	if index >= reflog.len() {
+		return Err(Error::Generic(format!(
+			"stash@{{{index}}} does not exist"
+		)));
+	}
+
+	// the reflog can only be appended to, so it is rebuilt from the
+	// oldest entry on with the message of `index` replaced
+	let entries = reflog
+		.iter()
+		.enumerate()
+		.map(|(idx, entry)| {
+			let message = if idx == index {
+				Some(new_message.to_string())
+			} else {
+				entry.message().map(String::from)
+			};
+
+			(entry.id_new(), entry.committer().to_owned(), message)
+		})
+		.collect::<Vec<_>>();
+
+	for _ in 0..entries.len() {
+		reflog.remove(0, false)?;
+	}
+
+	for (id, committer, message) in entries.iter().rev() {
+		reflog.append(*id, committer, message.as_deref())?;
+	}
+
+	reflog.write()?;
+
+	Ok(())
+}
+
 ///
 pub fn stash_save(
 	repo_path: &RepoPath,
@@ -124,6 +285,221 @@ pub fn stash_save(
 	Ok(CommitId::new(id))
 }
 
+/// stashes only the changes to files matching `paths` (relative to
+/// the repository root), all other changes stay in the working tree
+/// and index untouched
+pub fn stash_save_pathspec(
+	repo_path: &RepoPath,
+	message: Option<&str>,
+	include_untracked: bool,
+	paths: &[&str],
+) -> Result<CommitId> {
+	scope_time!("stash_save_pathspec");
+
+	let mut repo = repo(repo_path)?;
+
+	let sig = repo.signature()?;
+	let pathspec = Pathspec::new(paths)?;
+
+	let mut flags = StashFlags::DEFAULT;
+
+	if include_untracked {
+		flags.insert(StashFlags::INCLUDE_UNTRACKED);
+	}
+
+	// git2 does not expose stashing a subset of files, so we stash
+	// everything, restore it right away and build a new stash out of
+	// the parts of the full one that match `paths`
+	let full = repo.stash_save2(&sig, message, Some(flags))?;
+
+	let mut opt = StashApplyOptions::default();
+	opt.reinstantiate_index();
+	repo.stash_pop(0, Some(&mut opt))?;
+
+	let full = repo.find_commit(full)?;
+	let index_commit = full.parent(1)?;
+	let head = repo.head()?.peel_to_commit()?;
+	let head_tree = head.tree()?;
+
+	let index_tree = repo.find_tree(filter_tree(
+		&repo,
+		Some(&head_tree),
+		&index_commit.tree()?,
+		&pathspec,
+	)?)?;
+	let work_tree = repo.find_tree(filter_tree(
+		&repo,
+		Some(&head_tree),
+		&full.tree()?,
+		&pathspec,
+	)?)?;
+	let mut stashed = changed_paths(&repo, &head_tree, &index_tree)?;
+	stashed.extend(changed_paths(&repo, &head_tree, &work_tree)?);
+
+	let mut untracked = None;
+	if let Some(commit) = full.parents().nth(2) {
+		let tree = repo.find_tree(filter_tree(
+			&repo,
+			None,
+			&commit.tree()?,
+			&pathspec,
+		)?)?;
+
+		if !tree.is_empty() {
+			stashed.extend(tree_paths(&tree)?);
+			untracked = Some(repo.find_commit(repo.commit(
+				None,
+				&commit.author(),
+				&commit.committer(),
+				commit.message().unwrap_or_default(),
+				&tree,
+				&[],
+			)?)?);
+		}
+	}
+
+	if stashed.is_empty() {
+		return Err(Error::Generic(
+			"nothing to stash for the given paths".to_string(),
+		));
+	}
+
+	let index_commit = repo.find_commit(repo.commit(
+		None,
+		&index_commit.author(),
+		&index_commit.committer(),
+		index_commit.message().unwrap_or_default(),
+		&index_tree,
+		&[&head],
+	)?)?;
+
+	let mut parents = vec![&head, &index_commit];
+	parents.extend(untracked.as_ref());
+
+	let id = repo.commit(
+		None,
+		&full.author(),
+		&full.committer(),
+		full.message().unwrap_or_default(),
+		&work_tree,
+		parents.as_slice(),
+	)?;
+
+	repo.reference_ensure_log(STASH_REF)?;
+	repo.reference(
+		STASH_REF,
+		id,
+		true,
+		full.summary().unwrap_or_default(),
+	)?;
+
+	remove_stashed_changes(&repo, &head, &stashed)?;
+
+	Ok(CommitId::new(id))
+}
+
+/// `base` (or an empty tree) with all entries matching `pathspec`
+/// replaced by the ones found in `changed`
+fn filter_tree(
+	repo: &Repository,
+	base: Option<&Tree>,
+	changed: &Tree,
+	pathspec: &Pathspec,
+) -> Result<Oid> {
+	let matches = |entry: &IndexEntry| {
+		std::str::from_utf8(&entry.path).map_or(false, |path| {
+			pathspec
+				.matches_path(Path::new(path), PathspecFlags::DEFAULT)
+		})
+	};
+
+	let mut index = Index::new()?;
+	if let Some(base) = base {
+		index.read_tree(base)?;
+	}
+
+	let removed = index
+		.iter()
+		.filter(matches)
+		.map(|entry| String::from_utf8(entry.path))
+		.collect::<std::result::Result<Vec<_>, _>>()?;
+	for path in removed {
+		index.remove(Path::new(&path), 0)?;
+	}
+
+	let mut changed_index = Index::new()?;
+	changed_index.read_tree(changed)?;
+	for entry in changed_index.iter().filter(matches) {
+		index.add(&entry)?;
+	}
+
+	Ok(index.write_tree_to(repo)?)
+}
+
+/// paths that differ between `old` and `new`
+fn changed_paths(
+	repo: &Repository,
+	old: &Tree,
+	new: &Tree,
+) -> Result<BTreeSet<PathBuf>> {
+	let diff = repo.diff_tree_to_tree(Some(old), Some(new), None)?;
+
+	Ok(diff
+		.deltas()
+		.filter_map(|delta| {
+			delta
+				.new_file()
+				.path()
+				.or_else(|| delta.old_file().path())
+				.map(Path::to_path_buf)
+		})
+		.collect())
+}
+
+/// paths of all files in `tree`
+fn tree_paths(tree: &Tree) -> Result<Vec<PathBuf>> {
+	let mut index = Index::new()?;
+	index.read_tree(tree)?;
+
+	index
+		.iter()
+		.map(|entry| {
+			Ok(PathBuf::from(String::from_utf8(entry.path)?))
+		})
+		.collect()
+}
+
+/// resets `paths` in index and working tree to their state in `head`
+fn remove_stashed_changes(
+	repo: &Repository,
+	head: &Commit,
+	paths: &BTreeSet<PathBuf>,
+) -> Result<()> {
+	let work_dir = work_dir(repo)?;
+	let head_tree = head.tree()?;
+
+	repo.reset_default(Some(head.as_object()), paths)?;
+
+	let mut checkout = CheckoutBuilder::new();
+	checkout.force();
+
+	let mut any_tracked = false;
+	for path in paths {
+		if head_tree.get_path(path).is_ok() {
+			checkout.path(path);
+			any_tracked = true;
+		} else {
+			std::fs::remove_file(work_dir.join(path))?;
+		}
+	}
+
+	if any_tracked {
+		repo.checkout_head(Some(&mut checkout))?;
+	}
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -222,7 +598,7 @@ mod tests {
 
 		File::create(root.join(file_path1))?.write_all(b"test")?;
 		stage_add_file(repo_path, file_path1)?;
-		commit(repo_path, "c1")?;
+		commit(repo_path, "c1", false)?;
 
 		File::create(root.join(file_path1))?
 			.write_all(b"modified")?;
@@ -306,6 +682,54 @@ mod tests {
 		assert!(res.is_ok());
 	}
 
+	#[test]
+	fn test_stash_apply_reports_conflicts() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "test", "c1");
+		write_commit_file(&repo, "other.txt", "other", "c2");
+
+		repo_write_file(&repo, "test.txt", "test2")?;
+		repo_write_file(&repo, "other.txt", "other2")?;
+
+		let id = stash_save(repo_path, Some("foo"), true, false)?;
+
+		write_commit_file(&repo, "test.txt", "test3", "c3");
+
+		let res = stash_apply(repo_path, id, false)?;
+
+		assert_eq!(
+			res.conflicted_paths,
+			vec![String::from("test.txt")]
+		);
+		assert_eq!(repo_read_file(&repo, "other.txt")?, "other2");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_stash_apply_without_conflicts() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "test", "c1");
+		repo_write_file(&repo, "test.txt", "test2")?;
+
+		let id = stash_save(repo_path, Some("foo"), true, false)?;
+
+		let res = stash_apply(repo_path, id, false)?;
+
+		assert_eq!(res, StashApplyResult::default());
+		assert_eq!(repo_read_file(&repo, "test.txt")?, "test2");
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_stash_pop_no_conflict() {
 		let (_td, repo) = repo_init().unwrap();
@@ -322,11 +746,39 @@ mod tests {
 
 		let res = stash_pop(repo_path, id);
 
-		assert!(res.is_ok());
+		assert_eq!(res.unwrap(), StashPopResult::Popped);
 		assert_eq!(
 			repo_read_file(&repo, "test.txt").unwrap(),
 			"test2"
 		);
+		assert!(get_stashes(repo_path).unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_stash_pop_keeps_conflicting_stash() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "test.txt", "test", "c1");
+		repo_write_file(&repo, "test.txt", "test2")?;
+
+		let id = stash_save(repo_path, Some("foo"), true, false)?;
+
+		write_commit_file(&repo, "test.txt", "test3", "c2");
+
+		let res = stash_pop(repo_path, id)?;
+
+		assert_eq!(
+			res,
+			StashPopResult::Conflicted(vec![String::from(
+				"test.txt"
+			)])
+		);
+		assert_eq!(get_stashes(repo_path)?, vec![id]);
+
+		Ok(())
 	}
 
 	#[test]
@@ -376,4 +828,175 @@ mod tests {
 			"test3"
 		);
 	}
+
+	#[test]
+	fn test_stash_pathspec() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "b.txt", "b", "c2");
+
+		repo_write_file(&repo, "a.txt", "a2")?;
+		repo_write_file(&repo, "b.txt", "b2")?;
+		stage_add_file(repo_path, Path::new("b.txt"))?;
+
+		let id = stash_save_pathspec(
+			repo_path,
+			Some("foo"),
+			false,
+			&["a.txt"],
+		)?;
+
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a");
+		assert_eq!(repo_read_file(&repo, "b.txt")?, "b2");
+		assert_eq!(get_statuses(repo_path), (0, 1));
+
+		assert_eq!(get_stashes(repo_path)?, vec![id]);
+
+		let files = get_commit_files(repo_path, id, None)?;
+		assert_eq!(files.len(), 1);
+		assert_eq!(files[0].path, "a.txt");
+
+		let infos = get_commits_info(repo_path, &[id], 100)?;
+		assert_eq!(infos[0].message, "On master: foo");
+
+		// libgit2 refuses to apply a stash onto a dirty index
+		commit(repo_path, "c3", false)?;
+		stash_pop(repo_path, id)?;
+
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a2");
+		assert!(get_stashes(repo_path)?.is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_stash_pathspec_keeps_older_stashes() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a", "c1");
+
+		repo_write_file(&repo, "a.txt", "a2")?;
+		let first =
+			stash_save(repo_path, Some("first"), false, false)?;
+
+		repo_write_file(&repo, "a.txt", "a3")?;
+		repo_write_file(&repo, "new.txt", "new")?;
+		let second = stash_save_pathspec(
+			repo_path,
+			Some("second"),
+			true,
+			&["new.txt"],
+		)?;
+
+		assert!(!root.join("new.txt").exists());
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a3");
+		assert_eq!(get_stashes(repo_path)?, vec![second, first]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_stash_list_detailed() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a\n", "c1");
+		write_commit_file(&repo, "b.txt", "b\n", "c2");
+
+		repo_write_file(&repo, "a.txt", "a\na2\n")?;
+		let first =
+			stash_save(repo_path, Some("first"), true, false)?;
+
+		repo_write_file(&repo, "b.txt", "")?;
+		repo_write_file(&repo, "c.txt", "c\n")?;
+		let second =
+			stash_save(repo_path, Some("second"), true, false)?;
+
+		let list = stash_list_detailed(repo_path)?;
+
+		assert_eq!(list.len(), 2);
+
+		assert_eq!(list[0].index, 0);
+		assert_eq!(list[0].id, second);
+		assert_eq!(list[0].message, "On master: second");
+		let files = list[0]
+			.files
+			.iter()
+			.map(|item| (item.path.as_str(), item.status))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			files,
+			vec![
+				("b.txt", StatusItemType::Modified),
+				("c.txt", StatusItemType::New)
+			]
+		);
+		assert_eq!(
+			list[0].stats,
+			CommitStats {
+				files: 2,
+				insertions: 1,
+				deletions: 1,
+			}
+		);
+
+		assert_eq!(list[1].index, 1);
+		assert_eq!(list[1].id, first);
+		assert_eq!(list[1].message, "On master: first");
+		assert_eq!(list[1].files.len(), 1);
+		assert_eq!(list[1].files[0].path, "a.txt");
+		assert_eq!(list[1].files[0].status, StatusItemType::Modified);
+		assert_eq!(
+			list[1].stats,
+			CommitStats {
+				files: 1,
+				insertions: 1,
+				deletions: 0,
+			}
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rename_stash() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a\n", "c1");
+
+		repo_write_file(&repo, "a.txt", "a\na2\n")?;
+		stash_save(repo_path, Some("first"), true, false)?;
+
+		repo_write_file(&repo, "b.txt", "b\n")?;
+		stash_save(repo_path, Some("second"), true, false)?;
+
+		let before = stash_list_detailed(repo_path)?;
+
+		rename_stash(repo_path, 1, "renamed")?;
+
+		let after = stash_list_detailed(repo_path)?;
+
+		assert_eq!(after.len(), 2);
+		assert_eq!(after[0], before[0]);
+		assert_eq!(after[1].message, "renamed");
+		assert_eq!(after[1].id, before[1].id);
+		assert_eq!(after[1].files, before[1].files);
+		assert_eq!(after[1].stats, before[1].stats);
+
+		assert!(rename_stash(repo_path, 2, "foo").is_err());
+
+		Ok(())
+	}
 }
@@ -15,6 +15,10 @@ pub enum RepoState {
 	///
 	Revert,
 	///
+	CherryPick,
+	///
+	Bisect,
+	///
 	Other,
 }
 
@@ -23,8 +27,13 @@ impl From<RepositoryState> for RepoState {
 		match state {
 			RepositoryState::Clean => Self::Clean,
 			RepositoryState::Merge => Self::Merge,
-			RepositoryState::Revert => Self::Revert,
-			RepositoryState::RebaseMerge => Self::Rebase,
+			RepositoryState::Revert
+			| RepositoryState::RevertSequence => Self::Revert,
+			RepositoryState::RebaseMerge
+			| RepositoryState::RebaseInteractive => Self::Rebase,
+			RepositoryState::CherryPick
+			| RepositoryState::CherryPickSequence => Self::CherryPick,
+			RepositoryState::Bisect => Self::Bisect,
 			_ => {
 				log::warn!("state not supported yet: {:?}", state);
 				Self::Other
@@ -33,7 +42,7 @@ impl From<RepositoryState> for RepoState {
 	}
 }
 
-///
+/// returns the operation (merge, rebase, ..) currently in progress
 pub fn repo_state(repo_path: &RepoPath) -> Result<RepoState> {
 	scope_time!("repo_state");
 
@@ -43,3 +52,51 @@ pub fn repo_state(repo_path: &RepoPath) -> Result<RepoState> {
 
 	Ok(state.into())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		abort_pending_state, checkout_branch, create_branch,
+		tests::{repo_init, write_commit_file},
+	};
+
+	#[test]
+	fn test_repo_state() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+
+		create_branch(repo_path, "feature").unwrap();
+		let feature =
+			write_commit_file(&repo, "feature.txt", "test", "c1");
+		checkout_branch(repo_path, "refs/heads/master").unwrap();
+		write_commit_file(&repo, "master.txt", "test", "c2");
+
+		let annotated =
+			repo.find_annotated_commit(feature.into()).unwrap();
+
+		repo.merge(&[&annotated], None, None).unwrap();
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Merge);
+		abort_pending_state(repo_path).unwrap();
+
+		let commit = repo.find_commit(feature.into()).unwrap();
+		repo.cherrypick(&commit, None).unwrap();
+		assert_eq!(
+			repo_state(repo_path).unwrap(),
+			RepoState::CherryPick
+		);
+		abort_pending_state(repo_path).unwrap();
+		repo.index().unwrap().read(true).unwrap();
+
+		let mut rebase =
+			repo.rebase(None, Some(&annotated), None, None).unwrap();
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Rebase);
+
+		rebase.abort().unwrap();
+		assert_eq!(repo_state(repo_path).unwrap(), RepoState::Clean);
+	}
+}
@@ -5,9 +5,12 @@ use crate::{
 	error::Result,
 	sync::{config::untracked_files_config_repo, repository::repo},
 };
-use git2::{Delta, Status, StatusOptions, StatusShow};
+use git2::{
+	Delta, DiffDelta, Repository, Status, StatusOptions, StatusShow,
+	Statuses,
+};
 use scopetime::scope_time;
-use std::path::Path;
+use std::{collections::HashSet, path::Path};
 
 use super::{RepoPath, ShowUntrackedFilesConfig};
 
@@ -63,10 +66,24 @@ impl From<Delta> for StatusItemType {
 pub struct StatusItem {
 	///
 	pub path: String,
+	/// path before the rename of `Renamed` items
+	pub old_path: Option<String>,
 	///
 	pub status: StatusItemType,
 }
 
+/// old path of a renamed `delta`
+pub(crate) fn renamed_from(delta: &DiffDelta) -> Option<String> {
+	if delta.status() == Delta::Renamed {
+		delta
+			.old_file()
+			.path()
+			.map(|p| p.to_string_lossy().to_string())
+	} else {
+		None
+	}
+}
+
 ///
 #[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
 pub enum StatusType {
@@ -94,6 +111,49 @@ impl From<StatusType> for StatusShow {
 	}
 }
 
+/// `GIT_INDEX_ENTRY_SKIP_WORKTREE` of the extended index entry flags
+const INDEX_ENTRY_SKIP_WORKTREE: u16 = 1 << 14;
+
+/// paths outside of the sparse-checkout cone
+///
+/// git marks these index entries as skip-worktree and does not check
+/// them out, libgit2 ignores that flag and reports them as deleted.
+pub(crate) fn sparse_checkout_excluded(
+	repo: &Repository,
+) -> Result<HashSet<String>> {
+	// git keeps `core.sparseCheckout` in `config.worktree` which
+	// libgit2 does not read
+	let sparse = repo
+		.config()?
+		.get_bool("core.sparseCheckout")
+		.unwrap_or_default()
+		|| repo.path().join("info").join("sparse-checkout").exists();
+
+	if !sparse {
+		return Ok(HashSet::new());
+	}
+
+	let mut excluded = HashSet::new();
+	for entry in repo.index()?.iter() {
+		if entry.flags_extended & INDEX_ENTRY_SKIP_WORKTREE != 0 {
+			excluded.insert(String::from_utf8(entry.path)?);
+		}
+	}
+
+	Ok(excluded)
+}
+
+/// whether `status` is just the missing workdir file of a path
+/// outside of the sparse-checkout cone
+fn is_sparse_excluded(
+	excluded: &HashSet<String>,
+	status: Status,
+	path: Option<&str>,
+) -> bool {
+	status == Status::WT_DELETED
+		&& path.map_or(false, |path| excluded.contains(path))
+}
+
 ///
 pub fn is_workdir_clean(
 	repo_path: &RepoPath,
@@ -122,15 +182,45 @@ pub fn is_workdir_clean(
 		);
 
 	let statuses = repo.statuses(Some(&mut options))?;
+	let excluded = sparse_checkout_excluded(&repo)?;
 
-	Ok(statuses.is_empty())
+	Ok(statuses
+		.iter()
+		.all(|e| is_sparse_excluded(&excluded, e.status(), e.path())))
 }
 
 /// gurantees sorting
+///
+/// `show_untracked` overrides `status.showUntrackedFiles`, with
+/// `ShowUntrackedFilesConfig::No` untracked files are not even looked
+/// for which keeps status fast in trees full of build output.
 pub fn get_status(
 	repo_path: &RepoPath,
 	status_type: StatusType,
 	show_untracked: Option<ShowUntrackedFilesConfig>,
+) -> Result<Vec<StatusItem>> {
+	get_status_with_renames(
+		repo_path,
+		status_type,
+		show_untracked,
+		false,
+		None,
+	)
+}
+
+/// like `get_status`, which only pairs up renames staged in the index,
+/// with `detect_renames` renames in the workdir are found too
+///
+/// Deleted files are paired with untracked ones at least
+/// `rename_threshold` percent similar (50 like git if `None`), these
+/// come as a single `Renamed` item with `old_path` set. Untracked files
+/// are included for that even if `show_untracked` says otherwise.
+pub fn get_status_with_renames(
+	repo_path: &RepoPath,
+	status_type: StatusType,
+	show_untracked: Option<ShowUntrackedFilesConfig>,
+	detect_renames: bool,
+	rename_threshold: Option<u16>,
 ) -> Result<Vec<StatusItem>> {
 	scope_time!("get_status");
 
@@ -156,14 +246,114 @@ pub fn get_status(
 			show_untracked.recurse_untracked_dirs(),
 		);
 
+	if detect_renames {
+		options
+			.include_untracked(true)
+			.recurse_untracked_dirs(true)
+			.renames_index_to_workdir(true);
+	}
+	if let Some(threshold) = rename_threshold {
+		options.rename_threshold(threshold);
+	}
+
 	let statuses = repo.statuses(Some(&mut options))?;
+	let excluded = sparse_checkout_excluded(&repo)?;
 
+	let mut res = to_status_items(&statuses, &excluded)?;
+
+	res.sort_by(|a, b| {
+		Path::new(a.path.as_str()).cmp(Path::new(b.path.as_str()))
+	});
+
+	Ok(res)
+}
+
+/// the path `p` was renamed from, like `get_status_with_renames`
+/// reports it in its `old_path`
+///
+/// Instead of the whole tree only `p` and the deleted files it can be
+/// paired with are looked at, untracked directories are not walked.
+pub(crate) fn renamed_from_path(
+	repo: &Repository,
+	p: &str,
+	status_type: StatusType,
+	rename_threshold: Option<u16>,
+) -> Result<Option<String>> {
+	scope_time!("renamed_from_path");
+
+	let deleted = if status_type == StatusType::Stage {
+		Status::INDEX_DELETED
+	} else {
+		Status::WT_DELETED
+	};
+
+	let excluded = sparse_checkout_excluded(repo)?;
+
+	let mut options = StatusOptions::default();
+	options.show(status_type.into()).include_untracked(false);
+
+	let candidates = repo
+		.statuses(Some(&mut options))?
+		.iter()
+		.filter(|e| {
+			e.status().intersects(deleted)
+				&& !is_sparse_excluded(
+					&excluded,
+					e.status(),
+					e.path(),
+				)
+		})
+		.filter_map(|e| e.path().map(String::from))
+		.collect::<Vec<_>>();
+
+	if candidates.is_empty() {
+		return Ok(None);
+	}
+
+	let mut options = StatusOptions::default();
+	options
+		.show(status_type.into())
+		.include_untracked(true)
+		.recurse_untracked_dirs(true)
+		.renames_head_to_index(true)
+		.renames_index_to_workdir(status_type != StatusType::Stage)
+		.disable_pathspec_match(true)
+		.pathspec(p);
+	for candidate in candidates {
+		options.pathspec(candidate);
+	}
+	if let Some(threshold) = rename_threshold {
+		options.rename_threshold(threshold);
+	}
+
+	let statuses = repo.statuses(Some(&mut options))?;
+
+	Ok(to_status_items(&statuses, &excluded)?
+		.into_iter()
+		.find(|item| item.path == p)
+		.and_then(|item| item.old_path))
+}
+
+fn to_status_items(
+	statuses: &Statuses,
+	excluded: &HashSet<String>,
+) -> Result<Vec<StatusItem>> {
 	let mut res = Vec::with_capacity(statuses.len());
 
 	for e in statuses.iter() {
 		let status: Status = e.status();
 
-		let path = match e.head_to_index() {
+		if is_sparse_excluded(excluded, status, e.path()) {
+			continue;
+		}
+
+		// a workdir rename is the only change of such a path
+		let diff = e.head_to_index().or_else(|| {
+			e.index_to_workdir()
+				.filter(|_| status.contains(Status::WT_RENAMED))
+		});
+
+		let path = match diff.as_ref() {
 			Some(diff) => diff
 				.new_file()
 				.path()
@@ -185,13 +375,220 @@ pub fn get_status(
 
 		res.push(StatusItem {
 			path,
+			old_path: diff.as_ref().and_then(renamed_from),
 			status: StatusItemType::from(status),
 		});
 	}
 
-	res.sort_by(|a, b| {
-		Path::new(a.path.as_str()).cmp(Path::new(b.path.as_str()))
-	});
-
 	Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		diff::get_diff,
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+	use std::process::Command;
+
+	#[test]
+	fn test_sparse_checkout_excluded() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		std::fs::create_dir(root.join("dir")).unwrap();
+		write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "dir/b.txt", "b", "c2");
+
+		let output = Command::new("git")
+			.args(["sparse-checkout", "set", "--no-cone", "/a.txt"])
+			.current_dir(root)
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+		assert!(!root.join("dir/b.txt").exists());
+
+		assert!(get_status(repo_path, StatusType::WorkingDir, None)
+			.unwrap()
+			.is_empty());
+		assert!(is_workdir_clean(repo_path, None).unwrap());
+		assert!(get_diff(repo_path, "dir/b.txt", false, None)
+			.unwrap()
+			.hunks
+			.is_empty());
+
+		repo_write_file(&repo, "a.txt", "changed").unwrap();
+
+		let status =
+			get_status(repo_path, StatusType::WorkingDir, None)
+				.unwrap();
+		assert_eq!(status.len(), 1);
+		assert_eq!(status[0].path, "a.txt");
+		assert_eq!(status[0].status, StatusItemType::Modified);
+	}
+
+	#[test]
+	fn test_status_without_untracked() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "tracked.txt", "a", "c1");
+		repo_write_file(&repo, "tracked.txt", "b")?;
+		std::fs::create_dir(root.join("build"))?;
+		repo_write_file(&repo, "build/out.o", "o")?;
+		repo_write_file(&repo, "new.txt", "n")?;
+
+		let paths = |show_untracked| {
+			get_status(
+				repo_path,
+				StatusType::WorkingDir,
+				Some(show_untracked),
+			)
+			.map(|items| {
+				items.into_iter().map(|i| i.path).collect::<Vec<_>>()
+			})
+		};
+
+		assert_eq!(
+			paths(ShowUntrackedFilesConfig::All)?,
+			vec!["build/out.o", "new.txt", "tracked.txt"]
+		);
+		assert_eq!(
+			paths(ShowUntrackedFilesConfig::No)?,
+			vec!["tracked.txt"]
+		);
+		assert_eq!(
+			get_status(
+				repo_path,
+				StatusType::WorkingDir,
+				Some(ShowUntrackedFilesConfig::No)
+			)?[0]
+				.status,
+			StatusItemType::Modified
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_status_detect_renames() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = "line\n".repeat(20);
+		write_commit_file(&repo, "staged.txt", &content, "c1");
+		write_commit_file(&repo, "workdir.txt", &content, "c2");
+
+		std::fs::rename(root.join("staged.txt"), root.join("s.txt"))?;
+		crate::sync::stage_add_all(repo_path, "*", None)?;
+		std::fs::rename(
+			root.join("workdir.txt"),
+			root.join("w.txt"),
+		)?;
+
+		let stage = get_status_with_renames(
+			repo_path,
+			StatusType::Stage,
+			None,
+			true,
+			None,
+		)?;
+		assert_eq!(stage.len(), 1);
+		assert_eq!(stage[0].path, "s.txt");
+		assert_eq!(stage[0].old_path.as_deref(), Some("staged.txt"));
+		assert_eq!(stage[0].status, StatusItemType::Renamed);
+
+		let workdir = get_status_with_renames(
+			repo_path,
+			StatusType::WorkingDir,
+			None,
+			true,
+			None,
+		)?;
+		assert_eq!(workdir.len(), 1);
+		assert_eq!(workdir[0].path, "w.txt");
+		assert_eq!(
+			workdir[0].old_path.as_deref(),
+			Some("workdir.txt")
+		);
+		assert_eq!(workdir[0].status, StatusItemType::Renamed);
+
+		let plain =
+			get_status(repo_path, StatusType::WorkingDir, None)?;
+		assert_eq!(plain.len(), 2);
+		assert!(plain.iter().all(|item| item.old_path.is_none()));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_renamed_from_path() -> Result<()> {
+		let (_td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let content = "line\n".repeat(20);
+		write_commit_file(&repo, "staged.txt", &content, "c1");
+		write_commit_file(&repo, "workdir.txt", &content, "c2");
+
+		assert_eq!(
+			renamed_from_path(
+				&repo,
+				"workdir.txt",
+				StatusType::WorkingDir,
+				None
+			)?,
+			None
+		);
+
+		std::fs::rename(root.join("staged.txt"), root.join("s.txt"))?;
+		crate::sync::stage_add_all(repo_path, "*", None)?;
+		std::fs::create_dir_all(root.join("untracked/deep"))?;
+		std::fs::rename(
+			root.join("workdir.txt"),
+			root.join("untracked/deep/w.txt"),
+		)?;
+		repo_write_file(&repo, "other.txt", "other")?;
+
+		assert_eq!(
+			renamed_from_path(
+				&repo,
+				"s.txt",
+				StatusType::Stage,
+				None
+			)?
+			.as_deref(),
+			Some("staged.txt")
+		);
+		assert_eq!(
+			renamed_from_path(
+				&repo,
+				"untracked/deep/w.txt",
+				StatusType::WorkingDir,
+				None
+			)?
+			.as_deref(),
+			Some("workdir.txt")
+		);
+		assert_eq!(
+			renamed_from_path(
+				&repo,
+				"other.txt",
+				StatusType::WorkingDir,
+				None
+			)?,
+			None
+		);
+
+		Ok(())
+	}
+}
@@ -11,6 +11,37 @@ use crate::{error::Result, sync::utils::work_dir, Error};
 
 pub use git2::SubmoduleStatus;
 
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmoduleState {
+	/// not cloned into the workdir yet
+	Uninitialized,
+	/// checked out commit differs from the one recorded in the parent
+	OutOfDate,
+	/// has uncommitted or untracked changes
+	Modified,
+	///
+	UpToDate,
+}
+
+impl From<SubmoduleStatus> for SubmoduleState {
+	fn from(status: SubmoduleStatus) -> Self {
+		if status.is_wd_uninitialized() {
+			Self::Uninitialized
+		} else if status.is_wd_modified() {
+			Self::OutOfDate
+		} else if status.intersects(
+			SubmoduleStatus::WD_INDEX_MODIFIED
+				| SubmoduleStatus::WD_WD_MODIFIED
+				| SubmoduleStatus::WD_UNTRACKED,
+		) {
+			Self::Modified
+		} else {
+			Self::UpToDate
+		}
+	}
+}
+
 ///
 #[derive(Debug)]
 pub struct SubmoduleInfo {
@@ -40,6 +71,11 @@ pub struct SubmoduleParentInfo {
 }
 
 impl SubmoduleInfo {
+	///
+	pub fn state(&self) -> SubmoduleState {
+		self.status.into()
+	}
+
 	///
 	pub fn get_repo_path(
 		&self,
@@ -87,10 +123,14 @@ pub fn get_submodules(
 	Ok(res)
 }
 
+/// updates submodule `name` to the commit recorded in the parent,
+/// with `init` uninitialized submodules are cloned first
 ///
+/// Nested submodules are updated recursively.
 pub fn update_submodule(
 	repo_path: &RepoPath,
 	name: &str,
+	init: bool,
 ) -> Result<()> {
 	scope_time!("update_submodule");
 
@@ -98,10 +138,23 @@ pub fn update_submodule(
 
 	let mut submodule = repo.find_submodule(name)?;
 
+	update_recursive(&mut submodule, init)
+}
+
+fn update_recursive(
+	submodule: &mut Submodule,
+	init: bool,
+) -> Result<()> {
 	let mut options = SubmoduleUpdateOptions::new();
 	options.allow_fetch(true);
 
-	submodule.update(true, Some(&mut options))?;
+	submodule.update(init, Some(&mut options))?;
+
+	if let Ok(sub_repo) = submodule.open() {
+		for mut nested in sub_repo.submodules()? {
+			update_recursive(&mut nested, init)?;
+		}
+	}
 
 	Ok(())
 }
@@ -155,9 +208,12 @@ pub fn submodule_parent_info(
 
 #[cfg(test)]
 mod tests {
-	use super::get_submodules;
+	use super::{get_submodules, update_submodule, SubmoduleState};
 	use crate::sync::{
-		submodules::submodule_parent_info, tests::repo_init, RepoPath,
+		submodules::submodule_parent_info,
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+		RepoPath,
 	};
 	use git2::Repository;
 	use pretty_assertions::assert_eq;
@@ -198,4 +254,65 @@ mod tests {
 
 		assert_eq!(&info.submodule_info.name, "foo/bar");
 	}
+
+	#[test]
+	fn test_update_status_transitions() {
+		let (sub_dir, sub_repo) = repo_init().unwrap();
+		write_commit_file(&sub_repo, "sub.txt", "sub", "sub c1");
+
+		let (dir, r) = repo_init().unwrap();
+		{
+			let mut s = r
+				.submodule(
+					sub_dir.path().to_str().unwrap(),
+					Path::new("sub"),
+					false,
+				)
+				.unwrap();
+			s.clone(None).unwrap();
+			s.add_finalize().unwrap();
+		}
+		write_commit_file(&r, "parent.txt", "p", "add submodule");
+
+		// a fresh clone does not initialize submodules
+		let clone_dir = tempfile::TempDir::new().unwrap();
+		Repository::clone(
+			dir.path().to_str().unwrap(),
+			clone_dir.path(),
+		)
+		.unwrap();
+		let repo_p: RepoPath =
+			clone_dir.path().to_str().unwrap().into();
+
+		let subs = get_submodules(&repo_p).unwrap();
+		assert_eq!(subs.len(), 1);
+		assert_eq!(subs[0].state(), SubmoduleState::Uninitialized);
+
+		update_submodule(&repo_p, "sub", true).unwrap();
+
+		let subs = get_submodules(&repo_p).unwrap();
+		assert_eq!(subs[0].state(), SubmoduleState::UpToDate);
+
+		let cloned_sub =
+			Repository::open(clone_dir.path().join("sub")).unwrap();
+		{
+			let mut config = cloned_sub.config().unwrap();
+			config.set_str("user.name", "name").unwrap();
+			config.set_str("user.email", "email").unwrap();
+		}
+		repo_write_file(&cloned_sub, "sub.txt", "dirty").unwrap();
+
+		let subs = get_submodules(&repo_p).unwrap();
+		assert_eq!(subs[0].state(), SubmoduleState::Modified);
+
+		write_commit_file(&cloned_sub, "sub.txt", "newer", "sub c2");
+
+		let subs = get_submodules(&repo_p).unwrap();
+		assert_eq!(subs[0].state(), SubmoduleState::OutOfDate);
+
+		update_submodule(&repo_p, "sub", false).unwrap();
+
+		let subs = get_submodules(&repo_p).unwrap();
+		assert_eq!(subs[0].state(), SubmoduleState::UpToDate);
+	}
 }
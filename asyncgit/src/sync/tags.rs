@@ -51,6 +51,21 @@ pub struct TagWithMetadata {
 	pub annotation: Option<String>,
 }
 
+///
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TagDetail {
+	/// tag name
+	pub name: String,
+	/// commit the tag points to
+	pub target: CommitId,
+	/// `false` for lightweight tags
+	pub annotated: bool,
+	/// annotation message, `None` if empty or lightweight
+	pub message: Option<String>,
+	/// tagger time in seconds since epoch, `None` if lightweight
+	pub tagger_time: Option<i64>,
+}
+
 static MAX_MESSAGE_WIDTH: usize = 100;
 
 /// returns `Tags` type filled with all tags found in repo
@@ -176,6 +191,60 @@ pub fn get_tags_with_metadata(
 	Ok(tags)
 }
 
+/// returns all tags pointing to commits sorted by name
+pub fn get_tags_detailed(
+	repo_path: &RepoPath,
+) -> Result<Vec<TagDetail>> {
+	scope_time!("get_tags_detailed");
+
+	let repo = repo(repo_path)?;
+
+	let mut tags = Vec::new();
+	repo.tag_foreach(|id, name| {
+		// skip the `refs/tags/` part
+		let Ok(name) = String::from_utf8(name[10..].into()) else {
+			return true;
+		};
+
+		let detail = if let Ok(tag) = repo.find_tag(id) {
+			tag.target()
+				.and_then(|target| target.peel_to_commit())
+				.map(|commit| TagDetail {
+					name,
+					target: CommitId::new(commit.id()),
+					annotated: true,
+					message: tag.message_bytes().and_then(|msg| {
+						msg.is_empty()
+							.not()
+							.then(|| bytes2string(msg).ok())
+							.flatten()
+					}),
+					tagger_time: tag
+						.tagger()
+						.map(|tagger| tagger.when().seconds()),
+				})
+		} else {
+			repo.find_commit(id).map(|commit| TagDetail {
+				name,
+				target: CommitId::new(commit.id()),
+				annotated: false,
+				message: None,
+				tagger_time: None,
+			})
+		};
+
+		if let Ok(detail) = detail {
+			tags.push(detail);
+		}
+
+		true
+	})?;
+
+	tags.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+	Ok(tags)
+}
+
 ///
 pub fn delete_tag(
 	repo_path: &RepoPath,
@@ -253,4 +322,44 @@ mod tests {
 
 		assert_eq!(tags.len(), 0);
 	}
+
+	#[test]
+	fn test_tags_detailed() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let sig = repo.signature().unwrap();
+		let head_id = repo.head().unwrap().target().unwrap();
+		let target = repo
+			.find_object(head_id, Some(ObjectType::Commit))
+			.unwrap();
+
+		repo.tag_lightweight("light", &target, false).unwrap();
+		repo.tag("annotated", &target, &sig, "release\n", false)
+			.unwrap();
+
+		let tags = get_tags_detailed(repo_path).unwrap();
+
+		assert_eq!(
+			tags,
+			vec![
+				TagDetail {
+					name: String::from("annotated"),
+					target: CommitId::new(head_id),
+					annotated: true,
+					message: Some(String::from("release\n")),
+					tagger_time: Some(sig.when().seconds()),
+				},
+				TagDetail {
+					name: String::from("light"),
+					target: CommitId::new(head_id),
+					annotated: false,
+					message: None,
+					tagger_time: None,
+				},
+			]
+		);
+	}
 }
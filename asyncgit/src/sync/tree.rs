@@ -91,6 +91,39 @@ pub fn tree_file_content(
 	Ok(content)
 }
 
+/// returns the content of `path` as of `commit`, see
+/// `content_is_binary` before rendering it as text
+pub fn get_file_at_commit(
+	repo_path: &RepoPath,
+	commit: CommitId,
+	path: &Path,
+) -> Result<Vec<u8>> {
+	scope_time!("get_file_at_commit");
+
+	let repo = repo(repo_path)?;
+
+	let tree = repo.find_commit(commit.into())?.tree()?;
+	let entry = tree.get_path(path).map_err(|_| {
+		Error::Generic(format!(
+			"'{}' does not exist in commit {}",
+			path.display(),
+			commit.get_short_string()
+		))
+	})?;
+
+	let blob = entry.to_object(&repo)?.into_blob().map_err(|_| {
+		Error::Generic(format!("'{}' is not a file", path.display()))
+	})?;
+
+	Ok(blob.content().to_vec())
+}
+
+/// same heuristic git uses: content containing a NUL byte within the
+/// first 8000 bytes is binary
+pub fn content_is_binary(content: &[u8]) -> bool {
+	content.iter().take(8000).any(|b| *b == 0)
+}
+
 ///
 fn tree_recurse(
 	repo: &Repository,
@@ -154,6 +187,44 @@ mod tests {
 		assert_ne!(files_c2[0], files[0]);
 	}
 
+	#[test]
+	fn test_file_at_commit() {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let c1 = write_commit_file(&repo, "test.txt", "old", "c1");
+		let c2 = write_commit_file(&repo, "test.txt", "new", "c2");
+		write_commit_file(&repo, "bin.dat", "a\0b", "c3");
+		let c3 =
+			CommitId::new(repo.head().unwrap().target().unwrap());
+
+		let old =
+			get_file_at_commit(repo_path, c1, Path::new("test.txt"))
+				.unwrap();
+		assert_eq!(old, b"old");
+		assert!(!content_is_binary(&old));
+
+		assert_eq!(
+			get_file_at_commit(repo_path, c2, Path::new("test.txt"))
+				.unwrap(),
+			b"new"
+		);
+
+		assert!(content_is_binary(
+			&get_file_at_commit(repo_path, c3, Path::new("bin.dat"))
+				.unwrap()
+		));
+
+		assert!(get_file_at_commit(
+			repo_path,
+			c2,
+			Path::new("bin.dat")
+		)
+		.is_err());
+	}
+
 	#[test]
 	fn test_sorting() {
 		let mut list = vec!["file", "folder/file", "folder/afile"]
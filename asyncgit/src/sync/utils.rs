@@ -10,11 +10,15 @@ use crate::{
 use git2::{IndexAddOption, Repository, RepositoryOpenFlags};
 use scopetime::scope_time;
 use std::{
+	collections::BTreeSet,
 	fs::File,
 	io::Write,
 	path::{Path, PathBuf},
 };
 
+/// stage bits of `IndexEntry::flags`, zero for non conflict entries
+pub(crate) const INDEX_ENTRY_STAGE_MASK: u16 = 0x3000;
+
 ///
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Head {
@@ -136,6 +140,44 @@ pub fn stage_add_all(
 	Ok(())
 }
 
+/// stages every change in the working tree including untracked and
+/// deleted files but no ignored ones, writing the index only once
+pub fn stage_all(repo_path: &RepoPath) -> Result<()> {
+	scope_time!("stage_all");
+
+	let repo = repo(repo_path)?;
+
+	let mut index = repo.index()?;
+
+	index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+	// `add_all` does not pick up deletions
+	index.update_all(["*"], None)?;
+
+	index.write()?;
+
+	Ok(())
+}
+
+/// resets the whole index to `HEAD` (or empties it on an unborn
+/// branch), the working tree stays untouched
+pub fn unstage_all(repo_path: &RepoPath) -> Result<()> {
+	scope_time!("unstage_all");
+
+	let repo = repo(repo_path)?;
+
+	let mut index = repo.index()?;
+
+	if let Ok(head) = repo.head() {
+		index.read_tree(&head.peel_to_tree()?)?;
+	} else {
+		index.clear()?;
+	}
+
+	index.write()?;
+
+	Ok(())
+}
+
 /// Undo last commit in repo
 pub fn undo_last_commit(repo_path: &RepoPath) -> Result<()> {
 	let repo = repo(repo_path)?;
@@ -187,6 +229,25 @@ pub(crate) fn repo_write_file(
 	Ok(())
 }
 
+/// paths with conflicts in the index, sorted
+pub(crate) fn conflicted_paths(
+	repo: &Repository,
+) -> Result<Vec<String>> {
+	let mut paths = BTreeSet::new();
+
+	for conflict in repo.index()?.conflicts()? {
+		let conflict = conflict?;
+
+		if let Some(entry) =
+			conflict.our.or(conflict.their).or(conflict.ancestor)
+		{
+			paths.insert(String::from_utf8(entry.path)?);
+		}
+	}
+
+	Ok(paths.into_iter().collect())
+}
+
 ///
 pub fn read_file(path: &Path) -> Result<String> {
 	use std::io::Read;
@@ -388,7 +449,7 @@ mod tests {
 
 		stage_add_file(repo_path, file_path).unwrap();
 
-		commit(repo_path, "commit msg").unwrap();
+		commit(repo_path, "commit msg", false).unwrap();
 
 		// delete the file now
 		assert_eq!(remove_file(full_path).is_ok(), true);
@@ -402,6 +463,91 @@ mod tests {
 		assert_eq!(status_count(StatusType::Stage), 1);
 	}
 
+	#[test]
+	fn test_stage_all() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		for i in 0..100 {
+			write_commit_file(
+				&repo,
+				&format!("file{i}.txt"),
+				"content",
+				"c1",
+			);
+		}
+
+		for i in 0..100 {
+			repo_write_file(
+				&repo,
+				&format!("file{i}.txt"),
+				"changed",
+			)?;
+		}
+		remove_file(root.join("file0.txt"))?;
+		fs::create_dir(root.join("new"))?;
+		repo_write_file(&repo, "new/file.txt", "new")?;
+		repo_write_file(&repo, ".gitignore", "*.log\n")?;
+		repo_write_file(&repo, "ignored.log", "ignored")?;
+
+		assert_eq!(get_statuses(repo_path), (102, 0));
+
+		stage_all(repo_path)?;
+
+		assert_eq!(get_statuses(repo_path), (0, 102));
+		assert!(repo
+			.index()?
+			.get_path(Path::new("ignored.log"), 0)
+			.is_none());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unstage_all() -> Result<()> {
+		let (_td, repo) = repo_init().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "a.txt", "a", "c1");
+		write_commit_file(&repo, "b.txt", "b", "c2");
+
+		repo_write_file(&repo, "a.txt", "a2")?;
+		remove_file(root.join("b.txt"))?;
+		repo_write_file(&repo, "c.txt", "c")?;
+
+		stage_all(repo_path)?;
+		assert_eq!(get_statuses(repo_path), (0, 3));
+
+		unstage_all(repo_path)?;
+
+		assert_eq!(get_statuses(repo_path), (3, 0));
+		assert_eq!(repo_read_file(&repo, "a.txt")?, "a2");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unstage_all_unborn() -> Result<()> {
+		let (_td, repo) = repo_init_empty().unwrap();
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		repo_write_file(&repo, "a.txt", "a")?;
+
+		stage_all(repo_path)?;
+		assert_eq!(get_statuses(repo_path), (0, 1));
+
+		unstage_all(repo_path)?;
+		assert_eq!(get_statuses(repo_path), (1, 0));
+
+		Ok(())
+	}
+
 	// see https://github.com/extrawurst/gitui/issues/108
 	#[test]
 	fn test_staging_sub_git_folder() -> Result<()> {
@@ -0,0 +1,162 @@
+//! word level diff of a single pair of lines
+
+use std::ops::Range;
+
+/// lines with more `old * new` tokens than this are not compared
+/// token by token but considered changed as a whole
+const MAX_TOKEN_PRODUCT: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+	Word,
+	Whitespace,
+	Other,
+}
+
+impl From<char> for CharClass {
+	fn from(c: char) -> Self {
+		if c.is_alphanumeric() || c == '_' {
+			Self::Word
+		} else if c.is_whitespace() {
+			Self::Whitespace
+		} else {
+			Self::Other
+		}
+	}
+}
+
+/// splits a line into words, runs of whitespace and single other chars
+fn tokenize(line: &str) -> Vec<Range<usize>> {
+	let mut tokens = Vec::new();
+	let mut current: Option<(usize, CharClass)> = None;
+
+	for (idx, c) in line.char_indices() {
+		let class = CharClass::from(c);
+
+		match current {
+			Some((_, current_class))
+				if current_class == class
+					&& class != CharClass::Other => {}
+			Some((start, _)) => {
+				tokens.push(start..idx);
+				current = Some((idx, class));
+			}
+			None => current = Some((idx, class)),
+		}
+	}
+
+	if let Some((start, _)) = current {
+		tokens.push(start..line.len());
+	}
+
+	tokens
+}
+
+/// adds `range` to `ranges` merging it into the last one if adjacent
+fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+	if let Some(last) = ranges.last_mut() {
+		if last.end == range.start {
+			last.end = range.end;
+			return;
+		}
+	}
+
+	ranges.push(range);
+}
+
+fn whole_line(line: &str) -> Vec<Range<usize>> {
+	let mut ranges = Vec::new();
+
+	if !line.is_empty() {
+		ranges.push(0..line.len());
+	}
+
+	ranges
+}
+
+/// returns the byte ranges of `old` (removed) and `new` (added) that are
+/// not part of the longest common subsequence of their tokens
+pub fn word_diff(
+	old: &str,
+	new: &str,
+) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+	let old_tokens = tokenize(old);
+	let new_tokens = tokenize(new);
+
+	let (n, m) = (old_tokens.len(), new_tokens.len());
+
+	if n.saturating_mul(m) > MAX_TOKEN_PRODUCT {
+		return (whole_line(old), whole_line(new));
+	}
+
+	let equal = |i: usize, j: usize| {
+		old[old_tokens[i].clone()] == new[new_tokens[j].clone()]
+	};
+
+	// `lcs[i * (m + 1) + j]`: length of the longest common subsequence
+	// of `old_tokens[i..]` and `new_tokens[j..]`
+	let mut lcs = vec![0_usize; (n + 1) * (m + 1)];
+	for i in (0..n).rev() {
+		for j in (0..m).rev() {
+			lcs[i * (m + 1) + j] = if equal(i, j) {
+				lcs[(i + 1) * (m + 1) + j + 1] + 1
+			} else {
+				lcs[(i + 1) * (m + 1) + j]
+					.max(lcs[i * (m + 1) + j + 1])
+			};
+		}
+	}
+
+	let mut removed = Vec::new();
+	let mut added = Vec::new();
+	let (mut i, mut j) = (0, 0);
+
+	while i < n || j < m {
+		if i < n && j < m && equal(i, j) {
+			i += 1;
+			j += 1;
+		} else if j == m
+			|| (i < n
+				&& lcs[(i + 1) * (m + 1) + j]
+					>= lcs[i * (m + 1) + j + 1])
+		{
+			push_range(&mut removed, old_tokens[i].clone());
+			i += 1;
+		} else {
+			push_range(&mut added, new_tokens[j].clone());
+			j += 1;
+		}
+	}
+
+	(removed, added)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::word_diff;
+
+	#[test]
+	fn test_word_diff_changed_word() {
+		let (removed, added) =
+			word_diff("foo bar baz", "foo qux baz");
+
+		assert_eq!(removed, vec![4..7]);
+		assert_eq!(added, vec![4..7]);
+	}
+
+	#[test]
+	fn test_word_diff_inserted_words() {
+		let (removed, added) = word_diff("foo baz", "foo bar, baz");
+
+		assert!(removed.is_empty());
+		assert_eq!(added, vec![4..9]);
+	}
+
+	#[test]
+	fn test_word_diff_empty_line() {
+		let (removed, added) = word_diff("", "foo");
+
+		assert!(removed.is_empty());
+		assert_eq!(added, vec![0..3]);
+	}
+}
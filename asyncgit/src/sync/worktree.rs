@@ -0,0 +1,239 @@
+//! sync git api for working with worktrees
+
+use super::{repository::repo, RepoPath};
+use crate::error::{Error, Result};
+use git2::{
+	BranchType, Repository, StatusOptions, Worktree,
+	WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions,
+};
+use scopetime::scope_time;
+use std::path::{Path, PathBuf};
+
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorktreeInfo {
+	///
+	pub name: String,
+	///
+	pub path: PathBuf,
+	/// checked out branch, `None` if detached or not available
+	pub branch: Option<String>,
+	///
+	pub is_locked: bool,
+	/// the worktree directory is gone and `git worktree prune`
+	/// would remove it
+	pub is_prunable: bool,
+}
+
+fn worktree_to_info(worktree: &Worktree) -> WorktreeInfo {
+	let branch = Repository::open_from_worktree(worktree)
+		.ok()
+		.and_then(|repo| {
+			let head = repo.head().ok()?;
+
+			if head.is_branch() {
+				head.shorthand().map(String::from)
+			} else {
+				None
+			}
+		});
+
+	WorktreeInfo {
+		name: worktree.name().unwrap_or_default().into(),
+		path: worktree.path().to_path_buf(),
+		branch,
+		is_locked: matches!(
+			worktree.is_locked(),
+			Ok(WorktreeLockStatus::Locked(_))
+		),
+		is_prunable: worktree.is_prunable(None).unwrap_or_default(),
+	}
+}
+
+/// lists the linked worktrees of the repository
+pub fn list_worktrees(
+	repo_path: &RepoPath,
+) -> Result<Vec<WorktreeInfo>> {
+	scope_time!("list_worktrees");
+
+	let repo = repo(repo_path)?;
+
+	let mut infos = Vec::new();
+	for name in repo.worktrees()?.iter().flatten() {
+		infos.push(worktree_to_info(&repo.find_worktree(name)?));
+	}
+
+	Ok(infos)
+}
+
+/// adds a worktree `name` at `path` checking out `branch`
+///
+/// `branch` is created at `HEAD` if it does not exist yet, without a
+/// `branch` a new branch called `name` is created like `git worktree
+/// add` does.
+pub fn add_worktree(
+	repo_path: &RepoPath,
+	name: &str,
+	path: &Path,
+	branch: Option<&str>,
+) -> Result<WorktreeInfo> {
+	scope_time!("add_worktree");
+
+	let repo = repo(repo_path)?;
+
+	let mut options = WorktreeAddOptions::new();
+
+	let reference = if let Some(branch) = branch {
+		let branch = if let Ok(existing) =
+			repo.find_branch(branch, BranchType::Local)
+		{
+			existing
+		} else {
+			let head = repo.head()?.peel_to_commit()?;
+			repo.branch(branch, &head, false)?
+		};
+		Some(branch.into_reference())
+	} else {
+		None
+	};
+	options.reference(reference.as_ref());
+
+	let worktree = repo.worktree(name, path, Some(&options))?;
+
+	Ok(worktree_to_info(&worktree))
+}
+
+/// removes the worktree `name` including its directory
+///
+/// Without `force` this fails if the worktree is locked or has
+/// uncommitted changes.
+pub fn remove_worktree(
+	repo_path: &RepoPath,
+	name: &str,
+	force: bool,
+) -> Result<()> {
+	scope_time!("remove_worktree");
+
+	let repo = repo(repo_path)?;
+	let worktree = repo.find_worktree(name)?;
+
+	if !force {
+		if let WorktreeLockStatus::Locked(_) = worktree.is_locked()? {
+			return Err(Error::Generic(format!(
+				"worktree '{name}' is locked"
+			)));
+		}
+
+		if worktree.validate().is_ok() {
+			let worktree_repo =
+				Repository::open_from_worktree(&worktree)?;
+
+			let mut options = StatusOptions::new();
+			options.include_untracked(true);
+
+			if !worktree_repo.statuses(Some(&mut options))?.is_empty()
+			{
+				return Err(Error::UncommittedChanges);
+			}
+		}
+	}
+
+	let mut options = WorktreePruneOptions::new();
+	options.valid(true).locked(force).working_tree(true);
+
+	worktree.prune(Some(&mut options))?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sync::{
+		tests::{repo_init, write_commit_file},
+		utils::repo_write_file,
+	};
+
+	#[test]
+	fn test_add_list_remove() -> Result<()> {
+		let (td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		assert!(list_worktrees(repo_path)?.is_empty());
+
+		let path = td.path().join("wt");
+		let added =
+			add_worktree(repo_path, "wt", &path, Some("feature"))?;
+
+		assert_eq!(added.branch.as_deref(), Some("feature"));
+		assert!(path.exists());
+
+		let list = list_worktrees(repo_path)?;
+
+		assert_eq!(list.len(), 1);
+		assert_eq!(list[0].name, "wt");
+		assert_eq!(list[0].branch.as_deref(), Some("feature"));
+		assert!(!list[0].is_locked);
+		assert!(!list[0].is_prunable);
+		assert!(repo
+			.find_branch("feature", BranchType::Local)
+			.is_ok());
+
+		remove_worktree(repo_path, "wt", false)?;
+
+		assert!(list_worktrees(repo_path)?.is_empty());
+		assert!(!path.exists());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_add_default_branch() -> Result<()> {
+		let (td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		let added = add_worktree(
+			repo_path,
+			"other",
+			&td.path().join("o"),
+			None,
+		)?;
+
+		assert_eq!(added.branch.as_deref(), Some("other"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_remove_dirty_needs_force() -> Result<()> {
+		let (td, repo) = repo_init()?;
+		let root = repo.path().parent().unwrap();
+		let repo_path: &RepoPath =
+			&root.as_os_str().to_str().unwrap().into();
+
+		write_commit_file(&repo, "foo.txt", "foo", "c1");
+
+		let path = td.path().join("wt");
+		add_worktree(repo_path, "wt", &path, None)?;
+
+		let worktree_repo = Repository::open(&path)?;
+		repo_write_file(&worktree_repo, "foo.txt", "changed")?;
+
+		assert!(matches!(
+			remove_worktree(repo_path, "wt", false),
+			Err(Error::UncommittedChanges)
+		));
+		assert_eq!(list_worktrees(repo_path)?.len(), 1);
+
+		remove_worktree(repo_path, "wt", true)?;
+
+		assert!(list_worktrees(repo_path)?.is_empty());
+		assert!(!path.exists());
+
+		Ok(())
+	}
+}
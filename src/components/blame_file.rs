@@ -14,7 +14,7 @@ use crate::{
 use anyhow::Result;
 use asyncgit::{
 	sync::{BlameHunk, CommitId, FileBlame, RepoPathRef},
-	AsyncBlame, AsyncGitNotification, BlameParams,
+	AsyncBlame, AsyncGitNotification, BlameParams, ProgressPercent,
 };
 use crossbeam_channel::Sender;
 use crossterm::event::Event;
@@ -311,6 +311,9 @@ impl BlameFileComponent {
 				));
 			}
 		} else {
+			if let Err(e) = self.async_blame.cancel() {
+				log::error!("cancel blame failed: {}", e);
+			}
 			self.queue.push(InternalEvent::PopupStackPop);
 		}
 	}
@@ -379,9 +382,26 @@ impl BlameFileComponent {
 			self.file_blame.as_ref(),
 		) {
 			(true, Some(params), _) => {
+				let progress = self
+					.async_blame
+					.progress()
+					.ok()
+					.flatten()
+					.map(|progress| {
+						format!(
+							" {}%",
+							ProgressPercent::new(
+								progress.lines,
+								progress.total_lines
+							)
+							.progress
+						)
+					})
+					.unwrap_or_default();
+
 				format!(
-					"{} -- {} -- <calculating.. (who is to blame?)>",
-					self.title, params.file_path
+					"{} -- {} -- <calculating.. (who is to blame?)>{}",
+					self.title, params.file_path, progress
 				)
 			}
 			(false, Some(params), Some(file_blame)) => {
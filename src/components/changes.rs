@@ -151,7 +151,7 @@ impl ChangesComponent {
 	}
 
 	fn stage_remove_all(&mut self) -> Result<()> {
-		sync::reset_stage(&self.repo.borrow(), "*")?;
+		sync::unstage_all(&self.repo.borrow())?;
 
 		self.queue.push(InternalEvent::Update(NeedsUpdate::ALL));
 
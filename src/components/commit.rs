@@ -28,10 +28,8 @@ use ratatui::{
 	Frame,
 };
 use std::{
-	fs::{read_to_string, File},
+	fs::File,
 	io::{Read, Write},
-	path::PathBuf,
-	str::FromStr,
 };
 
 enum CommitResult {
@@ -288,9 +286,11 @@ impl CommitComponent {
 
 	fn do_commit(&self, msg: &str) -> Result<()> {
 		match &self.mode {
-			Mode::Normal => sync::commit(&self.repo.borrow(), msg)?,
+			Mode::Normal => {
+				sync::commit(&self.repo.borrow(), msg, false)?
+			}
 			Mode::Amend(amend) => {
-				sync::amend(&self.repo.borrow(), *amend, msg)?
+				sync::amend(&self.repo.borrow(), *amend, msg, false)?
 			}
 			Mode::Merge(ids) => {
 				sync::merge_commit(&self.repo.borrow(), msg, ids)?
@@ -366,80 +366,76 @@ impl CommitComponent {
 
 		let repo_state = sync::repo_state(&self.repo.borrow())?;
 
-		self.mode = if repo_state != RepoState::Clean
-			&& reword.is_some()
-		{
-			bail!("cannot reword while repo is not in a clean state");
-		} else if let Some(reword_id) = reword {
-			self.input.set_text(
-				sync::get_commit_details(
-					&self.repo.borrow(),
-					reword_id,
-				)?
-				.message
-				.unwrap_or_default()
-				.combine(),
-			);
-			self.input.set_title(strings::commit_reword_title());
-			Mode::Reword(reword_id)
-		} else {
-			match repo_state {
-				RepoState::Merge => {
-					let ids =
-						sync::mergehead_ids(&self.repo.borrow())?;
-					self.input
-						.set_title(strings::commit_title_merge());
-					self.input.set_text(sync::merge_msg(
-						&self.repo.borrow(),
-					)?);
-					Mode::Merge(ids)
-				}
-				RepoState::Revert => {
-					self.input
-						.set_title(strings::commit_title_revert());
-					self.input.set_text(sync::merge_msg(
+		self.mode =
+			if repo_state != RepoState::Clean && reword.is_some() {
+				bail!("cannot reword while repo is not in a clean state");
+			} else if let Some(reword_id) = reword {
+				self.input.set_text(
+					sync::get_commit_details(
 						&self.repo.borrow(),
-					)?);
-					Mode::Revert
-				}
+						reword_id,
+					)?
+					.message
+					.unwrap_or_default()
+					.combine(),
+				);
+				self.input.set_title(strings::commit_reword_title());
+				Mode::Reword(reword_id)
+			} else {
+				match repo_state {
+					RepoState::Merge => {
+						let ids =
+							sync::mergehead_ids(&self.repo.borrow())?;
+						self.input
+							.set_title(strings::commit_title_merge());
+						self.input.set_text(sync::merge_msg(
+							&self.repo.borrow(),
+						)?);
+						Mode::Merge(ids)
+					}
+					RepoState::Revert => {
+						self.input
+							.set_title(strings::commit_title_revert());
+						self.input.set_text(sync::merge_msg(
+							&self.repo.borrow(),
+						)?);
+						Mode::Revert
+					}
 
-				_ => {
-					self.commit_template = get_config_string(
-						&self.repo.borrow(),
-						"commit.template",
-					)
-					.map_err(|e| {
-						log::error!("load git-config failed: {}", e);
-						e
-					})
-					.ok()
-					.flatten()
-					.and_then(|path| {
-						shellexpand::full(path.as_str())
-							.ok()
-							.and_then(|path| {
-								PathBuf::from_str(path.as_ref()).ok()
-							})
-					})
-					.and_then(|path| {
-						read_to_string(&path)
+					_ => {
+						self.commit_template =
+							sync::load_commit_template(
+								&self.repo.borrow(),
+							)
 							.map_err(|e| {
-								log::error!("read commit.template failed: {e} (path: '{:?}')",path);
+								log::error!(
+									"load commit.template failed: {}",
+									e
+								);
 								e
 							})
 							.ok()
-					});
-
-					if self.is_empty() {
-						if let Some(s) = &self.commit_template {
-							self.input.set_text(s.clone());
+							.flatten()
+							.map(|template| {
+								sync::expand_commit_template(
+									&template,
+									&self
+										.git_branch_name
+										.lookup()
+										.unwrap_or_default(),
+								)
+							});
+
+						if self.is_empty() {
+							if let Some(s) = &self.commit_template {
+								self.input.set_text(s.clone());
+							}
 						}
+						self.input.set_title(strings::commit_title());
+						Mode::Normal
 					}
-					self.input.set_title(strings::commit_title());
-					Mode::Normal
 				}
-			}
-		};
+			};
 
 		self.commit_msg_history_idx = 0;
 		self.input.show()?;
@@ -505,7 +505,11 @@ impl CommitList {
 			try_or_popup!(
 				self,
 				"failed to checkout commit:",
-				checkout_commit(&self.repo.borrow(), commit_hash)
+				checkout_commit(
+					&self.repo.borrow(),
+					commit_hash,
+					false
+				)
 			);
 		}
 	}
@@ -577,25 +577,47 @@ impl DiffComponent {
 	}
 
 	fn stage_lines(&self) {
-		if let Some(diff) = &self.diff {
-			//TODO: support untracked files aswell
-			if !diff.untracked {
-				let selected_lines = self.selected_lines();
-
-				try_or_popup!(
-					self,
-					"(un)stage lines:",
-					sync::stage_lines(
-						&self.repo.borrow(),
-						&self.current.path,
-						self.is_stage(),
-						&selected_lines,
-					)
-				);
+		if self.diff.is_some() {
+			let selected_lines = self.selected_lines();
 
-				self.queue_update();
+			try_or_popup!(
+				self,
+				"(un)stage lines:",
+				self.stage_lines_or_file(&selected_lines)
+			);
+
+			self.queue_update();
+		}
+	}
+
+	/// binary files cannot be (un)staged line by line,
+	/// those get (un)staged as a whole instead
+	fn stage_lines_or_file(
+		&self,
+		lines: &[DiffLinePosition],
+	) -> Result<()> {
+		let repo = self.repo.borrow();
+
+		match sync::stage_lines(
+			&repo,
+			&self.current.path,
+			self.is_stage(),
+			lines,
+		) {
+			Err(asyncgit::Error::BinaryFile) => {
+				if self.is_stage() {
+					sync::reset_stage(&repo, &self.current.path)?;
+				} else {
+					sync::stage_add_file(
+						&repo,
+						Path::new(&self.current.path),
+					)?;
+				}
 			}
+			res => res?,
 		}
+
+		Ok(())
 	}
 
 	fn selected_lines(&self) -> Vec<DiffLinePosition> {
@@ -689,6 +711,12 @@ impl DiffComponent {
 	const fn is_stage(&self) -> bool {
 		self.current.is_stage
 	}
+
+	/// hunks and lines of a diff ignoring whitespace can not be
+	/// mapped back to the file content
+	fn can_stage(&self) -> bool {
+		!self.options.borrow().diff_options().ignores_whitespace()
+	}
 }
 
 impl DrawableComponent for DiffComponent {
@@ -789,17 +817,17 @@ impl Component for DiffComponent {
 		if !self.is_immutable {
 			out.push(CommandInfo::new(
 				strings::commands::diff_hunk_remove(&self.key_config),
-				self.selected_hunk.is_some(),
+				self.selected_hunk.is_some() && self.can_stage(),
 				self.focused() && self.is_stage(),
 			));
 			out.push(CommandInfo::new(
 				strings::commands::diff_hunk_add(&self.key_config),
-				self.selected_hunk.is_some(),
+				self.selected_hunk.is_some() && self.can_stage(),
 				self.focused() && !self.is_stage(),
 			));
 			out.push(CommandInfo::new(
 				strings::commands::diff_hunk_revert(&self.key_config),
-				self.selected_hunk.is_some(),
+				self.selected_hunk.is_some() && self.can_stage(),
 				self.focused() && !self.is_stage(),
 			));
 			out.push(CommandInfo::new(
@@ -807,13 +835,13 @@ impl Component for DiffComponent {
 					&self.key_config,
 				),
 				//TODO: only if any modifications are selected
-				true,
+				self.can_stage(),
 				self.focused() && !self.is_stage(),
 			));
 			out.push(CommandInfo::new(
 				strings::commands::diff_lines_stage(&self.key_config),
 				//TODO: only if any modifications are selected
-				true,
+				self.can_stage(),
 				self.focused() && !self.is_stage(),
 			));
 			out.push(CommandInfo::new(
@@ -821,7 +849,7 @@ impl Component for DiffComponent {
 					&self.key_config,
 				),
 				//TODO: only if any modifications are selected
-				true,
+				self.can_stage(),
 				self.focused() && self.is_stage(),
 			));
 		}
@@ -923,6 +951,7 @@ impl Component for DiffComponent {
 					e,
 					self.key_config.keys.diff_stage_lines,
 				) && !self.is_immutable
+					&& self.can_stage()
 				{
 					self.stage_lines();
 					Ok(EventState::Consumed)
@@ -931,12 +960,10 @@ impl Component for DiffComponent {
 					self.key_config.keys.diff_reset_lines,
 				) && !self.is_immutable
 					&& !self.is_stage()
+					&& self.can_stage()
 				{
-					if let Some(diff) = &self.diff {
-						//TODO: reset untracked lines
-						if !diff.untracked {
-							self.reset_lines();
-						}
+					if self.diff.is_some() {
+						self.reset_lines();
 					}
 					Ok(EventState::Consumed)
 				} else if key_match(e, self.key_config.keys.copy) {
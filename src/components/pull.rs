@@ -101,6 +101,7 @@ impl PullComponent {
 			remote: get_default_remote(&self.repo.borrow())?,
 			branch: self.branch.clone(),
 			basic_credential: cred,
+			prune: false,
 		})?;
 
 		Ok(())
@@ -15,11 +15,13 @@ use asyncgit::{
 			extract_username_password, need_username_password,
 			BasicAuthCredential,
 		},
-		get_branch_remote, get_default_remote, RepoPathRef,
+		get_branch_remote, get_default_remote, hooks_pre_push,
+		pre_push_ref, HookResult, RepoPathRef,
 	},
 	AsyncGitNotification, AsyncPush, PushRequest, PushType,
 	RemoteProgress, RemoteProgressState,
 };
+use bytesize::ByteSize;
 use crossbeam_channel::Sender;
 use crossterm::event::Event;
 use ratatui::{
@@ -148,6 +150,24 @@ impl PushComponent {
 			remote
 		};
 
+		let update = pre_push_ref(
+			&self.repo.borrow(),
+			&remote,
+			&self.branch,
+			self.push_type,
+			self.modifier.delete(),
+		)?;
+		let hook_result =
+			hooks_pre_push(&self.repo.borrow(), &remote, &[update])?;
+		if let HookResult::NotOk(e) = hook_result {
+			log::error!("pre-push hook error: {}", e);
+			self.queue.push(InternalEvent::ShowErrorMsg(format!(
+				"pre-push hook error:\n{e}"
+			)));
+			self.hide();
+			return Ok(());
+		}
+
 		self.pending = true;
 		self.progress = None;
 		self.git_push.request(PushRequest {
@@ -202,10 +222,21 @@ impl PushComponent {
 		progress.as_ref().map_or(
 			(strings::PUSH_POPUP_PROGRESS_NONE.into(), 0),
 			|progress| {
-				(
-					Self::progress_state_name(&progress.state),
-					progress.get_progress_percent(),
-				)
+				let state =
+					Self::progress_state_name(&progress.state);
+				let state = if progress.bytes > 0 {
+					format!(
+						"{state} {}",
+						ByteSize::b(
+							u64::try_from(progress.bytes)
+								.unwrap_or_default()
+						)
+					)
+				} else {
+					state
+				};
+
+				(state, progress.get_progress_percent())
 			},
 		)
 	}
@@ -199,6 +199,7 @@ impl Component for SubmodulesListComponent {
 						update_submodule(
 							&self.repo.borrow(),
 							&submodule.name,
+							true,
 						)
 					);
 
@@ -433,7 +434,7 @@ impl SubmodulesListComponent {
 				let span_title_status =
 					Span::styled("Status:", theme.text(false, false));
 				let span_status = Span::styled(
-					format!("{:?}", submodule.status),
+					format!("{:?}", submodule.state()),
 					theme.text(true, false),
 				);
 
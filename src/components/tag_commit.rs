@@ -177,6 +177,7 @@ impl TagCommitComponent {
 				&commit_id,
 				&tag_name,
 				tag_annotation.as_deref(),
+				false,
 			);
 			match result {
 				Ok(_) => {
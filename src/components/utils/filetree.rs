@@ -268,6 +268,7 @@ mod tests {
 			.iter()
 			.map(|a| StatusItem {
 				path: String::from(*a),
+				old_path: None,
 				status: StatusItemType::Modified,
 			})
 			.collect::<Vec<_>>()
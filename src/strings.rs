@@ -307,6 +307,18 @@ pub fn tag_popup_annotation_msg() -> String {
 pub fn stashlist_title(_key_config: &SharedKeyConfig) -> String {
 	"Stashes".to_string()
 }
+pub fn stash_apply_conflicts(paths: &[String]) -> String {
+	format!(
+		"stash applied with conflicts in:\n{}\nresolve them in the status tab",
+		paths.join("\n")
+	)
+}
+pub fn stash_pop_conflicts(paths: &[String]) -> String {
+	format!(
+		"stash applied with conflicts in:\n{}\nthe stash was kept, resolve them in the status tab",
+		paths.join("\n")
+	)
+}
 pub fn help_title(_key_config: &SharedKeyConfig) -> String {
 	"Help: all commands".to_string()
 }
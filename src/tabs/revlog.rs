@@ -219,7 +219,7 @@ impl Revlog {
 
 	fn revert_commit(&self) -> Result<()> {
 		if let Some(c) = self.selected_commit() {
-			sync::revert_commit(&self.repo.borrow(), c)?;
+			sync::revert_commit(&self.repo.borrow(), c, false)?;
 			self.queue.push(InternalEvent::TabSwitchStatus);
 		}
 
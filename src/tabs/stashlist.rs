@@ -65,8 +65,16 @@ impl StashList {
 		if let Some(e) = self.list.selected_entry() {
 			match sync::stash_apply(&self.repo.borrow(), e.id, false)
 			{
-				Ok(()) => {
+				Ok(res) => {
 					self.queue.push(InternalEvent::TabSwitchStatus);
+
+					if !res.conflicted_paths.is_empty() {
+						self.queue.push(InternalEvent::ShowInfoMsg(
+							strings::stash_apply_conflicts(
+								&res.conflicted_paths,
+							),
+						));
+					}
 				}
 				Err(e) => {
 					self.queue.push(InternalEvent::ShowErrorMsg(
@@ -138,7 +146,13 @@ impl StashList {
 	}
 
 	fn pop(&mut self, repo: &RepoPath, id: CommitId) -> Result<()> {
-		sync::stash_pop(repo, id)?;
+		if let sync::StashPopResult::Conflicted(paths) =
+			sync::stash_pop(repo, id)?
+		{
+			self.queue.push(InternalEvent::ShowInfoMsg(
+				strings::stash_pop_conflicts(&paths),
+			));
+		}
 
 		self.list.clear_marked();
 		self.update()?;
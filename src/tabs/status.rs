@@ -19,7 +19,7 @@ use asyncgit::{
 	sync::{
 		self, status::StatusType, RepoPath, RepoPathRef, RepoState,
 	},
-	sync::{BranchCompare, CommitId},
+	sync::{CommitId, TrackingInfo},
 	AsyncBranchesJob, AsyncDiff, AsyncGitNotification, AsyncStatus,
 	DiffParams, DiffType, PushType, StatusItem, StatusParams,
 };
@@ -72,7 +72,7 @@ pub struct Status {
 	git_state: RepoState,
 	git_status_workdir: AsyncStatus,
 	git_status_stage: AsyncStatus,
-	git_branch_state: Option<BranchCompare>,
+	git_branch_state: Option<TrackingInfo>,
 	git_branch_name: cached::BranchName,
 	git_branches: AsyncSingleJob<AsyncBranchesJob>,
 	queue: Queue,
@@ -231,9 +231,16 @@ impl Status {
 						state.ahead, state.behind,
 					)
 				});
+			let upstream = self
+				.git_branch_state
+				.as_ref()
+				.and_then(|state| state.upstream.as_ref())
+				.map_or_else(String::new, |upstream| {
+					format!(" \u{2192} {upstream}")
+				});
 
 			let w = Paragraph::new(format!(
-				"{ahead_behind}{{{branch_name}}}"
+				"{ahead_behind}{{{branch_name}}}{upstream}"
 			))
 			.alignment(Alignment::Right);
 
@@ -617,13 +624,10 @@ impl Status {
 
 	fn branch_compare(&mut self) {
 		self.git_branch_state =
-			self.git_branch_name.last().and_then(|branch| {
-				sync::branch_compare_upstream(
-					&self.repo.borrow(),
-					branch.as_str(),
-				)
+			sync::branch_tracking_info(&self.repo.borrow())
 				.ok()
-			});
+				.flatten()
+				.filter(|state| state.upstream.is_some());
 	}
 
 	fn can_push(&self) -> bool {